@@ -0,0 +1,139 @@
+//! Throughput of the PTY output path behind token scraping: splitting reads
+//! into lines, and `TokenScraper::ingest` with summaries stored by the
+//! writer thread. Run with `cargo bench --bench scraper`.
+
+use std::time::{Duration, Instant};
+
+use aion_lib::bench::{prepare_db, LineSplitter, TokenScraper};
+
+/// Size of one PTY read in the reader thread.
+const READ_SIZE: usize = 4096;
+
+/// Output replayed per scenario.
+const OUTPUT_BYTES: usize = 32 * 1024 * 1024;
+
+const COST_SUMMARY: &str = "Total cost:            $0.5512\r\n\
+Total duration (API):  6m 19.7s\r\n\
+Total duration (wall): 6h 33m 10.2s\r\n\
+Usage by model:\r\n\
+    claude-3-5-haiku:  3.9k input, 276 output, 0 cache read, 0 cache write\r\n\
+       claude-sonnet-4:  24 input, 6.3k output, 659.5k cache read, 58.2k cache write\r\n\
+\r\n";
+
+fn main() {
+    let build_log = output(80, None);
+    let long_lines = output(64 * 1024, None);
+
+    println!("line splitting, {} MiB", OUTPUT_BYTES / (1024 * 1024));
+    for (name, text) in [("80-byte lines", &build_log), ("64 KiB lines", &long_lines)] {
+        for chunk in [READ_SIZE, 64 * 1024] {
+            let elapsed = time(|| {
+                let mut splitter = LineSplitter::default();
+                for piece in chunks(text, chunk) {
+                    std::hint::black_box(splitter.feed(piece));
+                }
+            });
+            let baseline = time(|| {
+                let mut buffer = String::new();
+                for piece in chunks(text, chunk) {
+                    std::hint::black_box(split_reallocating(&mut buffer, piece));
+                }
+            });
+            println!(
+                "  {name}, {} KiB reads: {} (reallocating per line: {})",
+                chunk / 1024,
+                rate(text.len(), elapsed),
+                rate(text.len(), baseline),
+            );
+        }
+    }
+
+    let dir = std::env::temp_dir().join(format!("aion-bench-{}", std::process::id()));
+    std::fs::create_dir_all(&dir).expect("create bench dir");
+
+    println!(
+        "ingest, {} MiB in {READ_SIZE}-byte reads",
+        OUTPUT_BYTES / (1024 * 1024)
+    );
+    for every in [10_000, 100] {
+        let db_path = dir.join(format!("every-{every}.db"));
+        prepare_db(&db_path).expect("prepare bench db");
+        let scraper = TokenScraper::new(db_path, |_| {});
+        let text = output(80, Some(every));
+
+        let started = Instant::now();
+        let queued = chunks(&text, READ_SIZE)
+            .map(|piece| scraper.ingest("bench", piece).summaries)
+            .sum::<usize>();
+        let reader = started.elapsed();
+        scraper.flush().expect("store bench usage");
+        let stored = started.elapsed();
+
+        println!(
+            "  /cost every {every} lines: reader {} ({queued} summaries), stored after {:.0} ms",
+            rate(text.len(), reader),
+            stored.as_secs_f64() * 1000.0,
+        );
+    }
+
+    let _ = std::fs::remove_dir_all(&dir);
+}
+
+/// Roughly `OUTPUT_BYTES` of `width`-byte lines, with a `/cost` summary
+/// after every `summary_every` lines.
+fn output(width: usize, summary_every: Option<usize>) -> String {
+    let line = format!("{}\r\n", "x".repeat(width - 2));
+    let mut text = String::with_capacity(OUTPUT_BYTES + COST_SUMMARY.len());
+    let mut count = 0;
+    while text.len() < OUTPUT_BYTES {
+        text.push_str(&line);
+        count += 1;
+        if summary_every.is_some_and(|every| count % every == 0) {
+            text.push_str(COST_SUMMARY);
+        }
+    }
+    text
+}
+
+/// `text` in pieces of about `size` bytes, cut on char boundaries.
+fn chunks(text: &str, size: usize) -> impl Iterator<Item = &str> {
+    let mut rest = text;
+    std::iter::from_fn(move || {
+        if rest.is_empty() {
+            return None;
+        }
+        let mut end = size.min(rest.len());
+        while !rest.is_char_boundary(end) {
+            end += 1;
+        }
+        let (piece, tail) = rest.split_at(end);
+        rest = tail;
+        Some(piece)
+    })
+}
+
+/// The splitter the scraper used before `LineSplitter`, copying the rest of
+/// the buffer after every line.
+fn split_reallocating(buffer: &mut String, text: &str) -> Vec<String> {
+    buffer.push_str(text);
+    let mut lines = Vec::new();
+    while let Some(pos) = buffer.find('\n') {
+        lines.push(buffer[..pos].trim_end_matches('\r').to_string());
+        *buffer = buffer[pos + 1..].to_string();
+    }
+    lines
+}
+
+fn time(mut run: impl FnMut()) -> Duration {
+    run();
+    let started = Instant::now();
+    run();
+    started.elapsed()
+}
+
+fn rate(bytes: usize, elapsed: Duration) -> String {
+    format!(
+        "{:.0} MiB/s",
+        bytes as f64 / (1024.0 * 1024.0) / elapsed.as_secs_f64()
+    )
+}
@@ -0,0 +1,13 @@
+CREATE TABLE IF NOT EXISTS session_worktrees (
+    session_id    TEXT PRIMARY KEY,
+    name          TEXT NOT NULL,
+    repo_path     TEXT NOT NULL,
+    worktree_path TEXT NOT NULL,
+    branch        TEXT NOT NULL,
+    base_oid      TEXT NOT NULL,
+    status        TEXT NOT NULL DEFAULT 'active',
+    created_at    DATETIME DEFAULT CURRENT_TIMESTAMP,
+    ended_at      DATETIME
+);
+
+CREATE INDEX IF NOT EXISTS idx_worktrees_repo ON session_worktrees(repo_path);
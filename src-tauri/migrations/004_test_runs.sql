@@ -0,0 +1,16 @@
+CREATE TABLE IF NOT EXISTS test_runs (
+    id           INTEGER PRIMARY KEY AUTOINCREMENT,
+    session_id   TEXT NOT NULL,
+    project      TEXT NOT NULL,
+    git_head     TEXT,
+    runner       TEXT NOT NULL,
+    passed       INTEGER NOT NULL DEFAULT 0,
+    failed       INTEGER NOT NULL DEFAULT 0,
+    skipped      INTEGER NOT NULL DEFAULT 0,
+    failures     TEXT NOT NULL DEFAULT '[]',
+    duration_s   REAL,
+    captured_at  DATETIME DEFAULT CURRENT_TIMESTAMP
+);
+
+CREATE INDEX IF NOT EXISTS idx_test_runs_project ON test_runs(project, captured_at);
+CREATE INDEX IF NOT EXISTS idx_test_runs_session ON test_runs(session_id);
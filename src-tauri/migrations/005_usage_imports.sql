@@ -0,0 +1,16 @@
+ALTER TABLE token_usage ADD COLUMN source TEXT NOT NULL DEFAULT 'scraped';
+ALTER TABLE token_usage ADD COLUMN model TEXT;
+ALTER TABLE token_usage ADD COLUMN project TEXT;
+ALTER TABLE token_usage ADD COLUMN cache_read_tokens INTEGER;
+ALTER TABLE token_usage ADD COLUMN cache_creation_tokens INTEGER;
+ALTER TABLE token_usage ADD COLUMN message_id TEXT;
+
+CREATE UNIQUE INDEX IF NOT EXISTS idx_usage_message ON token_usage(agent, message_id) WHERE message_id IS NOT NULL;
+CREATE INDEX IF NOT EXISTS idx_usage_project ON token_usage(project);
+
+CREATE TABLE IF NOT EXISTS usage_import_files (
+    path        TEXT PRIMARY KEY,
+    agent       TEXT NOT NULL,
+    position    INTEGER NOT NULL DEFAULT 0,
+    scanned_at  DATETIME DEFAULT CURRENT_TIMESTAMP
+);
@@ -0,0 +1,3 @@
+ALTER TABLE usage_import_files ADD COLUMN context TEXT;
+ALTER TABLE usage_import_files ADD COLUMN errors INTEGER NOT NULL DEFAULT 0;
+ALTER TABLE usage_import_files ADD COLUMN last_error TEXT;
@@ -0,0 +1,13 @@
+ALTER TABLE token_usage ADD COLUMN estimated INTEGER NOT NULL DEFAULT 0;
+
+CREATE TABLE IF NOT EXISTS model_prices (
+    id             INTEGER PRIMARY KEY AUTOINCREMENT,
+    model          TEXT NOT NULL,
+    input          REAL NOT NULL DEFAULT 0,
+    output         REAL NOT NULL DEFAULT 0,
+    cache_read     REAL NOT NULL DEFAULT 0,
+    cache_write    REAL NOT NULL DEFAULT 0,
+    effective_from TEXT NOT NULL,
+    created_at     DATETIME DEFAULT CURRENT_TIMESTAMP,
+    UNIQUE(model, effective_from)
+);
@@ -0,0 +1,6 @@
+ALTER TABLE token_usage ADD COLUMN cumulative_cost_usd REAL;
+ALTER TABLE token_usage ADD COLUMN cumulative_tokens_in INTEGER;
+ALTER TABLE token_usage ADD COLUMN cumulative_tokens_out INTEGER;
+ALTER TABLE token_usage ADD COLUMN cumulative_tokens_total INTEGER;
+ALTER TABLE token_usage ADD COLUMN cumulative_cache_read_tokens INTEGER;
+ALTER TABLE token_usage ADD COLUMN cumulative_cache_creation_tokens INTEGER;
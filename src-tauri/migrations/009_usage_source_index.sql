@@ -0,0 +1 @@
+CREATE INDEX IF NOT EXISTS idx_usage_agent_source ON token_usage(agent, source, captured_at);
@@ -0,0 +1,302 @@
+use std::path::Path;
+
+use rusqlite::{params, Connection, OptionalExtension};
+use serde::Serialize;
+use tauri::State;
+
+use crate::pty::scrollback::ScrollbackPage;
+use crate::state::AppState;
+
+/// Lines shown before a bookmark when jumping to it.
+const BOOKMARK_CONTEXT_LINES: u64 = 20;
+
+#[derive(Debug, Clone, Serialize)]
+pub struct SessionAnnotations {
+    pub session_id: String,
+    pub notes: String,
+    pub tags: Vec<String>,
+    pub bookmarks: Vec<SessionBookmark>,
+    pub updated_at: Option<String>,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct SessionBookmark {
+    pub id: i64,
+    pub session_id: String,
+    pub label: String,
+    /// Absolute output line in the session's scrollback.
+    pub line: i64,
+    pub created_at: String,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct TagCount {
+    pub tag: String,
+    pub sessions: i64,
+}
+
+#[tauri::command]
+pub async fn session_annotations(
+    state: State<'_, AppState>,
+    session_id: String,
+) -> Result<SessionAnnotations, String> {
+    let conn = open_conn(state.db_path.as_ref())?;
+
+    let (notes, updated_at) = conn
+        .query_row(
+            "SELECT notes, updated_at FROM session_meta WHERE session_id = ?1",
+            params![session_id],
+            |row| Ok((row.get::<_, String>(0)?, row.get::<_, Option<String>>(1)?)),
+        )
+        .optional()
+        .map_err(|err| format!("failed to query session notes: {err}"))?
+        .unwrap_or_default();
+
+    Ok(SessionAnnotations {
+        tags: session_tags(&conn, &session_id)?,
+        bookmarks: session_bookmarks(&conn, &session_id)?,
+        session_id,
+        notes,
+        updated_at,
+    })
+}
+
+#[tauri::command]
+pub async fn session_notes_set(
+    state: State<'_, AppState>,
+    session_id: String,
+    notes: String,
+) -> Result<(), String> {
+    let conn = open_conn(state.db_path.as_ref())?;
+
+    conn.execute(
+        "INSERT INTO session_meta (session_id, notes) VALUES (?1, ?2) ON CONFLICT(session_id) DO UPDATE SET notes = excluded.notes, updated_at = CURRENT_TIMESTAMP",
+        params![session_id, notes],
+    )
+    .map_err(|err| format!("failed to save session notes: {err}"))?;
+
+    Ok(())
+}
+
+#[tauri::command]
+pub async fn session_tag_add(
+    state: State<'_, AppState>,
+    session_id: String,
+    tag: String,
+) -> Result<Vec<String>, String> {
+    let tag = normalize_tag(&tag)?;
+    let conn = open_conn(state.db_path.as_ref())?;
+
+    conn.execute(
+        "INSERT OR IGNORE INTO session_tags (session_id, tag) VALUES (?1, ?2)",
+        params![session_id, tag],
+    )
+    .map_err(|err| format!("failed to add session tag: {err}"))?;
+
+    session_tags(&conn, &session_id)
+}
+
+#[tauri::command]
+pub async fn session_tag_remove(
+    state: State<'_, AppState>,
+    session_id: String,
+    tag: String,
+) -> Result<Vec<String>, String> {
+    let tag = normalize_tag(&tag)?;
+    let conn = open_conn(state.db_path.as_ref())?;
+
+    conn.execute(
+        "DELETE FROM session_tags WHERE session_id = ?1 AND tag = ?2",
+        params![session_id, tag],
+    )
+    .map_err(|err| format!("failed to remove session tag: {err}"))?;
+
+    session_tags(&conn, &session_id)
+}
+
+#[tauri::command]
+pub async fn session_tags_list(state: State<'_, AppState>) -> Result<Vec<TagCount>, String> {
+    let conn = open_conn(state.db_path.as_ref())?;
+
+    let mut statement = conn
+        .prepare("SELECT tag, COUNT(*) FROM session_tags GROUP BY tag ORDER BY tag")
+        .map_err(|err| format!("failed to prepare tag query: {err}"))?;
+
+    let tags = statement
+        .query_map([], |row| {
+            Ok(TagCount {
+                tag: row.get(0)?,
+                sessions: row.get(1)?,
+            })
+        })
+        .map_err(|err| format!("failed to execute tag query: {err}"))?
+        .collect::<Result<Vec<_>, _>>()
+        .map_err(|err| format!("failed to map tag rows: {err}"))?;
+
+    Ok(tags)
+}
+
+/// Bookmarks the given output line, or the end of the session's current
+/// output when `line` is omitted.
+#[tauri::command]
+pub async fn session_bookmark_add(
+    state: State<'_, AppState>,
+    session_id: String,
+    label: Option<String>,
+    line: Option<u64>,
+) -> Result<SessionBookmark, String> {
+    let line = match line {
+        Some(line) => line,
+        None => state
+            .pty_manager
+            .scrollback()
+            .get(&session_id)
+            .map(|scrollback| scrollback.total_lines())
+            .ok_or_else(|| format!("no stored output for session: {session_id}"))?,
+    };
+    let line = i64::try_from(line).map_err(|_| format!("bookmark line out of range: {line}"))?;
+
+    let conn = open_conn(state.db_path.as_ref())?;
+    conn.execute(
+        "INSERT INTO session_bookmarks (session_id, label, line) VALUES (?1, ?2, ?3)",
+        params![session_id, label.unwrap_or_default(), line],
+    )
+    .map_err(|err| format!("failed to add bookmark: {err}"))?;
+
+    find_bookmark(&conn, conn.last_insert_rowid())
+}
+
+#[tauri::command]
+pub async fn session_bookmark_update(
+    state: State<'_, AppState>,
+    id: i64,
+    label: String,
+) -> Result<SessionBookmark, String> {
+    let conn = open_conn(state.db_path.as_ref())?;
+
+    conn.execute(
+        "UPDATE session_bookmarks SET label = ?2 WHERE id = ?1",
+        params![id, label],
+    )
+    .map_err(|err| format!("failed to update bookmark: {err}"))?;
+
+    find_bookmark(&conn, id)
+}
+
+#[tauri::command]
+pub async fn session_bookmark_remove(state: State<'_, AppState>, id: i64) -> Result<(), String> {
+    let conn = open_conn(state.db_path.as_ref())?;
+
+    conn.execute("DELETE FROM session_bookmarks WHERE id = ?1", params![id])
+        .map_err(|err| format!("failed to remove bookmark: {err}"))?;
+
+    Ok(())
+}
+
+/// Returns the scrollback page around a bookmark, with the bookmarked line
+/// `BOOKMARK_CONTEXT_LINES` into the page when there is enough output above.
+#[tauri::command]
+pub async fn session_bookmark_jump(
+    state: State<'_, AppState>,
+    id: i64,
+    count: Option<u64>,
+) -> Result<ScrollbackPage, String> {
+    let bookmark = {
+        let conn = open_conn(state.db_path.as_ref())?;
+        find_bookmark(&conn, id)?
+    };
+
+    let scrollback = state
+        .pty_manager
+        .scrollback()
+        .get(&bookmark.session_id)
+        .ok_or_else(|| format!("no stored output for session: {}", bookmark.session_id))?;
+
+    let line = u64::try_from(bookmark.line).unwrap_or_default();
+    if line < scrollback.first_line {
+        return Err(format!("bookmarked line {line} is no longer in scrollback"));
+    }
+
+    Ok(scrollback.page(line.saturating_sub(BOOKMARK_CONTEXT_LINES), count.unwrap_or(200)))
+}
+
+/// Session ids carrying `tag`, for filtering session lists.
+pub fn sessions_with_tag(db_path: &Path, tag: &str) -> Result<Vec<String>, String> {
+    let tag = normalize_tag(tag)?;
+    let conn = open_conn(db_path)?;
+
+    let mut statement = conn
+        .prepare("SELECT session_id FROM session_tags WHERE tag = ?1")
+        .map_err(|err| format!("failed to prepare tagged session query: {err}"))?;
+
+    let session_ids = statement
+        .query_map(params![tag], |row| row.get::<_, String>(0))
+        .map_err(|err| format!("failed to execute tagged session query: {err}"))?
+        .collect::<Result<Vec<_>, _>>()
+        .map_err(|err| format!("failed to map tagged session rows: {err}"))?;
+
+    Ok(session_ids)
+}
+
+pub(crate) fn normalize_tag(tag: &str) -> Result<String, String> {
+    let tag = tag.trim().to_lowercase();
+    if tag.is_empty() {
+        return Err("tag must not be empty".to_string());
+    }
+    Ok(tag)
+}
+
+fn session_tags(conn: &Connection, session_id: &str) -> Result<Vec<String>, String> {
+    let mut statement = conn
+        .prepare("SELECT tag FROM session_tags WHERE session_id = ?1 ORDER BY tag")
+        .map_err(|err| format!("failed to prepare session tag query: {err}"))?;
+
+    let tags = statement
+        .query_map(params![session_id], |row| row.get::<_, String>(0))
+        .map_err(|err| format!("failed to execute session tag query: {err}"))?
+        .collect::<Result<Vec<_>, _>>()
+        .map_err(|err| format!("failed to map session tag rows: {err}"))?;
+
+    Ok(tags)
+}
+
+fn session_bookmarks(conn: &Connection, session_id: &str) -> Result<Vec<SessionBookmark>, String> {
+    let mut statement = conn
+        .prepare(
+            "SELECT id, session_id, label, line, created_at FROM session_bookmarks WHERE session_id = ?1 ORDER BY line, id",
+        )
+        .map_err(|err| format!("failed to prepare bookmark query: {err}"))?;
+
+    let bookmarks = statement
+        .query_map(params![session_id], map_bookmark)
+        .map_err(|err| format!("failed to execute bookmark query: {err}"))?
+        .collect::<Result<Vec<_>, _>>()
+        .map_err(|err| format!("failed to map bookmark rows: {err}"))?;
+
+    Ok(bookmarks)
+}
+
+fn find_bookmark(conn: &Connection, id: i64) -> Result<SessionBookmark, String> {
+    conn.query_row(
+        "SELECT id, session_id, label, line, created_at FROM session_bookmarks WHERE id = ?1",
+        params![id],
+        map_bookmark,
+    )
+    .optional()
+    .map_err(|err| format!("failed to query bookmark: {err}"))?
+    .ok_or_else(|| format!("bookmark not found: {id}"))
+}
+
+fn map_bookmark(row: &rusqlite::Row<'_>) -> rusqlite::Result<SessionBookmark> {
+    Ok(SessionBookmark {
+        id: row.get(0)?,
+        session_id: row.get(1)?,
+        label: row.get(2)?,
+        line: row.get(3)?,
+        created_at: row.get(4)?,
+    })
+}
+
+fn open_conn(db_path: &Path) -> Result<Connection, String> {
+    Connection::open(db_path).map_err(|err| format!("failed to open db: {err}"))
+}
@@ -0,0 +1,146 @@
+use std::path::Path;
+use std::process::{Command, Stdio};
+
+use serde::{Deserialize, Serialize};
+use tauri::State;
+
+use crate::commands::settings;
+use crate::state::AppState;
+
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase", default)]
+pub struct EditorConfig {
+    /// Editor command, with optional leading arguments. Falls back to
+    /// `$VISUAL`, then `$EDITOR`, then `code`.
+    pub command: Option<String>,
+    /// Argument template using `{file}`, `{line}` and `{col}`. When empty,
+    /// the template for the editor is picked from its name.
+    pub args: Vec<String>,
+    /// Run the editor inside a terminal session instead of launching it.
+    /// Detected from the editor name when unset.
+    pub terminal: Option<bool>,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct EditorLaunch {
+    pub program: String,
+    pub args: Vec<String>,
+    /// Terminal editors are not launched; the frontend runs `program` and
+    /// `args` in a session.
+    pub terminal: bool,
+    pub launched: bool,
+}
+
+#[tauri::command]
+pub async fn open_in_editor(
+    state: State<'_, AppState>,
+    path: String,
+    line: Option<u32>,
+    col: Option<u32>,
+) -> Result<EditorLaunch, String> {
+    if !Path::new(&path).exists() {
+        return Err(format!("path does not exist: {path}"));
+    }
+
+    let config = settings::load_shell_config_from_path(state.shell_config_path.as_ref())
+        .map(|config| config.editor)
+        .unwrap_or_default();
+    let mut launch = editor_launch(&config, &path, line, col)?;
+    if launch.terminal {
+        return Ok(launch);
+    }
+
+    let mut child = Command::new(&launch.program)
+        .args(&launch.args)
+        .stdin(Stdio::null())
+        .stdout(Stdio::null())
+        .stderr(Stdio::null())
+        .spawn()
+        .map_err(|err| format!("failed to launch editor '{}': {err}", launch.program))?;
+    // Reap the editor (or its launcher) whenever it exits.
+    std::thread::spawn(move || {
+        let _ = child.wait();
+    });
+
+    launch.launched = true;
+    Ok(launch)
+}
+
+fn editor_launch(
+    config: &EditorConfig,
+    path: &str,
+    line: Option<u32>,
+    col: Option<u32>,
+) -> Result<EditorLaunch, String> {
+    let command = config
+        .command
+        .clone()
+        .filter(|command| !command.trim().is_empty())
+        .or_else(|| env_editor("VISUAL"))
+        .or_else(|| env_editor("EDITOR"))
+        .unwrap_or_else(|| "code".to_string());
+
+    let mut words = command.split_whitespace().map(str::to_string);
+    let program = words
+        .next()
+        .ok_or_else(|| "editor command is empty".to_string())?;
+    let leading = words.collect::<Vec<_>>();
+
+    let name = Path::new(&program)
+        .file_stem()
+        .and_then(|name| name.to_str())
+        .unwrap_or(&program)
+        .to_ascii_lowercase();
+    let (template, terminal) = template_for(&name);
+    let template = if config.args.is_empty() {
+        template.iter().map(|arg| arg.to_string()).collect()
+    } else {
+        config.args.clone()
+    };
+
+    let line = line.unwrap_or(1).max(1).to_string();
+    let col = col.unwrap_or(1).max(1).to_string();
+    let args = leading
+        .into_iter()
+        .chain(template.iter().map(|arg| {
+            arg.replace("{file}", path)
+                .replace("{line}", &line)
+                .replace("{col}", &col)
+        }))
+        .collect();
+
+    Ok(EditorLaunch {
+        program,
+        args,
+        terminal: config.terminal.unwrap_or(terminal),
+        launched: false,
+    })
+}
+
+/// Argument template for a known editor and whether it needs a terminal.
+fn template_for(name: &str) -> (&'static [&'static str], bool) {
+    match name {
+        "code" | "code-insiders" | "codium" | "vscodium" | "cursor" | "windsurf" => {
+            (&["--goto", "{file}:{line}:{col}"], false)
+        }
+        "idea" | "idea64" | "pycharm" | "webstorm" | "goland" | "clion" | "rustrover"
+        | "phpstorm" | "rubymine" | "rider" | "studio" => {
+            (&["--line", "{line}", "--column", "{col}", "{file}"], false)
+        }
+        "subl" | "sublime_text" | "zed" | "zeditor" => (&["{file}:{line}:{col}"], false),
+        "emacs" | "emacsclient" => (&["+{line}:{col}", "{file}"], false),
+        "mate" => (&["--line", "{line}", "{file}"], false),
+        "gedit" | "kate" => (&["+{line}", "{file}"], false),
+        "vim" | "nvim" | "vi" => (&["+call cursor({line},{col})", "{file}"], true),
+        "nano" => (&["+{line},{col}", "{file}"], true),
+        "kak" => (&["+{line}:{col}", "{file}"], true),
+        "hx" | "helix" | "micro" => (&["{file}:{line}:{col}"], true),
+        _ => (&["{file}"], false),
+    }
+}
+
+fn env_editor(key: &str) -> Option<String> {
+    std::env::var(key)
+        .ok()
+        .filter(|value| !value.trim().is_empty())
+}
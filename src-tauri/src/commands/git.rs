@@ -3,9 +3,12 @@ use std::path::Path;
 use serde::Deserialize;
 use tauri::{AppHandle, State};
 
+use crate::git::attribution::SessionChanges;
+use crate::git::checkpoint::{self, CheckpointInfo};
 use crate::git::porcelain::{
     self, BranchList, CherryPickResult, CommitEntry, CommitInfo, DiffResult, FetchResult,
-    FileStatus, MergeResult, PullResult, PushResult, StashAction, StashResult, TagResult,
+    FileDiff, FileStatus, MergeResult, PullResult, PushResult, StashAction, StashResult,
+    TagResult,
 };
 use crate::git::watcher;
 use crate::state::AppState;
@@ -130,37 +133,68 @@ pub async fn git_watch_start(
     path: String,
 ) -> Result<String, String> {
     let root = porcelain::discover_repo_root(&path)?;
+    ensure_watching(&state, app_handle, &root)?;
+    state
+        .git_watch_requests
+        .lock()
+        .map_err(|_| "failed to lock watch requests".to_string())?
+        .insert(root.clone());
+    Ok(root)
+}
+
+#[tauri::command]
+pub async fn git_watch_stop(state: State<'_, AppState>, path: String) -> Result<(), String> {
+    let root = porcelain::discover_repo_root(&path)?;
+    state
+        .git_watch_requests
+        .lock()
+        .map_err(|_| "failed to lock watch requests".to_string())?
+        .remove(&root);
+    release_watcher(&state, &root)
+}
 
+/// Stops the repo's watcher unless the frontend asked for it or a session
+/// is still attributing changes in the repo.
+pub fn release_watcher(state: &AppState, root: &str) -> Result<(), String> {
+    if state.change_tracker.is_tracking(root) {
+        return Ok(());
+    }
+    if state
+        .git_watch_requests
+        .lock()
+        .map_err(|_| "failed to lock watch requests".to_string())?
+        .contains(root)
     {
-        let guard = state
-            .git_watchers
-            .lock()
-            .map_err(|_| "failed to lock watcher map".to_string())?;
-        if guard.contains_key(&root) {
-            return Ok(root);
-        }
+        return Ok(());
     }
 
-    let watcher = watcher::start_watching(Path::new(&root), app_handle)?;
-
     let mut guard = state
         .git_watchers
         .lock()
-        .map_err(|_| "failed to lock watcher map for insert".to_string())?;
-    guard.insert(root.clone(), watcher);
+        .map_err(|_| "failed to lock watcher map for remove".to_string())?;
+    guard.remove(root);
 
-    Ok(root)
+    Ok(())
 }
 
-#[tauri::command]
-pub async fn git_watch_stop(state: State<'_, AppState>, path: String) -> Result<(), String> {
-    let root = porcelain::discover_repo_root(&path)?;
+pub fn ensure_watching(state: &AppState, app_handle: AppHandle, root: &str) -> Result<(), String> {
+    {
+        let guard = state
+            .git_watchers
+            .lock()
+            .map_err(|_| "failed to lock watcher map".to_string())?;
+        if guard.contains_key(root) {
+            return Ok(());
+        }
+    }
+
+    let watcher = watcher::start_watching(Path::new(root), app_handle, state.change_tracker.clone())?;
 
     let mut guard = state
         .git_watchers
         .lock()
-        .map_err(|_| "failed to lock watcher map for remove".to_string())?;
-    guard.remove(&root);
+        .map_err(|_| "failed to lock watcher map for insert".to_string())?;
+    guard.insert(root.to_string(), watcher);
 
     Ok(())
 }
@@ -194,3 +228,47 @@ pub async fn git_stash(
 ) -> Result<StashResult, String> {
     porcelain::stash(&path, action.into(), message, index)
 }
+
+#[tauri::command]
+pub async fn git_checkpoint_create(
+    path: String,
+    session_id: String,
+    label: Option<String>,
+) -> Result<Option<CheckpointInfo>, String> {
+    let label = label.unwrap_or_else(|| "manual".to_string());
+    checkpoint::create(&path, &session_id, &label, false)
+}
+
+#[tauri::command]
+pub async fn git_checkpoint_list(
+    path: String,
+    session_id: String,
+) -> Result<Vec<CheckpointInfo>, String> {
+    checkpoint::list(&path, &session_id)
+}
+
+#[tauri::command]
+pub async fn git_checkpoint_diff(
+    path: String,
+    session_id: String,
+    index: u32,
+) -> Result<Vec<FileDiff>, String> {
+    checkpoint::diff(&path, &session_id, index)
+}
+
+#[tauri::command]
+pub async fn git_checkpoint_restore(
+    path: String,
+    session_id: String,
+    index: u32,
+) -> Result<Option<CheckpointInfo>, String> {
+    checkpoint::restore(&path, &session_id, index)
+}
+
+#[tauri::command]
+pub async fn session_changes(
+    state: State<'_, AppState>,
+    session_id: String,
+) -> Result<SessionChanges, String> {
+    state.change_tracker.changes(&session_id)
+}
@@ -1,4 +1,9 @@
+pub mod annotations;
+pub mod editor;
 pub mod git;
+pub mod pricing;
 pub mod pty;
 pub mod settings;
+pub mod test_runs;
 pub mod tokens;
+pub mod worktree;
@@ -0,0 +1,97 @@
+use std::path::Path;
+
+use rusqlite::{params, Connection};
+use tauri::State;
+
+use crate::state::AppState;
+use crate::usage::pricing::{self, ModelPrice, RecomputeSummary};
+
+#[tauri::command]
+pub async fn pricing_list(state: State<'_, AppState>) -> Result<Vec<ModelPrice>, String> {
+    let conn = open_conn(state.db_path.as_ref())?;
+    pricing::list_prices(&conn)
+}
+
+/// Saves a price: `id` 0 adds one (replacing the model's price for the same
+/// date), any other `id` edits that price. Returns the updated table; costs
+/// are not recomputed until `usage_recompute_costs`.
+#[tauri::command]
+pub async fn pricing_set(
+    state: State<'_, AppState>,
+    price: ModelPrice,
+) -> Result<Vec<ModelPrice>, String> {
+    let model = price.model.trim().to_string();
+    if model.is_empty() {
+        return Err("model must not be empty".to_string());
+    }
+    if [
+        price.input,
+        price.output,
+        price.cache_read,
+        price.cache_write,
+    ]
+    .iter()
+    .any(|value| !value.is_finite() || *value < 0.0)
+    {
+        return Err("prices must be zero or more".to_string());
+    }
+    chrono::NaiveDate::parse_from_str(&price.effective_from, "%Y-%m-%d")
+        .map_err(|err| format!("invalid effective date '{}': {err}", price.effective_from))?;
+
+    let conn = open_conn(state.db_path.as_ref())?;
+    if price.id > 0 {
+        conn.execute(
+            "UPDATE model_prices SET model = ?1, input = ?2, output = ?3, cache_read = ?4, cache_write = ?5, effective_from = ?6 WHERE id = ?7",
+            params![
+                model,
+                price.input,
+                price.output,
+                price.cache_read,
+                price.cache_write,
+                price.effective_from,
+                price.id,
+            ],
+        )
+    } else {
+        conn.execute(
+            "INSERT INTO model_prices (model, input, output, cache_read, cache_write, effective_from) VALUES (?1, ?2, ?3, ?4, ?5, ?6)
+             ON CONFLICT(model, effective_from) DO UPDATE SET input = excluded.input, output = excluded.output, cache_read = excluded.cache_read, cache_write = excluded.cache_write",
+            params![
+                model,
+                price.input,
+                price.output,
+                price.cache_read,
+                price.cache_write,
+                price.effective_from,
+            ],
+        )
+    }
+    .map_err(|err| format!("failed to save model price: {err}"))?;
+
+    pricing::list_prices(&conn)
+}
+
+#[tauri::command]
+pub async fn pricing_remove(state: State<'_, AppState>, id: i64) -> Result<(), String> {
+    let conn = open_conn(state.db_path.as_ref())?;
+    conn.execute("DELETE FROM model_prices WHERE id = ?1", params![id])
+        .map_err(|err| format!("failed to remove model price: {err}"))?;
+    Ok(())
+}
+
+/// Prices every row without a reported cost again with the current table.
+#[tauri::command]
+pub async fn usage_recompute_costs(state: State<'_, AppState>) -> Result<RecomputeSummary, String> {
+    let mut conn = open_conn(state.db_path.as_ref())?;
+    let tx = conn
+        .transaction()
+        .map_err(|err| format!("failed to start cost recompute: {err}"))?;
+    let summary = pricing::estimate_costs(&tx, true)?;
+    tx.commit()
+        .map_err(|err| format!("failed to commit cost recompute: {err}"))?;
+    Ok(summary)
+}
+
+fn open_conn(db_path: &Path) -> Result<Connection, String> {
+    Connection::open(db_path).map_err(|err| format!("failed to open db: {err}"))
+}
@@ -1,34 +1,94 @@
 use std::collections::HashMap;
 use std::io::{Read, Write};
+use std::path::Path;
+use std::time::Duration;
 
 use chrono::Utc;
 use portable_pty::{CommandBuilder, NativePtySystem, PtySize, PtySystem};
 use serde::Serialize;
-use tauri::{AppHandle, Emitter, State};
-
-use crate::commands::settings;
+use tauri::{AppHandle, Emitter, Manager, State};
+
+use crate::commands::{
+    annotations, git as git_commands, settings, test_runs, tokens, worktree as worktree_commands,
+};
+use crate::git::worktree::{self, WorktreeInfo};
+use crate::git::{checkpoint, porcelain};
+use crate::pty::diagnostics::{Diagnostic, DiagnosticExtractor};
+use crate::pty::encoding::{self, StreamDecoder};
+use crate::pty::escapes::{EscapeTracker, TerminalEvent};
+use crate::pty::logging::{self, SessionLog};
+use crate::pty::limits::{self, SessionLimits, LIMIT_MAX_OUTPUT_BYTES};
+use crate::pty::links::{self, PathLink};
+use crate::pty::ports::{self, ListeningPort, PORT_SCAN_INTERVAL};
+use crate::pty::resources::{ResourceUsage, ThresholdCrossing, ThresholdWatch, TreeSampler, SAMPLE_INTERVAL};
+use crate::pty::sandbox::{self, SandboxProfile};
+use crate::pty::secrets;
+use crate::pty::scrollback::{Scrollback, ScrollbackPage};
 use crate::pty::session::{PtySession, SessionInfo};
+use crate::pty::terminal;
+use crate::pty::test_runs::{TestRun, TestRunExtractor};
+use crate::pty::transcript;
 use crate::state::AppState;
 
 #[derive(Debug, Clone, Serialize)]
 struct PtyDataPayload {
     session_id: String,
     data: Vec<u8>,
+    /// Read while the terminal had echo off (a password prompt). Stores
+    /// that persist output must skip these chunks.
+    secret: bool,
 }
 
+/// How long a session gets after SIGHUP before it is force-killed.
+const KILL_GRACE: Duration = Duration::from_secs(5);
+
 #[derive(Debug, Clone, Serialize)]
 struct PtyExitPayload {
     session_id: String,
     code: i32,
+    limit: Option<String>,
+}
+
+#[derive(Debug, Clone, Serialize)]
+struct PtyResourceThresholdPayload {
+    session_id: String,
+    #[serde(flatten)]
+    crossing: ThresholdCrossing,
+}
+
+#[derive(Debug, Clone, Serialize)]
+struct PtyPortPayload {
+    session_id: String,
+    #[serde(flatten)]
+    port: ListeningPort,
+}
+
+#[derive(Debug, Clone, Serialize)]
+struct PtyTitlePayload {
+    session_id: String,
+    title: String,
 }
 
 #[derive(Debug, Clone, Serialize)]
-struct TokenCapturedPayload {
+struct PtyBellPayload {
     session_id: String,
-    inserts: usize,
+}
+
+#[derive(Debug, Clone, Serialize)]
+struct PtyNotifyPayload {
+    session_id: String,
+    title: Option<String>,
+    body: String,
+}
+
+#[derive(Debug, Clone, Serialize)]
+struct PtyDiagnosticsPayload {
+    session_id: String,
+    diagnostics: Vec<Diagnostic>,
 }
 
 #[tauri::command]
+#[allow(clippy::too_many_arguments)]
 pub async fn pty_spawn(
     state: State<'_, AppState>,
     app_handle: AppHandle,
@@ -38,12 +98,25 @@ pub async fn pty_spawn(
     env: Option<HashMap<String, String>>,
     cols: u16,
     rows: u16,
+    worktree: Option<bool>,
+    checkpoint_interval_s: Option<u64>,
+    sandbox: Option<SandboxProfile>,
+    limits: Option<SessionLimits>,
+    encoding: Option<String>,
+    log_output: Option<bool>,
 ) -> Result<(), String> {
     let shell_config = settings::load_shell_config_from_path(state.shell_config_path.as_ref())
         .unwrap_or_else(|_| settings::ShellConfig::default());
     let shell_info = settings::resolve_shell_with_config(&shell_config, shell)
         .map_err(|err| format!("failed to resolve shell for spawn: {err}"))?;
 
+    let session_encoding = encoding::resolve(encoding.as_deref().or(shell_config.encoding.as_deref()))?;
+
+    let (launch_program, launch_args) = match &limits {
+        Some(limits) => limits::wrap_rlimits(limits, &shell_info.path, &shell_info.args)?,
+        None => (shell_info.path.clone(), shell_info.args.clone()),
+    };
+
     let mut merged_env = shell_config.default_env;
     if let Some(extra_env) = env {
         merged_env.extend(extra_env);
@@ -61,35 +134,114 @@ pub async fn pty_spawn(
         })
         .map_err(|err| format!("failed to open pty pair: {err}"))?;
 
-    let mut command = CommandBuilder::new(&shell_info.path);
-    for arg in shell_info.args {
-        command.arg(arg);
-    }
+    // Isolated sessions get their own worktree on a fresh branch so parallel
+    // agents never share a checkout.
+    let worktree_info = if worktree.unwrap_or(false) {
+        Some(worktree::create(
+            &resolved_cwd,
+            state.worktrees_dir.as_ref(),
+            &session_id,
+        )?)
+    } else {
+        None
+    };
+
+    let resolved_cwd = worktree_info
+        .as_ref()
+        .map(|info| info.worktree_path.clone())
+        .unwrap_or(resolved_cwd);
+
+    let sandbox_command = match sandbox
+        .as_ref()
+        .map(|profile| sandbox::wrap(profile, &launch_program, &launch_args, &resolved_cwd))
+        .transpose()
+    {
+        Ok(sandbox_command) => sandbox_command,
+        Err(err) => {
+            abandon_spawn(&state, &session_id, worktree_info.as_ref());
+            return Err(err);
+        }
+    };
+
+    let mut command = match &sandbox_command {
+        Some(wrapped) => {
+            let mut command = CommandBuilder::new(&wrapped.program);
+            for arg in &wrapped.args {
+                command.arg(arg);
+            }
+            command
+        }
+        None => {
+            let mut command = CommandBuilder::new(&launch_program);
+            for arg in &launch_args {
+                command.arg(arg);
+            }
+            command
+        }
+    };
     command.cwd(&resolved_cwd);
 
-    for (key, value) in merged_env {
+    for (key, value) in terminal::identity_env(&shell_config.terminal, state.terminfo_dir.as_ref()) {
         command.env(key, value);
     }
 
-    // Set TERM for proper terminal behavior
-    command.env("TERM", "xterm-256color");
+    for (key, value) in merged_env {
+        command.env(key, value);
+    }
 
-    let child = pty_pair
-        .slave
-        .spawn_command(command)
-        .map_err(|err| format!("failed to spawn command: {err}"))?;
+    // Baseline for change attribution: snapshot the repo before the shell
+    // gets a chance to touch it, and make sure its watcher is running.
+    if let Ok(root) = porcelain::discover_repo_root(&resolved_cwd) {
+        match checkpoint::snapshot(&root) {
+            Ok(baseline_tree) => {
+                state.change_tracker.begin(&session_id, &root, baseline_tree);
+                if let Err(err) = git_commands::ensure_watching(&state, app_handle.clone(), &root) {
+                    log::warn!("failed to watch {root} for session {session_id}: {err}");
+                }
+            }
+            Err(err) => log::warn!("failed to snapshot baseline for {session_id}: {err}"),
+        }
+    }
 
-    let pid = child.process_id().map(i64::from).unwrap_or_default();
+    let mut child = match pty_pair.slave.spawn_command(command) {
+        Ok(child) => child,
+        Err(err) => {
+            abandon_spawn(&state, &session_id, worktree_info.as_ref());
+            return Err(format!("failed to spawn command: {err}"));
+        }
+    };
 
-    let mut reader = pty_pair
+    let handles = pty_pair
         .master
         .try_clone_reader()
-        .map_err(|err| format!("failed to clone pty reader: {err}"))?;
+        .map_err(|err| format!("failed to clone pty reader: {err}"))
+        .and_then(|reader| {
+            let writer = pty_pair
+                .master
+                .take_writer()
+                .map_err(|err| format!("failed to acquire pty writer: {err}"))?;
+            Ok((reader, writer))
+        })
+        .and_then(|handles| match &worktree_info {
+            Some(info) => worktree_commands::record_worktree(state.db_path.as_ref(), &session_id, info)
+                .map(|()| handles),
+            None => Ok(handles),
+        });
+    let (mut reader, writer) = match handles {
+        Ok(handles) => handles,
+        Err(err) => {
+            // Nothing will track the child, so don't leave it running.
+            let _ = child.kill();
+            let _ = child.wait();
+            abandon_spawn(&state, &session_id, worktree_info.as_ref());
+            return Err(err);
+        }
+    };
 
-    let writer = pty_pair
-        .master
-        .take_writer()
-        .map_err(|err| format!("failed to acquire pty writer: {err}"))?;
+    let pid = child.process_id().map(i64::from).unwrap_or_default();
+    state.change_tracker.attach_process(&session_id, pid);
+
+    let checkpoint_cwd = resolved_cwd.clone();
 
     let session = PtySession::new(
         session_id.clone(),
@@ -97,13 +249,24 @@ pub async fn pty_spawn(
         resolved_cwd,
         pid,
         Utc::now(),
+        worktree_info.clone(),
+        sandbox_command.map(|wrapped| wrapped.info),
+        limits.clone(),
+        session_encoding,
         pty_pair.master,
         writer,
         child,
     );
 
     let child_handle = session.child();
+    let activity = session.activity();
+    let session_ports = session.ports();
+    let limit_session = session.clone();
     let sessions = state.pty_manager.sessions();
+    let scrollback = state.pty_manager.scrollback();
+    scrollback.begin(session.info());
+    let diagnostics = state.pty_manager.diagnostics();
+    diagnostics.set(&session_id, Vec::new());
 
     {
         let mut guard = sessions
@@ -112,6 +275,170 @@ pub async fn pty_spawn(
         guard.insert(session_id.clone(), session);
     }
 
+    // Checkpoint thread: snapshots the working tree at spawn and then on every
+    // interval while the session is alive, skipping unchanged trees.
+    if let Some(interval) = checkpoint_interval_s.filter(|secs| *secs > 0) {
+        let checkpoint_sessions = state.pty_manager.sessions();
+        let app_for_checkpoint = app_handle.clone();
+        let checkpoint_session_id = session_id.clone();
+
+        std::thread::spawn(move || {
+            emit_checkpoint(
+                &app_for_checkpoint,
+                &checkpoint_cwd,
+                &checkpoint_session_id,
+                "session start",
+            );
+
+            loop {
+                std::thread::sleep(Duration::from_secs(interval));
+
+                let alive = checkpoint_sessions
+                    .lock()
+                    .map(|guard| guard.contains_key(&checkpoint_session_id))
+                    .unwrap_or(false);
+                if !alive {
+                    break;
+                }
+
+                emit_checkpoint(
+                    &app_for_checkpoint,
+                    &checkpoint_cwd,
+                    &checkpoint_session_id,
+                    "interval",
+                );
+            }
+        });
+    }
+
+    // Watchdog thread: enforces wall-clock and idle limits via the graceful
+    // kill path.
+    if let Some(session_limits) = limits.clone().filter(SessionLimits::has_timers) {
+        let watchdog_session = limit_session.clone();
+        let watchdog_activity = activity.clone();
+
+        std::thread::spawn(move || {
+            while !watchdog_activity.has_exited() {
+                if let Some(limit) = limits::check_timers(&session_limits, &watchdog_activity) {
+                    if watchdog_activity.trip(limit) {
+                        watchdog_session.terminate(KILL_GRACE);
+                    }
+                    break;
+                }
+                std::thread::sleep(Duration::from_secs(1));
+            }
+        });
+    }
+
+    // Sampler thread: walks the session's process tree in /proc on an
+    // interval, keeps a short time series and reports threshold crossings.
+    if cfg!(target_os = "linux") {
+        let monitor = state.pty_manager.resources();
+        let sampler_activity = activity.clone();
+        let sampler_limits = limits.clone().unwrap_or_default();
+        let app_for_sampler = app_handle.clone();
+        let sampler_session_id = session_id.clone();
+
+        std::thread::spawn(move || {
+            let mut sampler = TreeSampler::new(pid);
+            let mut thresholds = ThresholdWatch::default();
+
+            while !sampler_activity.has_exited() {
+                if let Some(sample) = sampler.sample() {
+                    for crossing in thresholds.check(&sampler_limits, &sample) {
+                        let _ = app_for_sampler.emit(
+                            "pty:resource_threshold",
+                            PtyResourceThresholdPayload {
+                                session_id: sampler_session_id.clone(),
+                                crossing,
+                            },
+                        );
+                    }
+                    monitor.push(&sampler_session_id, sample);
+                }
+                std::thread::sleep(SAMPLE_INTERVAL);
+            }
+
+            monitor.remove(&sampler_session_id);
+        });
+    }
+
+    // Port thread: matches listening sockets to the session's process tree
+    // and reports ports as they open and close. Anything still listening
+    // when the session exits is reported closed.
+    if cfg!(target_os = "linux") {
+        let port_activity = activity.clone();
+        let app_for_ports = app_handle.clone();
+        let port_session_id = session_id.clone();
+
+        std::thread::spawn(move || {
+            let mut previous = Vec::<ListeningPort>::new();
+
+            loop {
+                let exited = port_activity.has_exited();
+                let current = if exited {
+                    Vec::new()
+                } else {
+                    ports::listening_ports(pid)
+                };
+
+                let (opened, closed) = ports::diff(&previous, &current);
+                if let Ok(mut guard) = session_ports.lock() {
+                    *guard = current.clone();
+                }
+
+                for port in closed {
+                    let _ = app_for_ports.emit(
+                        "pty:port_closed",
+                        PtyPortPayload {
+                            session_id: port_session_id.clone(),
+                            port,
+                        },
+                    );
+                }
+                for port in opened {
+                    let _ = app_for_ports.emit(
+                        "pty:port_opened",
+                        PtyPortPayload {
+                            session_id: port_session_id.clone(),
+                            port,
+                        },
+                    );
+                }
+
+                if exited {
+                    break;
+                }
+                previous = current;
+                std::thread::sleep(PORT_SCAN_INTERVAL);
+            }
+        });
+    }
+
+    let max_output_bytes = limits.as_ref().and_then(|limits| limits.max_output_bytes);
+    let echo_master = limit_session.master();
+    let reader_encoding = limit_session.encoding();
+    let reader_title = limit_session.title();
+    let reader_scrollback = scrollback.clone();
+    let mut reader_cwd = limit_session.cwd.clone();
+    let reported_cwd = limit_session.reported_cwd();
+    let mut extractor = DiagnosticExtractor::new(reader_cwd.clone());
+    let mut test_runs = TestRunExtractor::default();
+    let reader_db_path = state.db_path.clone();
+
+    let mut session_log = if log_output.unwrap_or(shell_config.logging.enabled) {
+        let log_dir = shell_config.logging.resolve_dir(state.logs_dir.as_ref());
+        match SessionLog::open(&shell_config.logging, log_dir, &session_id, limit_session.log_path()) {
+            Ok(session_log) => Some(session_log),
+            Err(err) => {
+                log::warn!("failed to open log for session {session_id}: {err}");
+                None
+            }
+        }
+    } else {
+        None
+    };
+    let reader_activity = activity.clone();
     let scraper = state.pty_manager.scraper();
     let app_for_data = app_handle.clone();
     let data_session_id = session_id.clone();
@@ -121,6 +448,8 @@ pub async fn pty_spawn(
     // responsiveness — echo characters appear without delay.
     std::thread::spawn(move || {
         let mut buf = [0_u8; 4096];
+        let mut decoder = StreamDecoder::new(session_encoding);
+        let mut escapes = EscapeTracker::default();
 
         loop {
             match reader.read(&mut buf) {
@@ -128,30 +457,172 @@ pub async fn pty_spawn(
                 Ok(read_len) => {
                     let bytes = &buf[..read_len];
 
-                    let inserts = scraper.ingest(&data_session_id, bytes);
-                    if inserts > 0 {
+                    let total_output = reader_activity.record_output(read_len);
+                    if max_output_bytes.is_some_and(|max| total_output > max)
+                        && reader_activity.trip(LIMIT_MAX_OUTPUT_BYTES)
+                    {
+                        limit_session.terminate(KILL_GRACE);
+                    }
+
+                    let secret = echo_master
+                        .lock()
+                        .map(|master| secrets::echo_disabled(master.as_ref()))
+                        .unwrap_or(false);
+
+                    // Text consumers get output decoded with the session's
+                    // encoding; the frontend still receives the raw bytes.
+                    if let Ok(current) = reader_encoding.lock() {
+                        if *current != decoder.encoding() {
+                            decoder = StreamDecoder::new(*current);
+                        }
+                    }
+                    let text = decoder.decode(bytes);
+
+                    for event in escapes.feed(&text) {
+                        match event {
+                            TerminalEvent::Title(title) => {
+                                if let Ok(mut guard) = reader_title.lock() {
+                                    *guard = Some(title.clone());
+                                }
+                                let _ = app_for_data.emit(
+                                    "pty:title_changed",
+                                    PtyTitlePayload {
+                                        session_id: data_session_id.clone(),
+                                        title,
+                                    },
+                                );
+                            }
+                            TerminalEvent::Bell => {
+                                let _ = app_for_data.emit(
+                                    "pty:bell",
+                                    PtyBellPayload {
+                                        session_id: data_session_id.clone(),
+                                    },
+                                );
+                            }
+                            TerminalEvent::Notify { title, body } => {
+                                let _ = app_for_data.emit(
+                                    "pty:notify",
+                                    PtyNotifyPayload {
+                                        session_id: data_session_id.clone(),
+                                        title,
+                                        body,
+                                    },
+                                );
+                            }
+                            TerminalEvent::Cwd(cwd) => {
+                                extractor.set_cwd(cwd.clone());
+                                if let Ok(mut guard) = reported_cwd.lock() {
+                                    *guard = Some(cwd.clone());
+                                }
+                                reader_cwd = cwd;
+                            }
+                            TerminalEvent::CommandStart => {
+                                if let Some(run) = test_runs.begin_run() {
+                                    emit_test_run(
+                                        &app_for_data,
+                                        reader_db_path.as_ref(),
+                                        &data_session_id,
+                                        &reader_cwd,
+                                        &run,
+                                    );
+                                }
+                                if extractor.begin_run() {
+                                    diagnostics.set(&data_session_id, Vec::new());
+                                    let _ = app_for_data.emit(
+                                        "pty:diagnostics",
+                                        PtyDiagnosticsPayload {
+                                            session_id: data_session_id.clone(),
+                                            diagnostics: Vec::new(),
+                                        },
+                                    );
+                                }
+                            }
+                        }
+                    }
+
+                    if !secret {
+                        reader_scrollback.push(&data_session_id, &text);
+                    }
+
+                    if let Some(session_log) = session_log.as_mut() {
+                        if secret {
+                            session_log.discard_pending();
+                        } else {
+                            session_log.write(&text);
+                        }
+                    }
+
+                    if secret {
+                        test_runs.discard_pending();
+                    } else {
+                        for run in test_runs.feed(&text) {
+                            emit_test_run(
+                                &app_for_data,
+                                reader_db_path.as_ref(),
+                                &data_session_id,
+                                &reader_cwd,
+                                &run,
+                            );
+                        }
+                    }
+
+                    if secret {
+                        extractor.discard_pending();
+                    } else if extractor.feed(&text) {
+                        let current = extractor.diagnostics().to_vec();
+                        diagnostics.set(&data_session_id, current.clone());
                         let _ = app_for_data.emit(
-                            "token:captured",
-                            TokenCapturedPayload {
+                            "pty:diagnostics",
+                            PtyDiagnosticsPayload {
                                 session_id: data_session_id.clone(),
-                                inserts,
+                                diagnostics: current,
                             },
                         );
                     }
 
+                    // Rows are stored, and `token:captured` emitted, by the
+                    // scraper's writer thread. An agent starting gets a
+                    // checkpoint whatever the checkpoint interval, so its
+                    // changes can be rolled back to the tree it started on.
+                    if secret {
+                        scraper.suppress(&data_session_id);
+                    } else if let Some(agent) = scraper.ingest(&data_session_id, &text).started {
+                        let app_for_checkpoint = app_for_data.clone();
+                        let checkpoint_session_id = data_session_id.clone();
+                        let checkpoint_cwd = reader_cwd.clone();
+                        std::thread::spawn(move || {
+                            emit_checkpoint(
+                                &app_for_checkpoint,
+                                &checkpoint_cwd,
+                                &checkpoint_session_id,
+                                &format!("{agent} start"),
+                            );
+                        });
+                    }
+
                     let payload = PtyDataPayload {
                         session_id: data_session_id.clone(),
                         data: bytes.to_vec(),
+                        secret,
                     };
                     let _ = app_for_data.emit("pty:data", payload);
                 }
                 Err(_) => break,
             }
         }
+
+        if let Some(session_log) = session_log {
+            session_log.finish();
+        }
     });
 
     let app_for_exit = app_handle;
     let exit_session_id = session_id;
+    let exit_db_path = state.db_path.clone();
+    let exit_tracker = state.change_tracker.clone();
+    let exit_diagnostics = state.pty_manager.diagnostics();
+    let has_worktree = worktree_info.is_some();
 
     // Exit thread: waits for process completion and emits exit event.
     std::thread::spawn(move || {
@@ -159,11 +630,13 @@ pub async fn pty_spawn(
             let mut guard = match child_handle.lock() {
                 Ok(guard) => guard,
                 Err(_) => {
+                    activity.mark_exited();
                     let _ = app_for_exit.emit(
                         "pty:exit",
                         PtyExitPayload {
                             session_id: exit_session_id.clone(),
                             code: -1,
+                            limit: activity.tripped_limit(),
                         },
                     );
                     return;
@@ -176,14 +649,40 @@ pub async fn pty_spawn(
             }
         };
 
+        activity.mark_exited();
+
         let _ = app_for_exit.emit(
             "pty:exit",
             PtyExitPayload {
                 session_id: exit_session_id.clone(),
                 code,
+                limit: activity.tripped_limit(),
             },
         );
 
+        if let Some(root) = exit_tracker.end(&exit_session_id) {
+            let state = app_for_exit.state::<AppState>();
+            if let Err(err) = git_commands::release_watcher(&state, &root) {
+                log::warn!("failed to release watcher for {root}: {err}");
+            }
+        }
+
+        let info = sessions
+            .lock()
+            .ok()
+            .and_then(|guard| guard.get(&exit_session_id).map(PtySession::info));
+        scrollback.end(&exit_session_id, code, info);
+        exit_diagnostics.remove(&exit_session_id);
+
+        if has_worktree {
+            match worktree_commands::end_worktree(exit_db_path.as_ref(), &exit_session_id) {
+                Ok(record) => {
+                    let _ = app_for_exit.emit("worktree:ended", record);
+                }
+                Err(err) => log::warn!("failed to finalize worktree for {exit_session_id}: {err}"),
+            }
+        }
+
         if let Ok(mut guard) = sessions.lock() {
             guard.remove(&exit_session_id);
         }
@@ -192,6 +691,36 @@ pub async fn pty_spawn(
     Ok(())
 }
 
+/// Releases what `pty_spawn` set up for a session that won't run: change
+/// tracking, the repo watcher if nothing else needs it, and the worktree.
+fn abandon_spawn(state: &AppState, session_id: &str, worktree_info: Option<&WorktreeInfo>) {
+    if let Some(root) = state.change_tracker.end(session_id) {
+        let _ = git_commands::release_watcher(state, &root);
+    }
+    if let Some(info) = worktree_info {
+        let _ = worktree::remove(info, true);
+    }
+}
+
+fn emit_checkpoint(app_handle: &AppHandle, cwd: &str, session_id: &str, label: &str) {
+    match checkpoint::create(cwd, session_id, label, true) {
+        Ok(Some(info)) => {
+            let _ = app_handle.emit("checkpoint:created", info);
+        }
+        Ok(None) => {}
+        Err(err) => log::debug!("skipping checkpoint for {session_id}: {err}"),
+    }
+}
+
+fn emit_test_run(app_handle: &AppHandle, db_path: &Path, session_id: &str, cwd: &str, run: &TestRun) {
+    match test_runs::record_test_run(db_path, session_id, cwd, run) {
+        Ok(record) => {
+            let _ = app_handle.emit("test:run_recorded", record);
+        }
+        Err(err) => log::warn!("failed to record test run for {session_id}: {err}"),
+    }
+}
+
 #[tauri::command]
 pub async fn pty_write(
     state: State<'_, AppState>,
@@ -209,6 +738,8 @@ pub async fn pty_write(
             .ok_or_else(|| format!("pty session not found: {session_id}"))?
     };
 
+    session.activity().touch();
+
     let writer = session.writer();
     let mut guard = writer
         .lock()
@@ -257,47 +788,219 @@ pub async fn pty_resize(
 }
 
 #[tauri::command]
-pub async fn pty_kill(
+pub async fn pty_set_encoding(
     state: State<'_, AppState>,
-    app_handle: AppHandle,
     session_id: String,
-) -> Result<(), String> {
+    encoding: String,
+) -> Result<SessionInfo, String> {
+    let resolved = encoding::resolve(Some(&encoding))?;
+
+    let sessions = state.pty_manager.sessions();
     let session = {
-        let sessions = state.pty_manager.sessions();
-        let mut guard = sessions
+        let guard = sessions
             .lock()
-            .map_err(|_| "failed to lock pty sessions for kill".to_string())?;
+            .map_err(|_| "failed to lock pty sessions for encoding".to_string())?;
         guard
-            .remove(&session_id)
+            .get(&session_id)
+            .cloned()
             .ok_or_else(|| format!("pty session not found: {session_id}"))?
     };
 
-    {
-        let child = session.child();
-        let mut guard = child
+    let shared = session.encoding();
+    let mut guard = shared
+        .lock()
+        .map_err(|_| format!("failed to lock encoding for session: {session_id}"))?;
+    *guard = resolved;
+    drop(guard);
+
+    Ok(session.info())
+}
+
+#[tauri::command]
+pub async fn pty_kill(state: State<'_, AppState>, session_id: String) -> Result<(), String> {
+    let session = {
+        let sessions = state.pty_manager.sessions();
+        let guard = sessions
             .lock()
-            .map_err(|_| format!("failed to lock child for session: {session_id}"))?;
-        let _ = guard.kill();
-        let _ = guard.wait();
-    }
+            .map_err(|_| "failed to lock pty sessions for kill".to_string())?;
+        guard
+            .get(&session_id)
+            .cloned()
+            .ok_or_else(|| format!("pty session not found: {session_id}"))?
+    };
 
-    app_handle
-        .emit(
-            "pty:exit",
-            PtyExitPayload {
-                session_id,
-                code: -1,
-            },
-        )
-        .map_err(|err| format!("failed to emit pty exit event: {err}"))
+    // The exit thread emits `pty:exit` and removes the session once the
+    // child has been reaped.
+    session.terminate(KILL_GRACE);
+    Ok(())
 }
 
 #[tauri::command]
-pub async fn pty_list(state: State<'_, AppState>) -> Result<Vec<SessionInfo>, String> {
+pub async fn pty_list(
+    state: State<'_, AppState>,
+    tag: Option<String>,
+) -> Result<Vec<SessionInfo>, String> {
+    let tagged = tag
+        .map(|tag| annotations::sessions_with_tag(state.db_path.as_ref(), &tag))
+        .transpose()?;
+
     let sessions = state.pty_manager.sessions();
     let guard = sessions
         .lock()
         .map_err(|_| "failed to lock pty sessions for list".to_string())?;
 
-    Ok(guard.values().map(PtySession::info).collect())
+    Ok(guard
+        .values()
+        .filter(|session| match &tagged {
+            Some(tagged) => tagged.contains(&session.session_id),
+            None => true,
+        })
+        .map(PtySession::info)
+        .collect())
+}
+
+/// Reads stored output by absolute line. Without `start_line`, returns the
+/// last `count` lines.
+#[tauri::command]
+pub async fn pty_scrollback(
+    state: State<'_, AppState>,
+    session_id: String,
+    start_line: Option<u64>,
+    count: Option<u64>,
+) -> Result<ScrollbackPage, String> {
+    let scrollback = state
+        .pty_manager
+        .scrollback()
+        .get(&session_id)
+        .ok_or_else(|| format!("no stored output for session: {session_id}"))?;
+
+    let count = count.unwrap_or(200);
+    let start_line = start_line.unwrap_or_else(|| scrollback.total_lines().saturating_sub(count));
+
+    Ok(scrollback.page(start_line, count))
+}
+
+#[tauri::command]
+pub async fn pty_resource_usage(
+    state: State<'_, AppState>,
+    session_id: String,
+) -> Result<ResourceUsage, String> {
+    {
+        let sessions = state.pty_manager.sessions();
+        let guard = sessions
+            .lock()
+            .map_err(|_| "failed to lock pty sessions for resource usage".to_string())?;
+        if !guard.contains_key(&session_id) {
+            return Err(format!("pty session not found: {session_id}"));
+        }
+    }
+
+    Ok(state.pty_manager.resources().usage(&session_id))
+}
+
+/// File references in `text` (typically a line of the session's output)
+/// that exist relative to where the session is now.
+#[tauri::command]
+pub async fn resolve_path_link(
+    state: State<'_, AppState>,
+    session_id: String,
+    text: String,
+) -> Result<Vec<PathLink>, String> {
+    let live = {
+        let sessions = state.pty_manager.sessions();
+        let guard = sessions
+            .lock()
+            .map_err(|_| "failed to lock pty sessions for link resolution".to_string())?;
+        guard
+            .get(&session_id)
+            .map(|session| (session.live_cwd(), session.cwd.clone()))
+    };
+
+    let (live_cwd, spawn_cwd) = match live {
+        Some(cwds) => cwds,
+        None => {
+            let scrollback = state
+                .pty_manager
+                .scrollback()
+                .get(&session_id)
+                .ok_or_else(|| format!("pty session not found: {session_id}"))?;
+            (scrollback.info.cwd.clone(), scrollback.info.cwd)
+        }
+    };
+
+    Ok(links::find_links(&text, &links::search_dirs(&live_cwd, &spawn_cwd)))
+}
+
+/// Diagnostics from the session's latest run, in the order they appeared.
+#[tauri::command]
+pub async fn pty_diagnostics(
+    state: State<'_, AppState>,
+    session_id: String,
+) -> Result<Vec<Diagnostic>, String> {
+    Ok(state.pty_manager.diagnostics().get(&session_id))
+}
+
+#[tauri::command]
+pub async fn pty_log_path(
+    state: State<'_, AppState>,
+    session_id: String,
+) -> Result<Option<String>, String> {
+    let live = {
+        let sessions = state.pty_manager.sessions();
+        let guard = sessions
+            .lock()
+            .map_err(|_| "failed to lock pty sessions for log path".to_string())?;
+        guard.get(&session_id).map(|session| session.info().log_path)
+    };
+
+    if let Some(log_path) = live {
+        return Ok(log_path);
+    }
+
+    let shell_config = settings::load_shell_config_from_path(state.shell_config_path.as_ref())
+        .unwrap_or_else(|_| settings::ShellConfig::default());
+    let log_dir = shell_config.logging.resolve_dir(state.logs_dir.as_ref());
+
+    Ok(logging::latest_log(&log_dir, &session_id).map(|path| path.to_string_lossy().to_string()))
+}
+
+#[tauri::command]
+pub async fn pty_export_transcript(
+    state: State<'_, AppState>,
+    session_id: String,
+    format: String,
+) -> Result<String, String> {
+    let stored = state.pty_manager.scrollback().get(&session_id);
+    let mut source = match stored {
+        Some(source) => source,
+        None => {
+            // Older sessions are only on disk, if logging was on.
+            let shell_config = settings::load_shell_config_from_path(state.shell_config_path.as_ref())
+                .unwrap_or_else(|_| settings::ShellConfig::default());
+            let log_dir = shell_config.logging.resolve_dir(state.logs_dir.as_ref());
+            let text = logging::read_session_log(&log_dir, &session_id)?
+                .ok_or_else(|| format!("no stored output or log for session: {session_id}"))?;
+
+            Scrollback {
+                info: SessionInfo {
+                    session_id: session_id.clone(),
+                    ..SessionInfo::default()
+                },
+                ended_at: None,
+                exit_code: None,
+                first_line: 0,
+                text,
+            }
+        }
+    };
+
+    // Live sessions have fresher metadata than the spawn-time snapshot.
+    if let Ok(guard) = state.pty_manager.sessions().lock() {
+        if let Some(session) = guard.get(&session_id) {
+            source.info = session.info();
+        }
+    }
+
+    let usage = tokens::session_usage(state.db_path.as_ref(), &session_id)?;
+    transcript::render(&format, &source, &usage)
 }
@@ -3,6 +3,9 @@ use std::path::Path;
 use serde::{Deserialize, Serialize};
 use tauri::State;
 
+use crate::commands::editor::EditorConfig;
+use crate::pty::agents::ScraperConfig;
+use crate::pty::logging::LogConfig;
 use crate::state::AppState;
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -13,6 +16,43 @@ pub struct ShellConfig {
     pub default_env: std::collections::HashMap<String, String>,
     pub login_shell: bool,
     pub profile_load: bool,
+    /// Default output encoding for new sessions; UTF-8 when unset.
+    pub encoding: Option<String>,
+    #[serde(default)]
+    pub terminal: TerminalIdentity,
+    #[serde(default)]
+    pub logging: LogConfig,
+    #[serde(default)]
+    pub editor: EditorConfig,
+    #[serde(default)]
+    pub scraper: ScraperConfig,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase", default)]
+pub struct TerminalIdentity {
+    pub term: String,
+    pub colorterm: Option<String>,
+    /// Sets TERM_PROGRAM=Aion and TERM_PROGRAM_VERSION.
+    pub term_program: bool,
+    /// Sets LANG and LC_CTYPE. When unset, LANG falls back to en_US.UTF-8
+    /// only if the app itself was started without a locale.
+    pub locale: Option<String>,
+    /// Install the bundled `aion` terminfo entry into a private TERMINFO dir,
+    /// so `term` can be set to `aion`.
+    pub bundled_terminfo: bool,
+}
+
+impl Default for TerminalIdentity {
+    fn default() -> Self {
+        Self {
+            term: "xterm-256color".to_string(),
+            colorterm: Some("truecolor".to_string()),
+            term_program: true,
+            locale: None,
+            bundled_terminfo: false,
+        }
+    }
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -48,6 +88,11 @@ impl Default for ShellConfig {
             default_env: std::collections::HashMap::new(),
             login_shell: true,
             profile_load: true,
+            encoding: None,
+            terminal: TerminalIdentity::default(),
+            logging: LogConfig::default(),
+            editor: EditorConfig::default(),
+            scraper: ScraperConfig::default(),
         }
     }
 }
@@ -63,7 +108,10 @@ pub fn save_shell_config(state: State<'_, AppState>, config: ShellConfig) -> Res
         .map_err(|err| format!("failed to serialize shell config: {err}"))?;
 
     std::fs::write(state.shell_config_path.as_ref(), payload)
-        .map_err(|err| format!("failed to write shell config: {err}"))
+        .map_err(|err| format!("failed to write shell config: {err}"))?;
+
+    state.pty_manager.scraper().set_rules(&config.scraper.rules);
+    Ok(())
 }
 
 #[tauri::command]
@@ -215,7 +263,7 @@ fn shell_info_from_path(path: &str) -> Result<ShellInfo, String> {
     })
 }
 
-fn which_in_path(binary: &str) -> bool {
+pub(crate) fn which_in_path(binary: &str) -> bool {
     if binary.contains('/') || binary.contains('\\') {
         return Path::new(binary).exists();
     }
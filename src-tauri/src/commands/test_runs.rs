@@ -0,0 +1,130 @@
+use std::path::Path;
+
+use rusqlite::{params, params_from_iter, types::Value, Connection};
+use serde::Serialize;
+use tauri::State;
+
+use crate::git::porcelain;
+use crate::pty::test_runs::TestRun;
+use crate::state::AppState;
+
+const TEST_RUN_COLUMNS: &str =
+    "id, session_id, project, git_head, runner, passed, failed, skipped, failures, duration_s, captured_at";
+
+#[derive(Debug, Clone, Serialize)]
+pub struct TestRunRecord {
+    pub id: i64,
+    pub session_id: String,
+    /// Repository root of the directory the tests ran in, or that directory
+    /// when it isn't inside a repository.
+    pub project: String,
+    pub git_head: Option<String>,
+    pub runner: String,
+    pub passed: i64,
+    pub failed: i64,
+    pub skipped: i64,
+    pub failures: Vec<String>,
+    pub duration_s: Option<f64>,
+    pub captured_at: String,
+}
+
+#[tauri::command]
+pub async fn query_test_runs(
+    state: State<'_, AppState>,
+    project: Option<String>,
+    session_id: Option<String>,
+    limit: Option<u32>,
+) -> Result<Vec<TestRunRecord>, String> {
+    let conn = open_conn(state.db_path.as_ref())?;
+
+    let mut query = format!("SELECT {TEST_RUN_COLUMNS} FROM test_runs WHERE 1=1");
+    let mut params = Vec::<Value>::new();
+
+    if let Some(project) = project {
+        query.push_str(" AND project = ?");
+        params.push(Value::Text(project));
+    }
+
+    if let Some(session_id) = session_id {
+        query.push_str(" AND session_id = ?");
+        params.push(Value::Text(session_id));
+    }
+
+    query.push_str(" ORDER BY captured_at DESC, id DESC LIMIT ?");
+    params.push(Value::Integer(i64::from(limit.unwrap_or(200))));
+
+    let mut statement = conn
+        .prepare(&query)
+        .map_err(|err| format!("failed to prepare test run query: {err}"))?;
+
+    let records = statement
+        .query_map(params_from_iter(params), map_record)
+        .map_err(|err| format!("failed to execute test run query: {err}"))?
+        .collect::<Result<Vec<_>, _>>()
+        .map_err(|err| format!("failed to map test run rows: {err}"))?;
+
+    Ok(records)
+}
+
+/// Stores a finished run against the repository and HEAD of `cwd` as they
+/// are now.
+pub fn record_test_run(
+    db_path: &Path,
+    session_id: &str,
+    cwd: &str,
+    run: &TestRun,
+) -> Result<TestRunRecord, String> {
+    let project = porcelain::discover_repo_root(cwd).unwrap_or_else(|_| cwd.to_string());
+    let git_head = porcelain::open_repo(cwd)
+        .ok()
+        .and_then(|repo| repo.head().ok().and_then(|head| head.target()))
+        .map(|oid| oid.to_string());
+    let failures = serde_json::to_string(&run.failures)
+        .map_err(|err| format!("failed to encode test failures: {err}"))?;
+
+    let conn = open_conn(db_path)?;
+    conn.execute(
+        "INSERT INTO test_runs (session_id, project, git_head, runner, passed, failed, skipped, failures, duration_s) VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8, ?9)",
+        params![
+            session_id,
+            project,
+            git_head,
+            run.runner,
+            run.passed,
+            run.failed,
+            run.skipped,
+            failures,
+            run.duration_s,
+        ],
+    )
+    .map_err(|err| format!("failed to record test run: {err}"))?;
+
+    conn.query_row(
+        &format!("SELECT {TEST_RUN_COLUMNS} FROM test_runs WHERE id = ?1"),
+        params![conn.last_insert_rowid()],
+        map_record,
+    )
+    .map_err(|err| format!("failed to query test run: {err}"))
+}
+
+fn map_record(row: &rusqlite::Row<'_>) -> rusqlite::Result<TestRunRecord> {
+    let failures: String = row.get(8)?;
+
+    Ok(TestRunRecord {
+        id: row.get(0)?,
+        session_id: row.get(1)?,
+        project: row.get(2)?,
+        git_head: row.get(3)?,
+        runner: row.get(4)?,
+        passed: row.get(5)?,
+        failed: row.get(6)?,
+        skipped: row.get(7)?,
+        failures: serde_json::from_str(&failures).unwrap_or_default(),
+        duration_s: row.get(9)?,
+        captured_at: row.get(10)?,
+    })
+}
+
+fn open_conn(db_path: &Path) -> Result<Connection, String> {
+    Connection::open(db_path).map_err(|err| format!("failed to open db: {err}"))
+}
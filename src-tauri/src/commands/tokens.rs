@@ -1,7 +1,13 @@
-use rusqlite::{params, params_from_iter, types::Value, Connection};
+use std::path::Path;
+
+use rusqlite::{params, params_from_iter, types::Value, Connection, Row};
 use serde::Serialize;
 use tauri::State;
 
+use crate::commands::annotations;
+use crate::pty::agents::{self, RuleError, RuleTestResult, ScrapeRule};
+use crate::pty::usage_writer;
+use crate::usage;
 use crate::state::AppState;
 
 #[derive(Debug, Serialize)]
@@ -16,6 +22,74 @@ pub struct UsageRecord {
     pub duration_s: Option<i64>,
     pub captured_at: String,
     pub raw_output: String,
+    /// `cost_usd` was computed from the pricing table, not reported.
+    pub estimated: bool,
+    /// One of `usage::SOURCES`.
+    pub source: String,
+    pub model: Option<String>,
+    pub project: Option<String>,
+    pub cache_read_tokens: Option<i64>,
+    pub cache_creation_tokens: Option<i64>,
+    /// Session totals printed by agents like Claude Code's `/cost`; the
+    /// counts above are then the increase since the previous readout.
+    pub cumulative_cost_usd: Option<f64>,
+    pub cumulative_tokens_total: Option<i64>,
+    /// Whether the row counts towards `query_usage_summary` totals under the
+    /// same filters.
+    pub counted: bool,
+}
+
+const USAGE_COLUMNS: &str = "id, session_id, agent, cost_usd, tokens_in, tokens_out, tokens_total, duration_s, captured_at, COALESCE(raw_output, ''), estimated, source, model, project, cache_read_tokens, cache_creation_tokens, cumulative_cost_usd, cumulative_tokens_total";
+
+fn usage_record(row: &Row<'_>) -> rusqlite::Result<UsageRecord> {
+    Ok(UsageRecord {
+        id: row.get(0)?,
+        session_id: row.get(1)?,
+        agent: row.get(2)?,
+        cost_usd: row.get(3)?,
+        tokens_in: row.get(4)?,
+        tokens_out: row.get(5)?,
+        tokens_total: row.get(6)?,
+        duration_s: row.get(7)?,
+        captured_at: row.get(8)?,
+        raw_output: row.get(9)?,
+        estimated: row.get(10)?,
+        source: row.get(11)?,
+        model: row.get(12)?,
+        project: row.get(13)?,
+        cache_read_tokens: row.get(14)?,
+        cache_creation_tokens: row.get(15)?,
+        cumulative_cost_usd: row.get(16)?,
+        cumulative_tokens_total: row.get(17)?,
+        counted: row.get(18)?,
+    })
+}
+
+#[derive(Debug, Default, Serialize)]
+pub struct UsageSummary {
+    pub records: i64,
+    pub cost_usd: f64,
+    pub tokens_total: i64,
+    /// Oldest first.
+    pub days: Vec<UsageDay>,
+    /// Most expensive first.
+    pub agents: Vec<AgentUsage>,
+}
+
+#[derive(Debug, Default, Serialize)]
+pub struct UsageDay {
+    /// `YYYY-MM-DD` of `captured_at`.
+    pub date: String,
+    pub cost_usd: f64,
+    pub tokens_total: i64,
+    pub agents: Vec<AgentUsage>,
+}
+
+#[derive(Debug, Serialize)]
+pub struct AgentUsage {
+    pub agent: String,
+    pub cost_usd: f64,
+    pub tokens_total: i64,
 }
 
 #[derive(Debug, Serialize)]
@@ -27,69 +101,270 @@ pub struct BudgetSummary {
     pub pct_used: f64,
 }
 
+#[derive(Debug, Serialize)]
+pub struct ImportSourceStatus {
+    pub agent: String,
+    /// Directory the agent's logs are read from.
+    pub root: Option<String>,
+    /// Whether the root holds any of the agent's logs.
+    pub found: bool,
+    pub files: Vec<ImportFileStatus>,
+}
+
+#[derive(Debug, Serialize)]
+pub struct ImportFileStatus {
+    pub path: String,
+    /// Bytes read so far.
+    pub position: i64,
+    /// Current size on disk, or null when the file is gone.
+    pub size: Option<i64>,
+    pub scanned_at: String,
+    pub errors: i64,
+    pub last_error: Option<String>,
+}
+
+/// `model` matches as a prefix, so `claude-sonnet-4` includes dated
+/// releases.
 #[tauri::command]
+#[allow(clippy::too_many_arguments)]
 pub async fn query_usage(
     state: State<'_, AppState>,
     from: Option<String>,
     to: Option<String>,
     agent: Option<String>,
     session_id: Option<String>,
+    tag: Option<String>,
+    model: Option<String>,
+    source: Option<String>,
+    project: Option<String>,
 ) -> Result<Vec<UsageRecord>, String> {
     let conn = open_conn(&state)?;
 
-    let mut query = String::from(
-        "SELECT id, session_id, agent, cost_usd, tokens_in, tokens_out, tokens_total, duration_s, captured_at, COALESCE(raw_output, '') FROM token_usage WHERE 1=1",
+    let filter = UsageFilter {
+        from,
+        to,
+        agent,
+        session_id,
+        tag,
+        model,
+        source,
+        project,
+    };
+    let counted = filter.counted();
+    let (conditions, params) = filter.sql()?;
+    let query = format!(
+        "SELECT {USAGE_COLUMNS}, {counted} FROM token_usage WHERE 1=1{conditions} ORDER BY captured_at DESC LIMIT 5000"
     );
-    let mut params = Vec::<Value>::new();
 
-    if let Some(from) = from {
-        query.push_str(" AND captured_at >= ?");
-        params.push(Value::Text(from));
-    }
+    let mut statement = conn
+        .prepare(&query)
+        .map_err(|err| format!("failed to prepare usage query: {err}"))?;
+
+    let records = statement
+        .query_map(params_from_iter(params.iter()), usage_record)
+        .map_err(|err| format!("failed to execute usage query: {err}"))?
+        .collect::<Result<Vec<_>, _>>()
+        .map_err(|err| format!("failed to map usage rows: {err}"))?;
+
+    Ok(records)
+}
+
+/// Totals per day and agent over every matching row, for the dashboard.
+/// Takes the same filters as `query_usage` but isn't capped by its limit,
+/// and sums the rows it marks `counted`.
+#[tauri::command]
+#[allow(clippy::too_many_arguments)]
+pub async fn query_usage_summary(
+    state: State<'_, AppState>,
+    from: Option<String>,
+    to: Option<String>,
+    agent: Option<String>,
+    session_id: Option<String>,
+    tag: Option<String>,
+    model: Option<String>,
+    source: Option<String>,
+    project: Option<String>,
+) -> Result<UsageSummary, String> {
+    let conn = open_conn(&state)?;
+
+    let filter = UsageFilter {
+        from,
+        to,
+        agent,
+        session_id,
+        tag,
+        model,
+        source,
+        project,
+    };
+    let counted = filter.counted();
+    let (conditions, params) = filter.sql()?;
+    let query = format!(
+        "SELECT substr(captured_at, 1, 10) AS day, agent, COUNT(*), COALESCE(SUM(cost_usd), 0), COALESCE(SUM(tokens_total), 0)
+         FROM token_usage WHERE 1=1{conditions} AND {counted} GROUP BY day, agent ORDER BY day, agent"
+    );
+
+    let mut statement = conn
+        .prepare(&query)
+        .map_err(|err| format!("failed to prepare usage summary query: {err}"))?;
 
-    if let Some(to) = to {
-        query.push_str(" AND captured_at <= ?");
-        params.push(Value::Text(to));
+    let buckets = statement
+        .query_map(params_from_iter(params.iter()), |row| {
+            Ok((
+                row.get::<_, String>(0)?,
+                row.get::<_, String>(1)?,
+                row.get::<_, i64>(2)?,
+                row.get::<_, f64>(3)?,
+                row.get::<_, i64>(4)?,
+            ))
+        })
+        .map_err(|err| format!("failed to execute usage summary query: {err}"))?
+        .collect::<Result<Vec<_>, _>>()
+        .map_err(|err| format!("failed to map usage summary rows: {err}"))?;
+
+    let mut summary = UsageSummary::default();
+    for (date, agent, records, cost_usd, tokens_total) in buckets {
+        summary.records += records;
+        summary.cost_usd += cost_usd;
+        summary.tokens_total += tokens_total;
+
+        if summary.days.last().map(|day| &day.date) != Some(&date) {
+            summary.days.push(UsageDay {
+                date,
+                ..UsageDay::default()
+            });
+        }
+        if let Some(day) = summary.days.last_mut() {
+            day.cost_usd += cost_usd;
+            day.tokens_total += tokens_total;
+            day.agents.push(AgentUsage {
+                agent: agent.clone(),
+                cost_usd,
+                tokens_total,
+            });
+        }
+
+        match summary.agents.iter_mut().find(|total| total.agent == agent) {
+            Some(total) => {
+                total.cost_usd += cost_usd;
+                total.tokens_total += tokens_total;
+            }
+            None => summary.agents.push(AgentUsage {
+                agent,
+                cost_usd,
+                tokens_total,
+            }),
+        }
     }
+    summary
+        .agents
+        .sort_by(|a, b| b.cost_usd.total_cmp(&a.cost_usd));
+
+    Ok(summary)
+}
+
+/// Filters shared by `query_usage` and `query_usage_summary`.
+struct UsageFilter {
+    from: Option<String>,
+    to: Option<String>,
+    agent: Option<String>,
+    session_id: Option<String>,
+    tag: Option<String>,
+    model: Option<String>,
+    source: Option<String>,
+    project: Option<String>,
+}
 
-    if let Some(agent) = agent {
-        query.push_str(" AND agent = ?");
-        params.push(Value::Text(agent));
+impl UsageFilter {
+    /// Condition on the rows that count towards totals: only
+    /// `usage::COUNTED_USAGE` rows unless a source is asked for.
+    fn counted(&self) -> &'static str {
+        if self.source.is_some() {
+            "1"
+        } else {
+            usage::COUNTED_USAGE
+        }
     }
 
-    if let Some(session_id) = session_id {
-        query.push_str(" AND session_id = ?");
-        params.push(Value::Text(session_id));
+    /// ` AND …` conditions for the filters that are set, and their params.
+    fn sql(self) -> Result<(String, Vec<Value>), String> {
+        let mut query = String::new();
+        let mut params = Vec::<Value>::new();
+
+        if let Some(from) = self.from {
+            query.push_str(" AND captured_at >= ?");
+            params.push(Value::Text(from));
+        }
+
+        if let Some(to) = self.to {
+            query.push_str(" AND captured_at <= ?");
+            params.push(Value::Text(to));
+        }
+
+        if let Some(agent) = self.agent {
+            query.push_str(" AND agent = ?");
+            params.push(Value::Text(agent));
+        }
+
+        if let Some(session_id) = self.session_id {
+            query.push_str(" AND session_id = ?");
+            params.push(Value::Text(session_id));
+        }
+
+        if let Some(tag) = self.tag {
+            query.push_str(" AND session_id IN (SELECT session_id FROM session_tags WHERE tag = ?)");
+            params.push(Value::Text(annotations::normalize_tag(&tag)?));
+        }
+
+        if let Some(model) = self.model {
+            query.push_str(" AND model LIKE ? ESCAPE '\\'");
+            let escaped = model
+                .replace('\\', "\\\\")
+                .replace('%', "\\%")
+                .replace('_', "\\_");
+            params.push(Value::Text(format!("{escaped}%")));
+        }
+
+        if let Some(source) = self.source {
+            if !usage::SOURCES.contains(&source.as_str()) {
+                return Err(format!("unknown usage source '{source}'"));
+            }
+            query.push_str(" AND source = ?");
+            params.push(Value::Text(source));
+        }
+
+        if let Some(project) = self.project {
+            query.push_str(" AND project = ?");
+            params.push(Value::Text(project));
+        }
+
+        Ok((query, params))
     }
+}
 
-    query.push_str(" ORDER BY captured_at DESC LIMIT 5000");
+/// Usage rows for one session in capture order, for transcripts.
+pub fn session_usage(db_path: &Path, session_id: &str) -> Result<Vec<UsageRecord>, String> {
+    let conn = Connection::open(db_path).map_err(|err| format!("failed to open db: {err}"))?;
 
     let mut statement = conn
-        .prepare(&query)
-        .map_err(|err| format!("failed to prepare usage query: {err}"))?;
+        .prepare(&format!(
+            "SELECT {USAGE_COLUMNS}, {} FROM token_usage WHERE session_id = ?1 ORDER BY captured_at ASC, id ASC",
+            usage::COUNTED_USAGE
+        ))
+        .map_err(|err| format!("failed to prepare session usage query: {err}"))?;
 
     let records = statement
-        .query_map(params_from_iter(params.iter()), |row| {
-            Ok(UsageRecord {
-                id: row.get(0)?,
-                session_id: row.get(1)?,
-                agent: row.get(2)?,
-                cost_usd: row.get(3)?,
-                tokens_in: row.get(4)?,
-                tokens_out: row.get(5)?,
-                tokens_total: row.get(6)?,
-                duration_s: row.get(7)?,
-                captured_at: row.get(8)?,
-                raw_output: row.get(9)?,
-            })
-        })
-        .map_err(|err| format!("failed to execute usage query: {err}"))?
+        .query_map(params![session_id], usage_record)
+        .map_err(|err| format!("failed to execute session usage query: {err}"))?
         .collect::<Result<Vec<_>, _>>()
-        .map_err(|err| format!("failed to map usage rows: {err}"))?;
+        .map_err(|err| format!("failed to map session usage rows: {err}"))?;
 
     Ok(records)
 }
 
+/// Cumulative readouts store only their increase in `cost_usd`, so the
+/// month's spend is the sum of deltas, over `usage::COUNTED_USAGE` rows so
+/// scraped and imported usage of the same agent isn't counted twice.
 #[tauri::command]
 pub async fn query_budget(state: State<'_, AppState>, month: String) -> Result<BudgetSummary, String> {
     let conn = open_conn(&state)?;
@@ -104,7 +379,10 @@ pub async fn query_budget(state: State<'_, AppState>, month: String) -> Result<B
 
     let spent_usd = conn
         .query_row(
-            "SELECT COALESCE(SUM(cost_usd), 0) FROM token_usage WHERE strftime('%Y-%m', captured_at) = ?1",
+            &format!(
+                "SELECT COALESCE(SUM(cost_usd), 0) FROM token_usage WHERE strftime('%Y-%m', captured_at) = ?1 AND {}",
+                usage::COUNTED_USAGE
+            ),
             params![month.clone()],
             |row| row.get::<_, f64>(0),
         )
@@ -139,6 +417,75 @@ pub async fn set_budget(state: State<'_, AppState>, month: String, limit_usd: f6
     Ok(())
 }
 
+/// One-time fix for rows captured before cumulative readouts were tracked,
+/// where each `/cost` counted the whole session again.
+#[tauri::command]
+pub async fn usage_repair_cumulative(state: State<'_, AppState>) -> Result<usize, String> {
+    let agents = state.pty_manager.scraper().cumulative_agents();
+    let mut conn = open_conn(&state)?;
+    let tx = conn
+        .transaction()
+        .map_err(|err| format!("failed to start cumulative usage repair: {err}"))?;
+    let repaired = usage_writer::repair_cumulative(&tx, &agents)?;
+    tx.commit()
+        .map_err(|err| format!("failed to commit cumulative usage repair: {err}"))?;
+    Ok(repaired)
+}
+
+/// How far each agent's usage logs have been imported.
+#[tauri::command]
+pub async fn import_status(state: State<'_, AppState>) -> Result<Vec<ImportSourceStatus>, String> {
+    let conn = open_conn(&state)?;
+
+    let mut statement = conn
+        .prepare(
+            "SELECT path, position, scanned_at, errors, last_error FROM usage_import_files WHERE agent = ?1 ORDER BY path",
+        )
+        .map_err(|err| format!("failed to prepare import status query: {err}"))?;
+
+    let mut sources = Vec::new();
+    for log in usage::all_logs() {
+        let files = statement
+            .query_map(params![log.agent()], |row| {
+                let path = row.get::<_, String>(0)?;
+                let size = std::fs::metadata(&path).ok().map(|meta| meta.len() as i64);
+                Ok(ImportFileStatus {
+                    path,
+                    position: row.get(1)?,
+                    size,
+                    scanned_at: row.get(2)?,
+                    errors: row.get(3)?,
+                    last_error: row.get(4)?,
+                })
+            })
+            .map_err(|err| format!("failed to execute import status query: {err}"))?
+            .collect::<Result<Vec<_>, _>>()
+            .map_err(|err| format!("failed to map import status rows: {err}"))?;
+
+        let root = log.root();
+        sources.push(ImportSourceStatus {
+            agent: log.agent().to_string(),
+            found: usage::importer::has_logs(log.as_ref()),
+            root: root.map(|root| root.to_string_lossy().to_string()),
+            files,
+        });
+    }
+
+    Ok(sources)
+}
+
+/// Problems found in the scraping rules from the shell config.
+#[tauri::command]
+pub async fn scraper_rule_errors(state: State<'_, AppState>) -> Result<Vec<RuleError>, String> {
+    Ok(state.pty_manager.scraper().rule_errors())
+}
+
+/// Tries a scraping rule against sample output without saving anything.
+#[tauri::command]
+pub async fn scraper_test_rule(rule: ScrapeRule, sample_text: String) -> Result<RuleTestResult, String> {
+    Ok(agents::test_rule(&rule, &sample_text))
+}
+
 fn open_conn(state: &State<'_, AppState>) -> Result<Connection, String> {
     Connection::open(state.db_path.as_ref()).map_err(|err| format!("failed to open db: {err}"))
 }
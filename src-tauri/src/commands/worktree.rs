@@ -0,0 +1,192 @@
+use std::path::Path;
+
+use rusqlite::{params, params_from_iter, types::Value, Connection, OptionalExtension};
+use serde::Serialize;
+use tauri::State;
+
+use crate::git::porcelain::MergeResult;
+use crate::git::worktree::{self, WorktreeInfo};
+use crate::state::AppState;
+
+#[derive(Debug, Clone, Serialize)]
+pub struct WorktreeRecord {
+    pub session_id: String,
+    pub name: String,
+    pub repo_path: String,
+    pub worktree_path: String,
+    pub branch: String,
+    pub base_oid: String,
+    pub status: String,
+    pub created_at: String,
+    pub ended_at: Option<String>,
+}
+
+impl WorktreeRecord {
+    fn info(&self) -> WorktreeInfo {
+        WorktreeInfo {
+            name: self.name.clone(),
+            repo_path: self.repo_path.clone(),
+            worktree_path: self.worktree_path.clone(),
+            branch: self.branch.clone(),
+            base_oid: self.base_oid.clone(),
+        }
+    }
+}
+
+const WORKTREE_COLUMNS: &str =
+    "session_id, name, repo_path, worktree_path, branch, base_oid, status, created_at, ended_at";
+
+#[tauri::command]
+pub async fn worktree_list(
+    state: State<'_, AppState>,
+    repo_path: Option<String>,
+    status: Option<String>,
+) -> Result<Vec<WorktreeRecord>, String> {
+    let conn = open_conn(state.db_path.as_ref())?;
+
+    let mut query = format!("SELECT {WORKTREE_COLUMNS} FROM session_worktrees WHERE 1=1");
+    let mut params = Vec::<Value>::new();
+
+    if let Some(repo_path) = repo_path {
+        query.push_str(" AND repo_path = ?");
+        params.push(Value::Text(repo_path));
+    }
+
+    if let Some(status) = status {
+        query.push_str(" AND status = ?");
+        params.push(Value::Text(status));
+    }
+
+    query.push_str(" ORDER BY created_at DESC");
+
+    let mut statement = conn
+        .prepare(&query)
+        .map_err(|err| format!("failed to prepare worktree query: {err}"))?;
+
+    let records = statement
+        .query_map(params_from_iter(params.iter()), map_record)
+        .map_err(|err| format!("failed to execute worktree query: {err}"))?
+        .collect::<Result<Vec<_>, _>>()
+        .map_err(|err| format!("failed to map worktree rows: {err}"))?;
+
+    Ok(records)
+}
+
+#[tauri::command]
+pub async fn worktree_merge(
+    state: State<'_, AppState>,
+    session_id: String,
+    no_ff: Option<bool>,
+) -> Result<MergeResult, String> {
+    let conn = open_conn(state.db_path.as_ref())?;
+    let record = find_record(&conn, &session_id)?;
+
+    let result = worktree::merge(&record.info(), no_ff)?;
+    if result.ok {
+        set_status(&conn, &session_id, "merged")?;
+    }
+
+    Ok(result)
+}
+
+#[tauri::command]
+pub async fn worktree_keep(state: State<'_, AppState>, session_id: String) -> Result<(), String> {
+    let conn = open_conn(state.db_path.as_ref())?;
+    find_record(&conn, &session_id)?;
+    set_status(&conn, &session_id, "kept")
+}
+
+#[tauri::command]
+pub async fn worktree_remove(
+    state: State<'_, AppState>,
+    session_id: String,
+    delete_branch: Option<bool>,
+) -> Result<(), String> {
+    let conn = open_conn(state.db_path.as_ref())?;
+    let record = find_record(&conn, &session_id)?;
+
+    if record.status == "active" {
+        let sessions = state.pty_manager.sessions();
+        let guard = sessions
+            .lock()
+            .map_err(|_| "failed to lock pty sessions for worktree remove".to_string())?;
+        if guard.contains_key(&session_id) {
+            return Err(format!("session is still running in worktree: {session_id}"));
+        }
+    }
+
+    worktree::remove(&record.info(), delete_branch.unwrap_or(false))?;
+    set_status(&conn, &session_id, "removed")
+}
+
+pub fn record_worktree(db_path: &Path, session_id: &str, info: &WorktreeInfo) -> Result<(), String> {
+    let conn = open_conn(db_path)?;
+
+    conn.execute(
+        "INSERT OR REPLACE INTO session_worktrees (session_id, name, repo_path, worktree_path, branch, base_oid) VALUES (?1, ?2, ?3, ?4, ?5, ?6)",
+        params![
+            session_id,
+            info.name,
+            info.repo_path,
+            info.worktree_path,
+            info.branch,
+            info.base_oid,
+        ],
+    )
+    .map_err(|err| format!("failed to record worktree: {err}"))?;
+
+    Ok(())
+}
+
+/// Marks the session's worktree as ended and returns the updated record so the
+/// caller can prompt for merge, keep or remove.
+pub fn end_worktree(db_path: &Path, session_id: &str) -> Result<WorktreeRecord, String> {
+    let conn = open_conn(db_path)?;
+
+    conn.execute(
+        "UPDATE session_worktrees SET status = 'ended', ended_at = CURRENT_TIMESTAMP WHERE session_id = ?1 AND status = 'active'",
+        params![session_id],
+    )
+    .map_err(|err| format!("failed to mark worktree ended: {err}"))?;
+
+    find_record(&conn, session_id)
+}
+
+fn find_record(conn: &Connection, session_id: &str) -> Result<WorktreeRecord, String> {
+    conn.query_row(
+        &format!("SELECT {WORKTREE_COLUMNS} FROM session_worktrees WHERE session_id = ?1"),
+        params![session_id],
+        map_record,
+    )
+    .optional()
+    .map_err(|err| format!("failed to query worktree: {err}"))?
+    .ok_or_else(|| format!("no worktree recorded for session: {session_id}"))
+}
+
+fn set_status(conn: &Connection, session_id: &str, status: &str) -> Result<(), String> {
+    conn.execute(
+        "UPDATE session_worktrees SET status = ?2, ended_at = COALESCE(ended_at, CURRENT_TIMESTAMP) WHERE session_id = ?1",
+        params![session_id, status],
+    )
+    .map_err(|err| format!("failed to update worktree status: {err}"))?;
+
+    Ok(())
+}
+
+fn map_record(row: &rusqlite::Row<'_>) -> rusqlite::Result<WorktreeRecord> {
+    Ok(WorktreeRecord {
+        session_id: row.get(0)?,
+        name: row.get(1)?,
+        repo_path: row.get(2)?,
+        worktree_path: row.get(3)?,
+        branch: row.get(4)?,
+        base_oid: row.get(5)?,
+        status: row.get(6)?,
+        created_at: row.get(7)?,
+        ended_at: row.get(8)?,
+    })
+}
+
+fn open_conn(db_path: &Path) -> Result<Connection, String> {
+    Connection::open(db_path).map_err(|err| format!("failed to open db: {err}"))
+}
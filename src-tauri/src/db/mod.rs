@@ -1,8 +1,10 @@
-use std::path::PathBuf;
+use std::path::{Path, PathBuf};
 
 use rusqlite::Connection;
 use tauri::{AppHandle, Manager};
 
+use crate::usage::pricing;
+
 pub fn init_db(app: &AppHandle) -> Result<PathBuf, String> {
     let app_dir = app
         .path()
@@ -13,11 +15,91 @@ pub fn init_db(app: &AppHandle) -> Result<PathBuf, String> {
         .map_err(|err| format!("failed to create app data dir: {err}"))?;
 
     let db_path = app_dir.join("aion.db");
-    let conn = Connection::open(&db_path).map_err(|err| format!("failed to open db: {err}"))?;
+    prepare(&db_path)?;
+
+    Ok(db_path)
+}
+
+/// Creates or upgrades the database at `db_path`. The database is switched
+/// to WAL, which persists in the file, so readers never wait on the usage
+/// writer's batches.
+pub fn prepare(db_path: &Path) -> Result<(), String> {
+    let conn = Connection::open(db_path).map_err(|err| format!("failed to open db: {err}"))?;
+    conn.pragma_update(None, "journal_mode", "WAL")
+        .map_err(|err| format!("failed to enable WAL: {err}"))?;
 
     let schema = include_str!("schema.sql");
     conn.execute_batch(schema)
         .map_err(|err| format!("failed to apply db schema: {err}"))?;
+    migrate(&conn)
+}
 
-    Ok(db_path)
+/// Columns added to `token_usage` after its first release. `schema.sql`
+/// creates them on fresh databases; older ones get them here.
+const TOKEN_USAGE_COLUMNS: &[(&str, &str)] = &[
+    ("source", "TEXT NOT NULL DEFAULT 'scraped'"),
+    ("model", "TEXT"),
+    ("project", "TEXT"),
+    ("cache_read_tokens", "INTEGER"),
+    ("cache_creation_tokens", "INTEGER"),
+    ("message_id", "TEXT"),
+    ("estimated", "INTEGER NOT NULL DEFAULT 0"),
+    ("cumulative_cost_usd", "REAL"),
+    ("cumulative_tokens_in", "INTEGER"),
+    ("cumulative_tokens_out", "INTEGER"),
+    ("cumulative_tokens_total", "INTEGER"),
+    ("cumulative_cache_read_tokens", "INTEGER"),
+    ("cumulative_cache_creation_tokens", "INTEGER"),
+];
+
+const USAGE_IMPORT_FILE_COLUMNS: &[(&str, &str)] = &[
+    ("context", "TEXT"),
+    ("errors", "INTEGER NOT NULL DEFAULT 0"),
+    ("last_error", "TEXT"),
+];
+
+pub fn migrate(conn: &Connection) -> Result<(), String> {
+    for (column, definition) in TOKEN_USAGE_COLUMNS {
+        ensure_column(conn, "token_usage", column, definition)?;
+    }
+    for (column, definition) in USAGE_IMPORT_FILE_COLUMNS {
+        ensure_column(conn, "usage_import_files", column, definition)?;
+    }
+
+    // Indexes on added columns can only be created once the columns exist.
+    conn.execute_batch(
+        "CREATE UNIQUE INDEX IF NOT EXISTS idx_usage_message ON token_usage(agent, message_id) WHERE message_id IS NOT NULL;
+         CREATE INDEX IF NOT EXISTS idx_usage_project ON token_usage(project);
+         CREATE INDEX IF NOT EXISTS idx_usage_agent_source ON token_usage(agent, source, captured_at);",
+    )
+    .map_err(|err| format!("failed to create token usage indexes: {err}"))?;
+
+    pricing::seed_defaults(conn)
+}
+
+/// Adds `column` to `table` unless it is already there.
+pub fn ensure_column(
+    conn: &Connection,
+    table: &str,
+    column: &str,
+    definition: &str,
+) -> Result<(), String> {
+    let mut statement = conn
+        .prepare(&format!("PRAGMA table_info({table})"))
+        .map_err(|err| format!("failed to read columns of {table}: {err}"))?;
+    let exists = statement
+        .query_map([], |row| row.get::<_, String>(1))
+        .map_err(|err| format!("failed to read columns of {table}: {err}"))?
+        .filter_map(Result::ok)
+        .any(|name| name == column);
+    if exists {
+        return Ok(());
+    }
+
+    conn.execute(
+        &format!("ALTER TABLE {table} ADD COLUMN {column} {definition}"),
+        [],
+    )
+    .map(|_| ())
+    .map_err(|err| format!("failed to add {table}.{column}: {err}"))
 }
@@ -8,7 +8,20 @@ CREATE TABLE IF NOT EXISTS token_usage (
     tokens_total INTEGER NOT NULL,
     duration_s   INTEGER,
     captured_at  DATETIME DEFAULT CURRENT_TIMESTAMP,
-    raw_output   TEXT
+    raw_output   TEXT,
+    source       TEXT NOT NULL DEFAULT 'scraped',
+    model        TEXT,
+    project      TEXT,
+    cache_read_tokens     INTEGER,
+    cache_creation_tokens INTEGER,
+    message_id   TEXT,
+    estimated    INTEGER NOT NULL DEFAULT 0,
+    cumulative_cost_usd              REAL,
+    cumulative_tokens_in             INTEGER,
+    cumulative_tokens_out            INTEGER,
+    cumulative_tokens_total          INTEGER,
+    cumulative_cache_read_tokens     INTEGER,
+    cumulative_cache_creation_tokens INTEGER
 );
 
 CREATE TABLE IF NOT EXISTS budgets (
@@ -20,3 +33,87 @@ CREATE TABLE IF NOT EXISTS budgets (
 
 CREATE INDEX IF NOT EXISTS idx_usage_date ON token_usage(captured_at);
 CREATE INDEX IF NOT EXISTS idx_usage_agent ON token_usage(agent);
+
+CREATE TABLE IF NOT EXISTS session_worktrees (
+    session_id    TEXT PRIMARY KEY,
+    name          TEXT NOT NULL,
+    repo_path     TEXT NOT NULL,
+    worktree_path TEXT NOT NULL,
+    branch        TEXT NOT NULL,
+    base_oid      TEXT NOT NULL,
+    status        TEXT NOT NULL DEFAULT 'active',
+    created_at    DATETIME DEFAULT CURRENT_TIMESTAMP,
+    ended_at      DATETIME
+);
+
+CREATE INDEX IF NOT EXISTS idx_worktrees_repo ON session_worktrees(repo_path);
+
+CREATE TABLE IF NOT EXISTS session_meta (
+    session_id  TEXT PRIMARY KEY,
+    notes       TEXT NOT NULL DEFAULT '',
+    updated_at  DATETIME DEFAULT CURRENT_TIMESTAMP
+);
+
+CREATE TABLE IF NOT EXISTS session_tags (
+    session_id  TEXT NOT NULL,
+    tag         TEXT NOT NULL,
+    created_at  DATETIME DEFAULT CURRENT_TIMESTAMP,
+    PRIMARY KEY (session_id, tag)
+);
+
+CREATE INDEX IF NOT EXISTS idx_session_tags_tag ON session_tags(tag);
+
+CREATE TABLE IF NOT EXISTS session_bookmarks (
+    id          INTEGER PRIMARY KEY AUTOINCREMENT,
+    session_id  TEXT NOT NULL,
+    label       TEXT NOT NULL DEFAULT '',
+    line        INTEGER NOT NULL,
+    created_at  DATETIME DEFAULT CURRENT_TIMESTAMP
+);
+
+CREATE INDEX IF NOT EXISTS idx_bookmarks_session ON session_bookmarks(session_id);
+
+CREATE TABLE IF NOT EXISTS test_runs (
+    id           INTEGER PRIMARY KEY AUTOINCREMENT,
+    session_id   TEXT NOT NULL,
+    project      TEXT NOT NULL,
+    git_head     TEXT,
+    runner       TEXT NOT NULL,
+    passed       INTEGER NOT NULL DEFAULT 0,
+    failed       INTEGER NOT NULL DEFAULT 0,
+    skipped      INTEGER NOT NULL DEFAULT 0,
+    failures     TEXT NOT NULL DEFAULT '[]',
+    duration_s   REAL,
+    captured_at  DATETIME DEFAULT CURRENT_TIMESTAMP
+);
+
+CREATE INDEX IF NOT EXISTS idx_test_runs_project ON test_runs(project, captured_at);
+CREATE INDEX IF NOT EXISTS idx_test_runs_session ON test_runs(session_id);
+
+CREATE TABLE IF NOT EXISTS usage_import_files (
+    path        TEXT PRIMARY KEY,
+    agent       TEXT NOT NULL,
+    position    INTEGER NOT NULL DEFAULT 0,
+    scanned_at  DATETIME DEFAULT CURRENT_TIMESTAMP,
+    context     TEXT,
+    errors      INTEGER NOT NULL DEFAULT 0,
+    last_error  TEXT
+);
+
+CREATE TABLE IF NOT EXISTS model_prices (
+    id             INTEGER PRIMARY KEY AUTOINCREMENT,
+    model          TEXT NOT NULL,
+    input          REAL NOT NULL DEFAULT 0,
+    output         REAL NOT NULL DEFAULT 0,
+    cache_read     REAL NOT NULL DEFAULT 0,
+    cache_write    REAL NOT NULL DEFAULT 0,
+    effective_from TEXT NOT NULL,
+    created_at     DATETIME DEFAULT CURRENT_TIMESTAMP,
+    UNIQUE(model, effective_from)
+);
+
+CREATE TABLE IF NOT EXISTS app_meta (
+    key         TEXT PRIMARY KEY,
+    value       TEXT NOT NULL,
+    updated_at  DATETIME DEFAULT CURRENT_TIMESTAMP
+);
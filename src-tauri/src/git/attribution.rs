@@ -0,0 +1,366 @@
+use std::collections::{BTreeSet, HashMap};
+use std::path::{Path, PathBuf};
+use std::sync::{Arc, Mutex};
+
+use chrono::{DateTime, Utc};
+use git2::{DiffOptions, Oid};
+use serde::Serialize;
+
+use crate::git::porcelain::{self, FileDiff};
+use crate::pty::resources;
+
+/// Ended sessions kept for `changes`; older ones are forgotten.
+const MAX_ENDED_SESSIONS: usize = 32;
+
+/// Tracks which repo files changed while each session was running. A change
+/// is attributed to the one active session in the repo that wrote it; files
+/// written by more than one session are reported as overlaps, and changes no
+/// single session can be pinned to are marked ambiguous on every candidate.
+#[derive(Clone, Default)]
+pub struct ChangeTracker {
+    sessions: Arc<Mutex<HashMap<String, TrackedSession>>>,
+}
+
+struct TrackedSession {
+    repo_root: PathBuf,
+    roots: Vec<PathBuf>,
+    pid: i64,
+    baseline_tree: String,
+    started_at: DateTime<Utc>,
+    ended_at: Option<DateTime<Utc>>,
+    touched: BTreeSet<String>,
+    ambiguous: BTreeSet<String>,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct SessionOverlap {
+    pub path: String,
+    pub repo_root: String,
+    pub session_ids: Vec<String>,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct SessionChangedFile {
+    pub path: String,
+    pub changed: bool,
+    /// Changed while other sessions were active in the repo and the writer
+    /// couldn't be told apart.
+    pub ambiguous: bool,
+    pub overlapping_sessions: Vec<String>,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct SessionChanges {
+    pub session_id: String,
+    pub repo_root: String,
+    pub baseline_tree: String,
+    pub started_at: String,
+    pub ended_at: Option<String>,
+    pub files: Vec<SessionChangedFile>,
+    pub diff: Vec<FileDiff>,
+}
+
+impl ChangeTracker {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn begin(&self, session_id: &str, repo_root: &str, baseline_tree: String) {
+        let Ok(mut guard) = self.sessions.lock() else {
+            return;
+        };
+
+        guard.insert(
+            session_id.to_string(),
+            TrackedSession {
+                repo_root: PathBuf::from(repo_root),
+                roots: porcelain::path_forms(Path::new(repo_root)),
+                pid: 0,
+                baseline_tree,
+                started_at: Utc::now(),
+                ended_at: None,
+                touched: BTreeSet::new(),
+                ambiguous: BTreeSet::new(),
+            },
+        );
+    }
+
+    /// Sets the session's root pid once the shell is spawned; its process
+    /// tree is what tells concurrent sessions' writes apart.
+    pub fn attach_process(&self, session_id: &str, pid: i64) {
+        if let Ok(mut guard) = self.sessions.lock() {
+            if let Some(session) = guard.get_mut(session_id) {
+                session.pid = pid;
+            }
+        }
+    }
+
+    /// Stops attributing changes to the session and returns its repo root.
+    pub fn end(&self, session_id: &str) -> Option<String> {
+        let Ok(mut guard) = self.sessions.lock() else {
+            return None;
+        };
+
+        let session = guard.get_mut(session_id)?;
+        session.ended_at.get_or_insert_with(Utc::now);
+        let repo_root = session.repo_root.to_string_lossy().to_string();
+
+        let mut ended = guard
+            .iter()
+            .filter_map(|(id, session)| session.ended_at.map(|ended_at| (ended_at, id.clone())))
+            .collect::<Vec<_>>();
+        if ended.len() > MAX_ENDED_SESSIONS {
+            ended.sort();
+            for (_, id) in &ended[..ended.len() - MAX_ENDED_SESSIONS] {
+                guard.remove(id);
+            }
+        }
+
+        Some(repo_root)
+    }
+
+    pub fn is_tracking(&self, repo_root: &str) -> bool {
+        let Ok(guard) = self.sessions.lock() else {
+            return false;
+        };
+
+        guard
+            .values()
+            .any(|session| session.ended_at.is_none() && session.repo_root == Path::new(repo_root))
+    }
+
+    /// Records changed paths against the active session that wrote them and
+    /// returns the paths that are now touched by more than one active
+    /// session.
+    pub fn record(&self, paths: &[PathBuf]) -> Vec<SessionOverlap> {
+        let Ok(mut guard) = self.sessions.lock() else {
+            return Vec::new();
+        };
+
+        let mut trees = HashMap::<String, Vec<i64>>::new();
+        let mut overlaps = Vec::new();
+
+        for path in paths {
+            let candidates = guard
+                .iter()
+                .filter(|(_, session)| session.ended_at.is_none())
+                .filter_map(|(session_id, session)| {
+                    let relative = porcelain::strip_roots(path, &session.roots)?;
+                    Some((session_id.clone(), relative.to_string_lossy().to_string()))
+                })
+                .collect::<Vec<_>>();
+
+            let writer = match candidates.as_slice() {
+                [] => continue,
+                [(session_id, _)] => Some(session_id.clone()),
+                _ => {
+                    for (session_id, _) in &candidates {
+                        trees.entry(session_id.clone()).or_insert_with(|| {
+                            let pid = guard[session_id].pid;
+                            if pid > 0 {
+                                resources::process_tree(pid)
+                            } else {
+                                Vec::new()
+                            }
+                        });
+                    }
+                    find_writer(&guard, &trees, &candidates)
+                }
+            };
+
+            let Some(writer) = writer else {
+                for (session_id, relative) in candidates {
+                    if let Some(session) = guard.get_mut(&session_id) {
+                        session.ambiguous.insert(relative);
+                    }
+                }
+                continue;
+            };
+
+            let Some(relative) = candidates
+                .iter()
+                .find(|(session_id, _)| *session_id == writer)
+                .map(|(_, relative)| relative.clone())
+            else {
+                continue;
+            };
+            let Some(session) = guard.get_mut(&writer) else {
+                continue;
+            };
+            if !session.touched.insert(relative.clone()) {
+                continue;
+            }
+            let repo_root = session.repo_root.clone();
+
+            let mut touched_by = guard
+                .iter()
+                .filter(|(_, other)| {
+                    other.ended_at.is_none()
+                        && other.repo_root == repo_root
+                        && other.touched.contains(&relative)
+                })
+                .map(|(session_id, _)| session_id.clone())
+                .collect::<Vec<_>>();
+            if touched_by.len() > 1 {
+                touched_by.sort();
+                overlaps.push(SessionOverlap {
+                    path: relative,
+                    repo_root: repo_root.to_string_lossy().to_string(),
+                    session_ids: touched_by,
+                });
+            }
+        }
+
+        overlaps
+    }
+
+    pub fn changes(&self, session_id: &str) -> Result<SessionChanges, String> {
+        let (repo_root, baseline_tree, started_at, ended_at, touched, ambiguous, overlaps) = {
+            let guard = self
+                .sessions
+                .lock()
+                .map_err(|_| "failed to lock change tracker".to_string())?;
+            let session = guard
+                .get(session_id)
+                .ok_or_else(|| format!("no change tracking for session: {session_id}"))?;
+
+            let mut overlaps = HashMap::<String, Vec<String>>::new();
+            for (other_id, other) in guard.iter() {
+                if other_id == session_id || other.repo_root != session.repo_root {
+                    continue;
+                }
+                for path in session.touched.intersection(&other.touched) {
+                    overlaps.entry(path.clone()).or_default().push(other_id.clone());
+                }
+            }
+
+            (
+                session.repo_root.clone(),
+                session.baseline_tree.clone(),
+                session.started_at,
+                session.ended_at,
+                session.touched.clone(),
+                session.ambiguous.clone(),
+                overlaps,
+            )
+        };
+
+        let repo_path = repo_root.to_string_lossy().to_string();
+        let repo = porcelain::open_repo(&repo_path)?;
+        let ambiguous = &ambiguous - &touched;
+        let touched = touched
+            .union(&ambiguous)
+            .filter(|path| !repo.status_should_ignore(Path::new(path)).unwrap_or(false))
+            .cloned()
+            .collect::<Vec<_>>();
+
+        let diff = if touched.is_empty() {
+            Vec::new()
+        } else {
+            let tree_oid = Oid::from_str(&baseline_tree)
+                .map_err(|err| format!("invalid baseline tree '{baseline_tree}': {err}"))?;
+            let tree = repo
+                .find_tree(tree_oid)
+                .map_err(|err| format!("failed to find baseline tree: {err}"))?;
+
+            let mut options = DiffOptions::new();
+            options
+                .include_untracked(true)
+                .recurse_untracked_dirs(true)
+                .show_untracked_content(true)
+                .disable_pathspec_match(true);
+            for path in &touched {
+                options.pathspec(path);
+            }
+
+            let diff = repo
+                .diff_tree_to_workdir(Some(&tree), Some(&mut options))
+                .map_err(|err| format!("failed to diff session baseline: {err}"))?;
+            porcelain::collect_file_diffs(&diff)?
+        };
+
+        let files = touched
+            .into_iter()
+            .map(|path| {
+                let mut overlapping_sessions = overlaps.get(&path).cloned().unwrap_or_default();
+                overlapping_sessions.sort();
+                SessionChangedFile {
+                    changed: diff.iter().any(|file| file.path == path),
+                    ambiguous: ambiguous.contains(&path),
+                    overlapping_sessions,
+                    path,
+                }
+            })
+            .collect();
+
+        Ok(SessionChanges {
+            session_id: session_id.to_string(),
+            repo_root: repo_path,
+            baseline_tree,
+            started_at: started_at.to_rfc3339(),
+            ended_at: ended_at.map(|ended| ended.to_rfc3339()),
+            files,
+            diff,
+        })
+    }
+}
+
+/// Picks the one candidate session that wrote the file: the only one whose
+/// process tree still has it open, otherwise the one with a process working
+/// deepest inside the file's directory. `None` when that doesn't single out
+/// one session.
+fn find_writer(
+    sessions: &HashMap<String, TrackedSession>,
+    trees: &HashMap<String, Vec<i64>>,
+    candidates: &[(String, String)],
+) -> Option<String> {
+    let pids = |session_id: &String| trees.get(session_id).map(Vec::as_slice).unwrap_or_default();
+
+    let holding = candidates
+        .iter()
+        .filter(|(session_id, relative)| {
+            let roots = &sessions[session_id].roots;
+            pids(session_id).iter().any(|pid| {
+                let Ok(fds) = std::fs::read_dir(format!("/proc/{pid}/fd")) else {
+                    return false;
+                };
+                fds.flatten().any(|fd| {
+                    std::fs::read_link(fd.path()).is_ok_and(|target| {
+                        porcelain::strip_roots(&target, roots) == Some(Path::new(relative))
+                    })
+                })
+            })
+        })
+        .collect::<Vec<_>>();
+    if let [(session_id, _)] = holding.as_slice() {
+        return Some(session_id.clone());
+    }
+
+    let depths = candidates
+        .iter()
+        .filter_map(|(session_id, relative)| {
+            let roots = &sessions[session_id].roots;
+            pids(session_id)
+                .iter()
+                .filter_map(|pid| std::fs::read_link(format!("/proc/{pid}/cwd")).ok())
+                .filter_map(|cwd| {
+                    let cwd = porcelain::strip_roots(&cwd, roots)?;
+                    Path::new(relative)
+                        .starts_with(cwd)
+                        .then(|| cwd.components().count())
+                })
+                .max()
+                .map(|depth| (depth, session_id))
+        })
+        .collect::<Vec<_>>();
+    let deepest = depths.iter().map(|(depth, _)| *depth).max()?;
+    match depths
+        .iter()
+        .filter(|(depth, _)| *depth == deepest)
+        .collect::<Vec<_>>()
+        .as_slice()
+    {
+        [(_, session_id)] => Some((*session_id).clone()),
+        _ => None,
+    }
+}
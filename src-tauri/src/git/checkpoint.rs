@@ -0,0 +1,223 @@
+use chrono::{DateTime, Utc};
+use git2::{build::CheckoutBuilder, DiffOptions, IndexAddOption, Oid, Repository, Signature};
+use serde::Serialize;
+
+use crate::git::porcelain::{self, FileDiff};
+
+const CHECKPOINT_NAMESPACE: &str = "refs/aion/checkpoints";
+
+#[derive(Debug, Clone, Serialize)]
+pub struct CheckpointInfo {
+    pub session_id: String,
+    pub index: u32,
+    pub reference: String,
+    pub oid: String,
+    pub tree_oid: String,
+    pub head_oid: Option<String>,
+    pub label: String,
+    pub created_at: String,
+}
+
+/// Snapshots the working tree, untracked files included, into a commit stored
+/// under `refs/aion/checkpoints/<session>/<n>`. Neither HEAD nor the on-disk
+/// index is touched. When `skip_unchanged` is set and the tree matches the
+/// latest checkpoint for the session, no new checkpoint is written.
+pub fn create(
+    path: &str,
+    session_id: &str,
+    label: &str,
+    skip_unchanged: bool,
+) -> Result<Option<CheckpointInfo>, String> {
+    let repo = porcelain::open_repo(path)?;
+    let tree_oid = snapshot_tree(&repo)?;
+
+    let existing = list_in_repo(&repo, session_id)?;
+    if skip_unchanged {
+        if let Some(latest) = existing.last() {
+            if latest.tree_oid == tree_oid.to_string() {
+                return Ok(None);
+            }
+        }
+    }
+
+    let index = existing.last().map(|latest| latest.index + 1).unwrap_or(1);
+    let tree = repo
+        .find_tree(tree_oid)
+        .map_err(|err| format!("failed to find checkpoint tree: {err}"))?;
+    let head_commit = repo.head().ok().and_then(|head| head.peel_to_commit().ok());
+    let parents = head_commit.iter().collect::<Vec<_>>();
+
+    let signature = repo
+        .signature()
+        .or_else(|_| Signature::now("Aion", "aion@local"))
+        .map_err(|err| format!("failed to build checkpoint signature: {err}"))?;
+
+    let message = format!("aion checkpoint {session_id} #{index}: {label}");
+    let oid = repo
+        .commit(None, &signature, &signature, &message, &tree, &parents)
+        .map_err(|err| format!("failed to write checkpoint commit: {err}"))?;
+
+    let reference = checkpoint_ref(session_id, index);
+    repo.reference(&reference, oid, false, &message)
+        .map_err(|err| format!("failed to create checkpoint ref '{reference}': {err}"))?;
+
+    Ok(Some(CheckpointInfo {
+        session_id: session_id.to_string(),
+        index,
+        reference,
+        oid: oid.to_string(),
+        tree_oid: tree_oid.to_string(),
+        head_oid: head_commit.map(|commit| commit.id().to_string()),
+        label: label.to_string(),
+        created_at: Utc::now().to_rfc3339(),
+    }))
+}
+
+/// Writes the current working tree as a tree object without recording a
+/// checkpoint and returns its oid.
+pub fn snapshot(path: &str) -> Result<String, String> {
+    let repo = porcelain::open_repo(path)?;
+    snapshot_tree(&repo).map(|oid| oid.to_string())
+}
+
+pub fn list(path: &str, session_id: &str) -> Result<Vec<CheckpointInfo>, String> {
+    let repo = porcelain::open_repo(path)?;
+    list_in_repo(&repo, session_id)
+}
+
+/// Diffs the checkpoint against the current working tree, so added lines are
+/// what changed since the checkpoint was taken.
+pub fn diff(path: &str, session_id: &str, index: u32) -> Result<Vec<FileDiff>, String> {
+    let repo = porcelain::open_repo(path)?;
+    let tree = find_checkpoint(&repo, session_id, index)?
+        .tree()
+        .map_err(|err| format!("failed to read checkpoint tree: {err}"))?;
+
+    let mut options = DiffOptions::new();
+    options
+        .include_untracked(true)
+        .recurse_untracked_dirs(true)
+        .show_untracked_content(true);
+
+    let diff = repo
+        .diff_tree_to_workdir(Some(&tree), Some(&mut options))
+        .map_err(|err| format!("failed to diff checkpoint to workdir: {err}"))?;
+
+    porcelain::collect_file_diffs(&diff)
+}
+
+/// Restores the working tree to a checkpoint. The current state is
+/// checkpointed first so a restore can itself be undone. The index is left as
+/// it is.
+pub fn restore(path: &str, session_id: &str, index: u32) -> Result<Option<CheckpointInfo>, String> {
+    let backup = create(path, session_id, &format!("before restore to #{index}"), true)?;
+
+    let repo = porcelain::open_repo(path)?;
+    let commit = find_checkpoint(&repo, session_id, index)?;
+
+    let mut checkout = CheckoutBuilder::new();
+    checkout
+        .force()
+        .remove_untracked(true)
+        .recreate_missing(true)
+        .update_index(false);
+
+    repo.checkout_tree(commit.as_object(), Some(&mut checkout))
+        .map_err(|err| format!("failed to restore checkpoint #{index}: {err}"))?;
+
+    Ok(backup)
+}
+
+fn snapshot_tree(repo: &Repository) -> Result<Oid, String> {
+    // The repository index is only modified in memory here and never written
+    // back, so the user's staging area is left untouched.
+    let mut index = repo
+        .index()
+        .map_err(|err| format!("failed to open index for checkpoint: {err}"))?;
+
+    index
+        .add_all(["*"].iter(), IndexAddOption::DEFAULT, None)
+        .map_err(|err| format!("failed to collect worktree for checkpoint: {err}"))?;
+    index
+        .update_all(["*"].iter(), None)
+        .map_err(|err| format!("failed to collect removals for checkpoint: {err}"))?;
+
+    index
+        .write_tree()
+        .map_err(|err| format!("failed to write checkpoint tree: {err}"))
+}
+
+fn list_in_repo(repo: &Repository, session_id: &str) -> Result<Vec<CheckpointInfo>, String> {
+    let glob = format!("{CHECKPOINT_NAMESPACE}/{}/*", session_slug(session_id));
+    let references = repo
+        .references_glob(&glob)
+        .map_err(|err| format!("failed to list checkpoints: {err}"))?;
+
+    let mut checkpoints = Vec::new();
+    for reference in references {
+        let reference = reference.map_err(|err| format!("failed to read checkpoint ref: {err}"))?;
+        let Some(name) = reference.name().map(str::to_string) else {
+            continue;
+        };
+        let Some(index) = name
+            .rsplit('/')
+            .next()
+            .and_then(|tail| tail.parse::<u32>().ok())
+        else {
+            continue;
+        };
+        let commit = reference
+            .peel_to_commit()
+            .map_err(|err| format!("failed to resolve checkpoint '{name}': {err}"))?;
+
+        let label = commit
+            .summary()
+            .and_then(|summary| summary.split_once(": ").map(|(_, label)| label.to_string()))
+            .unwrap_or_default();
+        let created_at = DateTime::<Utc>::from_timestamp(commit.time().seconds(), 0)
+            .unwrap_or_else(Utc::now)
+            .to_rfc3339();
+
+        checkpoints.push(CheckpointInfo {
+            session_id: session_id.to_string(),
+            index,
+            reference: name,
+            oid: commit.id().to_string(),
+            tree_oid: commit.tree_id().to_string(),
+            head_oid: commit.parent_ids().next().map(|oid| oid.to_string()),
+            label,
+            created_at,
+        });
+    }
+
+    checkpoints.sort_by_key(|checkpoint| checkpoint.index);
+    Ok(checkpoints)
+}
+
+fn find_checkpoint<'repo>(
+    repo: &'repo Repository,
+    session_id: &str,
+    index: u32,
+) -> Result<git2::Commit<'repo>, String> {
+    let reference = checkpoint_ref(session_id, index);
+    repo.find_reference(&reference)
+        .and_then(|reference| reference.peel_to_commit())
+        .map_err(|err| format!("failed to find checkpoint '{reference}': {err}"))
+}
+
+fn checkpoint_ref(session_id: &str, index: u32) -> String {
+    format!("{CHECKPOINT_NAMESPACE}/{}/{index}", session_slug(session_id))
+}
+
+fn session_slug(session_id: &str) -> String {
+    session_id
+        .chars()
+        .map(|ch| {
+            if ch.is_ascii_alphanumeric() || ch == '-' || ch == '_' {
+                ch
+            } else {
+                '_'
+            }
+        })
+        .collect()
+}
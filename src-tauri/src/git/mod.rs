@@ -1,2 +1,5 @@
+pub mod attribution;
+pub mod checkpoint;
 pub mod porcelain;
 pub mod watcher;
+pub mod worktree;
@@ -33,6 +33,13 @@ pub struct DiffResult {
     pub hunks: Vec<DiffHunk>,
 }
 
+#[derive(Debug, Clone, Serialize)]
+pub struct FileDiff {
+    pub path: String,
+    pub status: String,
+    pub hunks: Vec<DiffHunk>,
+}
+
 #[derive(Debug, Clone, Serialize)]
 pub struct CommitInfo {
     pub oid: String,
@@ -721,16 +728,116 @@ pub fn discover_repo_root(path: &str) -> Result<String, String> {
     Ok(root.to_string_lossy().to_string())
 }
 
-fn open_repo(path: &str) -> Result<Repository, String> {
+pub(crate) fn open_repo(path: &str) -> Result<Repository, String> {
     Repository::discover(path).map_err(|err| format!("failed to discover repository: {err}"))
 }
 
-fn repo_root(repo: &Repository, fallback: &str) -> PathBuf {
+pub(crate) fn repo_root(repo: &Repository, fallback: &str) -> PathBuf {
     repo.workdir()
         .map(Path::to_path_buf)
         .unwrap_or_else(|| PathBuf::from(fallback))
 }
 
+/// `path` plus its canonical form; some watcher backends (FSEvents) report
+/// canonical paths, so event paths are matched against both.
+pub(crate) fn path_forms(path: &Path) -> Vec<PathBuf> {
+    let mut forms = vec![path.to_path_buf()];
+    if let Ok(canonical) = std::fs::canonicalize(path) {
+        if canonical != path {
+            forms.push(canonical);
+        }
+    }
+    forms
+}
+
+/// `path` relative to the first of `roots` that contains it.
+pub(crate) fn strip_roots<'a>(path: &'a Path, roots: &[PathBuf]) -> Option<&'a Path> {
+    roots.iter().find_map(|root| path.strip_prefix(root).ok())
+}
+
+/// Walks a multi-file diff into per-file hunks, keyed by the new path (or the
+/// old path for deletions).
+pub(crate) fn collect_file_diffs(diff: &git2::Diff<'_>) -> Result<Vec<FileDiff>, String> {
+    let files: RefCell<Vec<FileDiff>> = RefCell::new(Vec::new());
+
+    diff.foreach(
+        &mut |delta, _progress| {
+            let path = delta
+                .new_file()
+                .path()
+                .or_else(|| delta.old_file().path())
+                .map(|path| path.to_string_lossy().to_string())
+                .unwrap_or_default();
+            files.borrow_mut().push(FileDiff {
+                path,
+                status: delta_to_label(delta.status()).to_string(),
+                hunks: Vec::new(),
+            });
+            true
+        },
+        None,
+        Some(&mut |_delta, hunk| {
+            let mut files = files.borrow_mut();
+            let header = String::from_utf8_lossy(hunk.header()).trim().to_string();
+            if let Some(file) = files.last_mut() {
+                file.hunks.push(DiffHunk {
+                    header,
+                    lines: Vec::new(),
+                });
+            }
+            true
+        }),
+        Some(&mut |_delta, _hunk, line| {
+            let mut files = files.borrow_mut();
+            let Some(file) = files.last_mut() else {
+                return true;
+            };
+            if file.hunks.is_empty() {
+                file.hunks.push(DiffHunk {
+                    header: "@@".to_string(),
+                    lines: Vec::new(),
+                });
+            }
+
+            let line_type = match line.origin() {
+                '+' => "add",
+                '-' => "del",
+                _ => "context",
+            }
+            .to_string();
+
+            let content = String::from_utf8_lossy(line.content())
+                .trim_end_matches('\n')
+                .to_string();
+
+            if let Some(last) = file.hunks.last_mut() {
+                last.lines.push(DiffLine {
+                    r#type: line_type,
+                    content,
+                    old_ln: line.old_lineno(),
+                    new_ln: line.new_lineno(),
+                });
+            }
+
+            true
+        }),
+    )
+    .map_err(|err| format!("failed to walk diff: {err}"))?;
+
+    Ok(files.into_inner())
+}
+
+fn delta_to_label(delta: git2::Delta) -> &'static str {
+    match delta {
+        git2::Delta::Added | git2::Delta::Copied => "A",
+        git2::Delta::Deleted => "D",
+        git2::Delta::Renamed => "R",
+        git2::Delta::Untracked => "?",
+        git2::Delta::Typechange => "T",
+        _ => "M",
+    }
+}
+
 fn status_to_label(status: Status) -> &'static str {
     if status.is_wt_new() {
         "?"
@@ -1,14 +1,26 @@
-use std::path::Path;
+use std::path::{Path, PathBuf};
 use std::sync::{Arc, Mutex};
 use std::time::{Duration, Instant};
 
-use notify::{Event, RecommendedWatcher, RecursiveMode, Result as NotifyResult, Watcher};
+use git2::Repository;
+use notify::event::{AccessKind, AccessMode};
+use notify::{Event, EventKind, RecommendedWatcher, RecursiveMode, Result as NotifyResult, Watcher};
 use tauri::{AppHandle, Emitter};
 
-pub fn start_watching(path: &Path, app_handle: AppHandle) -> Result<RecommendedWatcher, String> {
+use crate::git::attribution::ChangeTracker;
+use crate::git::porcelain;
+
+pub fn start_watching(
+    path: &Path,
+    app_handle: AppHandle,
+    tracker: ChangeTracker,
+) -> Result<RecommendedWatcher, String> {
     let last_emit = Arc::new(Mutex::new(Instant::now() - Duration::from_millis(300)));
     let emit_state = Arc::clone(&last_emit);
 
+    let repo = Mutex::new(porcelain::open_repo(&path.to_string_lossy())?);
+    let roots = porcelain::path_forms(path);
+
     let mut watcher = notify::recommended_watcher(move |event: NotifyResult<Event>| {
         let Ok(event) = event else {
             return;
@@ -22,6 +34,31 @@ pub fn start_watching(path: &Path, app_handle: AppHandle) -> Result<RecommendedW
             return;
         }
 
+        // Build output and other ignored files neither change `git status`
+        // nor count as a session's changes.
+        let paths = event
+            .paths
+            .iter()
+            .filter(|path| !is_ignored(&repo, &roots, path))
+            .cloned()
+            .collect::<Vec<PathBuf>>();
+        if paths.is_empty() {
+            return;
+        }
+
+        // Attribution sees every write, not just the debounced ones.
+        if matches!(
+            event.kind,
+            EventKind::Create(_)
+                | EventKind::Modify(_)
+                | EventKind::Remove(_)
+                | EventKind::Access(AccessKind::Close(AccessMode::Write))
+        ) {
+            for overlap in tracker.record(&paths) {
+                let _ = app_handle.emit("session:overlap", overlap);
+            }
+        }
+
         let mut guard = match emit_state.lock() {
             Ok(guard) => guard,
             Err(_) => return,
@@ -43,3 +80,17 @@ pub fn start_watching(path: &Path, app_handle: AppHandle) -> Result<RecommendedW
 
     Ok(watcher)
 }
+
+fn is_ignored(repo: &Mutex<Repository>, roots: &[PathBuf], path: &Path) -> bool {
+    let Some(relative) = porcelain::strip_roots(path, roots) else {
+        return false;
+    };
+    if relative.as_os_str().is_empty() {
+        return false;
+    }
+
+    repo.lock()
+        .ok()
+        .and_then(|repo| repo.status_should_ignore(relative).ok())
+        .unwrap_or(false)
+}
@@ -0,0 +1,138 @@
+use std::path::Path;
+
+use git2::{BranchType, WorktreeAddOptions, WorktreePruneOptions};
+use serde::Serialize;
+
+use crate::git::porcelain::{self, MergeResult};
+
+#[derive(Debug, Clone, Serialize)]
+pub struct WorktreeInfo {
+    pub name: String,
+    pub repo_path: String,
+    pub worktree_path: String,
+    pub branch: String,
+    pub base_oid: String,
+}
+
+/// Creates a worktree for `session_id` on a fresh `aion/<name>` branch cut from
+/// the current HEAD of the repository containing `path`. Worktrees live under
+/// `managed_dir/<repo name>/<name>`. Session ids sharing a prefix, or a
+/// branch kept from an earlier session, get a numbered name instead.
+pub fn create(path: &str, managed_dir: &Path, session_id: &str) -> Result<WorktreeInfo, String> {
+    let repo = porcelain::open_repo(path)?;
+    let root = porcelain::repo_root(&repo, path);
+
+    let head_commit = repo
+        .head()
+        .and_then(|head| head.peel_to_commit())
+        .map_err(|err| format!("failed to resolve HEAD for worktree: {err}"))?;
+
+    let repo_dir_name = root
+        .file_name()
+        .and_then(|name| name.to_str())
+        .unwrap_or("repo");
+    let parent_dir = managed_dir.join(repo_dir_name);
+    std::fs::create_dir_all(&parent_dir)
+        .map_err(|err| format!("failed to create worktree dir: {err}"))?;
+
+    let base_name = worktree_name(session_id);
+    let name = (1..)
+        .map(|n| match n {
+            1 => base_name.clone(),
+            n => format!("{base_name}-{n}"),
+        })
+        .find(|name| {
+            repo.find_branch(&format!("aion/{name}"), BranchType::Local).is_err()
+                && repo.find_worktree(name).is_err()
+                && !parent_dir.join(name).exists()
+        })
+        .unwrap_or(base_name);
+    let branch_name = format!("aion/{name}");
+    let worktree_path = parent_dir.join(&name);
+
+    let branch = repo
+        .branch(&branch_name, &head_commit, false)
+        .map_err(|err| format!("failed to create worktree branch '{branch_name}': {err}"))?;
+
+    let mut options = WorktreeAddOptions::new();
+    options.reference(Some(branch.get()));
+
+    if let Err(err) = repo.worktree(&name, &worktree_path, Some(&options)) {
+        let _ = branch.into_reference().delete();
+        return Err(format!("failed to add worktree '{name}': {err}"));
+    }
+
+    Ok(WorktreeInfo {
+        name,
+        repo_path: root.to_string_lossy().to_string(),
+        worktree_path: worktree_path.to_string_lossy().to_string(),
+        branch: branch_name,
+        base_oid: head_commit.id().to_string(),
+    })
+}
+
+/// Merges the worktree branch into whatever is checked out in the main repo.
+/// Uncommitted changes in the worktree are not carried over, so they are
+/// rejected up front instead of being silently left behind.
+pub fn merge(info: &WorktreeInfo, no_ff: Option<bool>) -> Result<MergeResult, String> {
+    if Path::new(&info.worktree_path).exists() {
+        let pending = porcelain::status(&info.worktree_path)?;
+        if !pending.is_empty() {
+            return Err(format!(
+                "worktree '{}' has {} uncommitted change(s); commit or discard them before merging",
+                info.name,
+                pending.len()
+            ));
+        }
+    }
+
+    porcelain::merge(&info.repo_path, info.branch.clone(), no_ff)
+}
+
+/// Prunes the worktree (deleting its working directory) and optionally the
+/// branch it was created on. A branch with no commits beyond its base holds
+/// nothing worth keeping and is deleted either way.
+pub fn remove(info: &WorktreeInfo, delete_branch: bool) -> Result<(), String> {
+    let repo = porcelain::open_repo(&info.repo_path)?;
+
+    match repo.find_worktree(&info.name) {
+        Ok(worktree) => {
+            let mut prune = WorktreePruneOptions::new();
+            prune.valid(true).locked(true).working_tree(true);
+            worktree
+                .prune(Some(&mut prune))
+                .map_err(|err| format!("failed to prune worktree '{}': {err}", info.name))?;
+        }
+        Err(err) if err.code() == git2::ErrorCode::NotFound => {}
+        Err(err) => return Err(format!("failed to find worktree '{}': {err}", info.name)),
+    }
+
+    if let Ok(branch) = repo.find_branch(&info.branch, BranchType::Local) {
+        let unchanged = branch
+            .get()
+            .target()
+            .is_some_and(|oid| oid.to_string() == info.base_oid);
+        if delete_branch || unchanged {
+            branch
+                .into_reference()
+                .delete()
+                .map_err(|err| format!("failed to delete branch '{}': {err}", info.branch))?;
+        }
+    }
+
+    Ok(())
+}
+
+fn worktree_name(session_id: &str) -> String {
+    let slug = session_id
+        .chars()
+        .filter(|ch| ch.is_ascii_alphanumeric() || *ch == '-')
+        .take(12)
+        .collect::<String>();
+
+    if slug.is_empty() {
+        format!("aion-{}", uuid::Uuid::new_v4().simple())
+    } else {
+        format!("aion-{slug}")
+    }
+}
@@ -3,9 +3,18 @@ mod db;
 mod git;
 mod pty;
 mod state;
+mod usage;
 
 use tauri::Manager;
 
+/// Internals driven by `benches/`, which can only reach public items.
+#[doc(hidden)]
+pub mod bench {
+    pub use crate::db::prepare as prepare_db;
+    pub use crate::pty::lines::LineSplitter;
+    pub use crate::pty::scraper::TokenScraper;
+}
+
 #[cfg_attr(mobile, tauri::mobile_entry_point)]
 pub fn run() {
     env_logger::init();
@@ -23,8 +32,16 @@ pub fn run() {
             commands::pty::pty_spawn,
             commands::pty::pty_write,
             commands::pty::pty_resize,
+            commands::pty::pty_set_encoding,
             commands::pty::pty_kill,
             commands::pty::pty_list,
+            commands::pty::pty_scrollback,
+            commands::pty::pty_resource_usage,
+            commands::pty::pty_log_path,
+            commands::pty::pty_diagnostics,
+            commands::pty::resolve_path_link,
+            commands::editor::open_in_editor,
+            commands::pty::pty_export_transcript,
             commands::git::git_status,
             commands::git::git_diff,
             commands::git::git_stage,
@@ -44,7 +61,35 @@ pub fn run() {
             commands::git::git_watch_start,
             commands::git::git_watch_stop,
             commands::git::git_stash,
+            commands::git::git_checkpoint_create,
+            commands::git::git_checkpoint_list,
+            commands::git::git_checkpoint_diff,
+            commands::git::git_checkpoint_restore,
+            commands::git::session_changes,
+            commands::worktree::worktree_list,
+            commands::worktree::worktree_merge,
+            commands::worktree::worktree_keep,
+            commands::worktree::worktree_remove,
+            commands::annotations::session_annotations,
+            commands::annotations::session_notes_set,
+            commands::annotations::session_tag_add,
+            commands::annotations::session_tag_remove,
+            commands::annotations::session_tags_list,
+            commands::annotations::session_bookmark_add,
+            commands::annotations::session_bookmark_update,
+            commands::annotations::session_bookmark_remove,
+            commands::annotations::session_bookmark_jump,
+            commands::test_runs::query_test_runs,
             commands::tokens::query_usage,
+            commands::tokens::query_usage_summary,
+            commands::pricing::pricing_list,
+            commands::pricing::pricing_set,
+            commands::pricing::pricing_remove,
+            commands::pricing::usage_recompute_costs,
+            commands::tokens::usage_repair_cumulative,
+            commands::tokens::import_status,
+            commands::tokens::scraper_rule_errors,
+            commands::tokens::scraper_test_rule,
             commands::tokens::query_budget,
             commands::tokens::set_budget,
             commands::settings::resolve_shell,
@@ -0,0 +1,45 @@
+use once_cell::sync::Lazy;
+use regex::Regex;
+
+use super::{banner_text, parse_compact_int, AgentParser, ParsedUsage};
+
+static TOKENS_RE: Lazy<Regex> = Lazy::new(|| {
+    Regex::new(r"Tokens:\s*([\d.,]+[kKmM]?) sent,(?:.*?,)?\s*([\d.,]+[kKmM]?) received\.\s*Cost:\s*\$([\d.]+) message")
+        .expect("valid TOKENS_RE")
+});
+static BANNER_RE: Lazy<Regex> =
+    Lazy::new(|| Regex::new(r"^Aider v\d+\.\d+").expect("valid BANNER_RE"));
+
+/// Aider reports usage after every message:
+/// `Tokens: 2.3k sent, 156 received. Cost: $0.0081 message, $0.04 session.`
+/// Each line is stored as its own record with the per-message cost.
+#[derive(Default)]
+pub struct AiderParser;
+
+impl AgentParser for AiderParser {
+    fn agent(&self) -> &str {
+        "aider"
+    }
+
+    fn detect(&self, line: &str) -> bool {
+        BANNER_RE.is_match(banner_text(line))
+    }
+
+    fn parse_line(&mut self, line: &str) -> Option<ParsedUsage> {
+        let captures = TOKENS_RE.captures(line)?;
+        let tokens_in = parse_compact_int(&captures[1])?;
+        let tokens_out = parse_compact_int(&captures[2])?;
+
+        Some(ParsedUsage {
+            cost_usd: captures[3].parse().ok()?,
+            tokens_in,
+            tokens_out,
+            tokens_total: tokens_in + tokens_out,
+            duration_s: None,
+            raw_lines: vec![line.trim().to_string()],
+            ..ParsedUsage::default()
+        })
+    }
+
+    fn reset(&mut self) {}
+}
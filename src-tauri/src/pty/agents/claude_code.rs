@@ -0,0 +1,203 @@
+use once_cell::sync::Lazy;
+use regex::Regex;
+
+use super::{
+    banner_text, parse_compact_int, parse_duration_s, parse_int_with_commas, AgentParser, ParsedUsage,
+};
+
+static COST_RE: Lazy<Regex> =
+    Lazy::new(|| Regex::new(r"Total cost:\s*\$(\d+(?:\.\d+)?)").expect("valid COST_RE"));
+static TOKENS_RE: Lazy<Regex> = Lazy::new(|| {
+    Regex::new(r"Total tokens:\s*([\d,]+)\s*\(in:\s*([\d.,KM]+),\s*out:\s*([\d.,KM]+)\)")
+        .expect("valid TOKENS_RE")
+});
+static DURATION_RE: Lazy<Regex> =
+    Lazy::new(|| Regex::new(r"Duration:\s*(?:(\d+)m)?\s*(\d+)s").expect("valid DURATION_RE"));
+static WALL_RE: Lazy<Regex> =
+    Lazy::new(|| Regex::new(r"Total duration \(wall\):\s+(.+?)\s*$").expect("valid WALL_RE"));
+/// `✻ Welcome to Claude Code!` in older releases, `Claude Code v2.0.14`
+/// beside the logo in newer ones.
+static BANNER_RE: Lazy<Regex> = Lazy::new(|| {
+    Regex::new(r"^(?:Welcome to Claude Code!|Claude Code v\d+\.\d+)").expect("valid BANNER_RE")
+});
+/// `claude-sonnet-4:  24 input, 6.3k output, 659.5k cache read, 58.2k cache
+/// write` rows under `Usage by model:`.
+static MODEL_ROW_RE: Lazy<Regex> = Lazy::new(|| {
+    Regex::new(
+        r"^\s*(\S+?):\s+([\d.,]+[kKmM]?) input,\s*([\d.,]+[kKmM]?) output(?:,\s*([\d.,]+[kKmM]?) cache read)?(?:,\s*([\d.,]+[kKmM]?) cache write)?",
+    )
+    .expect("valid MODEL_ROW_RE")
+});
+
+/// The `/cost` summary. Older releases print token totals and a duration
+/// after the cost; newer ones print the wall time and a `Usage by model:`
+/// block with per-model input, output and cache counts.
+#[derive(Default)]
+pub struct ClaudeCodeParser {
+    cost_usd: Option<f64>,
+    tokens_in: Option<i64>,
+    tokens_out: Option<i64>,
+    tokens_total: Option<i64>,
+    duration_s: Option<i64>,
+    in_models: bool,
+    models: Vec<ModelRow>,
+    raw_lines: Vec<String>,
+}
+
+struct ModelRow {
+    model: String,
+    tokens_in: i64,
+    tokens_out: i64,
+    cache_read_tokens: i64,
+    cache_creation_tokens: i64,
+}
+
+impl ModelRow {
+    fn total(&self) -> i64 {
+        self.tokens_in + self.tokens_out + self.cache_read_tokens + self.cache_creation_tokens
+    }
+}
+
+impl AgentParser for ClaudeCodeParser {
+    fn agent(&self) -> &str {
+        "claude-code"
+    }
+
+    fn detect(&self, line: &str) -> bool {
+        BANNER_RE.is_match(banner_text(line))
+    }
+
+    fn parse_line(&mut self, line: &str) -> Option<ParsedUsage> {
+        if self.in_models {
+            if let Some(captures) = MODEL_ROW_RE.captures(line) {
+                let count = |index: usize| {
+                    captures
+                        .get(index)
+                        .and_then(|m| parse_compact_int(m.as_str()))
+                        .unwrap_or_default()
+                };
+                self.models.push(ModelRow {
+                    model: captures[1].to_string(),
+                    tokens_in: count(2),
+                    tokens_out: count(3),
+                    cache_read_tokens: count(4),
+                    cache_creation_tokens: count(5),
+                });
+                self.raw_lines.push(line.trim().to_string());
+                return None;
+            }
+
+            // First line after the model rows closes the summary.
+            let usage = self.model_usage();
+            self.reset();
+            return usage;
+        }
+
+        if line.contains("Total cost:") {
+            // A new summary replaces one that never completed.
+            self.reset();
+        }
+
+        if line.contains("Total cost:")
+            || line.contains("Total tokens:")
+            || line.contains("Duration:")
+            || line.contains("Total duration (wall):")
+        {
+            self.raw_lines.push(line.to_string());
+        }
+
+        if let Some(captures) = COST_RE.captures(line) {
+            self.cost_usd = captures.get(1).and_then(|m| m.as_str().parse::<f64>().ok());
+        }
+
+        if let Some(captures) = WALL_RE.captures(line) {
+            self.duration_s = parse_duration_s(&captures[1]);
+        }
+
+        if self.cost_usd.is_some() && line.trim_start().starts_with("Usage by model:") {
+            self.in_models = true;
+            self.raw_lines.push(line.trim().to_string());
+            return None;
+        }
+
+        if let Some(captures) = TOKENS_RE.captures(line) {
+            self.tokens_total = captures
+                .get(1)
+                .and_then(|m| parse_int_with_commas(m.as_str()));
+            self.tokens_in = captures.get(2).and_then(|m| parse_compact_int(m.as_str()));
+            self.tokens_out = captures.get(3).and_then(|m| parse_compact_int(m.as_str()));
+        }
+
+        if let Some(captures) = DURATION_RE.captures(line) {
+            let mins = captures
+                .get(1)
+                .and_then(|m| m.as_str().parse::<i64>().ok())
+                .unwrap_or(0);
+            let secs = captures
+                .get(2)
+                .and_then(|m| m.as_str().parse::<i64>().ok())
+                .unwrap_or(0);
+            self.duration_s = Some(mins * 60 + secs);
+        }
+
+        let is_complete = self.cost_usd.is_some()
+            && self.tokens_total.is_some()
+            && self.tokens_in.is_some()
+            && self.tokens_out.is_some()
+            && (self.duration_s.is_some() || line.trim().is_empty());
+        if !is_complete {
+            return None;
+        }
+
+        let usage = ParsedUsage {
+            cost_usd: self.cost_usd.unwrap_or_default(),
+            tokens_in: self.tokens_in.unwrap_or_default(),
+            tokens_out: self.tokens_out.unwrap_or_default(),
+            tokens_total: self.tokens_total.unwrap_or_default(),
+            duration_s: self.duration_s,
+            raw_lines: std::mem::take(&mut self.raw_lines),
+            ..ParsedUsage::default()
+        };
+        self.reset();
+        Some(usage)
+    }
+
+    fn cumulative(&self) -> bool {
+        true
+    }
+
+    fn reset(&mut self) {
+        *self = Self::default();
+    }
+}
+
+impl ClaudeCodeParser {
+    /// Sums the `Usage by model:` rows. The row is stored under the model
+    /// that used the most tokens; the cost is Claude's own total.
+    fn model_usage(&mut self) -> Option<ParsedUsage> {
+        let mut usage = ParsedUsage {
+            cost_usd: self.cost_usd.unwrap_or_default(),
+            duration_s: self.duration_s,
+            cache_read_tokens: Some(0),
+            cache_creation_tokens: Some(0),
+            model: self
+                .models
+                .iter()
+                .max_by_key(|row| row.total())
+                .map(|row| row.model.clone()),
+            raw_lines: std::mem::take(&mut self.raw_lines),
+            ..ParsedUsage::default()
+        };
+        for row in &self.models {
+            usage.tokens_in += row.tokens_in;
+            usage.tokens_out += row.tokens_out;
+            usage.cache_read_tokens = usage.cache_read_tokens.map(|n| n + row.cache_read_tokens);
+            usage.cache_creation_tokens = usage
+                .cache_creation_tokens
+                .map(|n| n + row.cache_creation_tokens);
+        }
+        usage.tokens_total = usage.tokens_in + usage.tokens_out;
+
+        (usage.tokens_total > 0).then_some(usage)
+    }
+}
@@ -0,0 +1,45 @@
+use once_cell::sync::Lazy;
+use regex::Regex;
+
+use super::{banner_text, parse_int_with_commas, AgentParser, ParsedUsage};
+
+static USAGE_RE: Lazy<Regex> = Lazy::new(|| {
+    Regex::new(r"Token usage:\s*total=([\d,]+)\s+input=([\d,]+)(?:\s*\(\+\s*[\d,]+ cached\))?\s+output=([\d,]+)")
+        .expect("valid USAGE_RE")
+});
+/// `>_ OpenAI Codex (v0.46.0)`, or `OpenAI Codex (research preview) v0.1.x`
+/// in early releases.
+static BANNER_RE: Lazy<Regex> = Lazy::new(|| {
+    Regex::new(r"^OpenAI Codex (?:\(research preview\) )?\(?v\d+\.\d+").expect("valid BANNER_RE")
+});
+
+/// Codex CLI prints a single `Token usage: total=… input=… output=…` line
+/// when a session ends. It reports no cost.
+#[derive(Default)]
+pub struct CodexParser;
+
+impl AgentParser for CodexParser {
+    fn agent(&self) -> &str {
+        "codex-cli"
+    }
+
+    fn detect(&self, line: &str) -> bool {
+        BANNER_RE.is_match(banner_text(line))
+    }
+
+    fn parse_line(&mut self, line: &str) -> Option<ParsedUsage> {
+        let captures = USAGE_RE.captures(line)?;
+
+        Some(ParsedUsage {
+            cost_usd: 0.0,
+            tokens_in: parse_int_with_commas(&captures[2])?,
+            tokens_out: parse_int_with_commas(&captures[3])?,
+            tokens_total: parse_int_with_commas(&captures[1])?,
+            duration_s: None,
+            raw_lines: vec![line.trim().to_string()],
+            ..ParsedUsage::default()
+        })
+    }
+
+    fn reset(&mut self) {}
+}
@@ -0,0 +1,94 @@
+use once_cell::sync::Lazy;
+use regex::Regex;
+
+use super::{banner_text, parse_compact_int, parse_duration_s, AgentParser, ParsedUsage};
+
+static BANNER_RE: Lazy<Regex> = Lazy::new(|| {
+    Regex::new(r"^(?:Welcome to GitHub Copilot CLI|GitHub Copilot CLI v?\d+\.\d+)")
+        .expect("valid BANNER_RE")
+});
+static WALL_RE: Lazy<Regex> =
+    Lazy::new(|| Regex::new(r"Total duration \(wall\):\s+(.+?)\s*$").expect("valid WALL_RE"));
+/// `claude-sonnet-4   12.3k input, 456 output, 0 cache read, …` rows under
+/// `Usage by model:`.
+static MODEL_ROW_RE: Lazy<Regex> = Lazy::new(|| {
+    Regex::new(r"^\s*(\S+?):?\s+([\d.,]+[kKmM]?) input,\s*([\d.,]+[kKmM]?) output")
+        .expect("valid MODEL_ROW_RE")
+});
+
+/// GitHub Copilot CLI's exit summary. Billing is in premium requests rather
+/// than dollars, so only tokens and duration are recorded.
+#[derive(Default)]
+pub struct CopilotParser {
+    /// `Total usage est:` opens the summary; Claude Code prints a similar
+    /// `Usage by model:` block after `Total cost:` instead.
+    in_summary: bool,
+    duration_s: Option<i64>,
+    in_models: bool,
+    tokens_in: i64,
+    tokens_out: i64,
+    raw_lines: Vec<String>,
+}
+
+impl AgentParser for CopilotParser {
+    fn agent(&self) -> &str {
+        "copilot-cli"
+    }
+
+    fn detect(&self, line: &str) -> bool {
+        BANNER_RE.is_match(banner_text(line))
+    }
+
+    fn parse_line(&mut self, line: &str) -> Option<ParsedUsage> {
+        if line.trim_start().starts_with("Total usage est:") {
+            self.reset();
+            self.in_summary = true;
+            self.raw_lines.push(line.trim().to_string());
+            return None;
+        }
+
+        if !self.in_summary {
+            return None;
+        }
+
+        if let Some(captures) = WALL_RE.captures(line) {
+            self.duration_s = parse_duration_s(&captures[1]);
+            self.raw_lines.push(line.trim().to_string());
+            return None;
+        }
+
+        if line.trim_start().starts_with("Usage by model:") {
+            self.in_models = true;
+            self.raw_lines.push(line.trim().to_string());
+            return None;
+        }
+
+        if !self.in_models {
+            return None;
+        }
+
+        if let Some(captures) = MODEL_ROW_RE.captures(line) {
+            self.tokens_in += parse_compact_int(&captures[2]).unwrap_or_default();
+            self.tokens_out += parse_compact_int(&captures[3]).unwrap_or_default();
+            self.raw_lines.push(line.trim().to_string());
+            return None;
+        }
+
+        // First line after the model rows closes the summary.
+        let usage = (self.tokens_in + self.tokens_out > 0).then(|| ParsedUsage {
+            cost_usd: 0.0,
+            tokens_in: self.tokens_in,
+            tokens_out: self.tokens_out,
+            tokens_total: self.tokens_in + self.tokens_out,
+            duration_s: self.duration_s,
+            raw_lines: std::mem::take(&mut self.raw_lines),
+            ..ParsedUsage::default()
+        });
+        self.reset();
+        usage
+    }
+
+    fn reset(&mut self) {
+        *self = Self::default();
+    }
+}
@@ -0,0 +1,116 @@
+use once_cell::sync::Lazy;
+use regex::Regex;
+
+use super::{banner_text, parse_duration_s, parse_int_with_commas, AgentParser, ParsedUsage};
+
+static INPUT_RE: Lazy<Regex> =
+    Lazy::new(|| Regex::new(r"Input Tokens:?\s+([\d,]+)\s*$").expect("valid INPUT_RE"));
+static OUTPUT_RE: Lazy<Regex> =
+    Lazy::new(|| Regex::new(r"Output Tokens:?\s+([\d,]+)\s*$").expect("valid OUTPUT_RE"));
+static TOTAL_RE: Lazy<Regex> =
+    Lazy::new(|| Regex::new(r"Total Tokens:?\s+([\d,]+)\s*$").expect("valid TOTAL_RE"));
+static WALL_RE: Lazy<Regex> =
+    Lazy::new(|| Regex::new(r"Wall Time:?\s+(.+?)\s*$").expect("valid WALL_RE"));
+/// Gemini CLI draws its startup logo as block art, so the `/about` panel
+/// title, a versioned name and the update notice are what identify it.
+static BANNER_RE: Lazy<Regex> = Lazy::new(|| {
+    Regex::new(r"^(?:About Gemini CLI|Gemini CLI (?:v\d+\.\d+|update available))")
+        .expect("valid BANNER_RE")
+});
+/// `gemini-2.5-pro   3   12,345   678` rows of the model usage table.
+static MODEL_ROW_RE: Lazy<Regex> = Lazy::new(|| {
+    Regex::new(r"^(gemini-[\w.-]+)\s+\d+\s+([\d,]+)\s+([\d,]+)\s*$").expect("valid MODEL_ROW_RE")
+});
+
+/// Gemini CLI's session stats (`/stats` and the exit summary). Older
+/// versions print cumulative `Input Tokens`/`Output Tokens`/`Total Tokens`
+/// rows; newer ones a per-model table. The panel is drawn in a box, so the
+/// border characters are trimmed before matching.
+#[derive(Default)]
+pub struct GeminiParser {
+    tokens_in: Option<i64>,
+    tokens_out: Option<i64>,
+    tokens_total: Option<i64>,
+    duration_s: Option<i64>,
+    model_rows: bool,
+    raw_lines: Vec<String>,
+}
+
+impl AgentParser for GeminiParser {
+    fn agent(&self) -> &str {
+        "gemini-cli"
+    }
+
+    fn detect(&self, line: &str) -> bool {
+        BANNER_RE.is_match(banner_text(line))
+    }
+
+    fn parse_line(&mut self, line: &str) -> Option<ParsedUsage> {
+        let line = line.trim().trim_matches(|ch| ch == '│' || ch == '|').trim();
+
+        if let Some(captures) = WALL_RE.captures(line) {
+            self.duration_s = parse_duration_s(&captures[1]);
+            self.raw_lines.push(line.to_string());
+            return None;
+        }
+
+        if let Some(captures) = MODEL_ROW_RE.captures(line) {
+            let tokens_in = parse_int_with_commas(&captures[2])?;
+            let tokens_out = parse_int_with_commas(&captures[3])?;
+            *self.tokens_in.get_or_insert(0) += tokens_in;
+            *self.tokens_out.get_or_insert(0) += tokens_out;
+            self.model_rows = true;
+            self.raw_lines.push(line.to_string());
+            return None;
+        }
+
+        if let Some(captures) = INPUT_RE.captures(line) {
+            self.tokens_in = parse_int_with_commas(&captures[1]);
+            self.raw_lines.push(line.to_string());
+            return None;
+        }
+
+        if let Some(captures) = OUTPUT_RE.captures(line) {
+            self.tokens_out = parse_int_with_commas(&captures[1]);
+            self.raw_lines.push(line.to_string());
+            return None;
+        }
+
+        if let Some(captures) = TOTAL_RE.captures(line) {
+            self.tokens_total = parse_int_with_commas(&captures[1]);
+            self.raw_lines.push(line.to_string());
+        }
+
+        // The cumulative layout ends with its total row; the table ends at
+        // the first line after its rows.
+        let complete =
+            self.tokens_total.is_some() || (self.model_rows && !line.starts_with("gemini-"));
+        if !complete {
+            return None;
+        }
+
+        let (Some(tokens_in), Some(tokens_out)) = (self.tokens_in, self.tokens_out) else {
+            self.reset();
+            return None;
+        };
+        let usage = ParsedUsage {
+            cost_usd: 0.0,
+            tokens_in,
+            tokens_out,
+            tokens_total: self.tokens_total.unwrap_or(tokens_in + tokens_out),
+            duration_s: self.duration_s,
+            raw_lines: std::mem::take(&mut self.raw_lines),
+            ..ParsedUsage::default()
+        };
+        self.reset();
+        Some(usage)
+    }
+
+    fn cumulative(&self) -> bool {
+        true
+    }
+
+    fn reset(&mut self) {
+        *self = Self::default();
+    }
+}
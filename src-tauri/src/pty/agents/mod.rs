@@ -0,0 +1,133 @@
+mod aider;
+mod claude_code;
+mod codex;
+mod copilot;
+mod gemini;
+mod rules;
+
+use std::sync::Arc;
+
+use serde::Serialize;
+
+pub use aider::AiderParser;
+pub use claude_code::ClaudeCodeParser;
+pub use codex::CodexParser;
+pub use copilot::CopilotParser;
+pub use gemini::GeminiParser;
+pub use rules::{
+    compile_rules, test_rule, CompiledRule, RuleError, RuleTestResult, ScrapeRule, ScraperConfig,
+};
+
+use rules::RuleParser;
+
+/// One usage report read from an agent's output, ready to persist.
+#[derive(Debug, Clone, Default, PartialEq, Serialize)]
+pub struct ParsedUsage {
+    pub cost_usd: f64,
+    pub tokens_in: i64,
+    pub tokens_out: i64,
+    pub tokens_total: i64,
+    pub duration_s: Option<i64>,
+    pub cache_read_tokens: Option<i64>,
+    pub cache_creation_tokens: Option<i64>,
+    pub model: Option<String>,
+    /// The summary lines the numbers came from.
+    pub raw_lines: Vec<String>,
+}
+
+/// Reads token and cost summaries out of one agent CLI's output. Lines are
+/// fed one at a time, ANSI-stripped, and a parser keeps whatever state it
+/// needs for summaries that span several lines.
+pub trait AgentParser: Send {
+    /// Name stored in `token_usage.agent`.
+    fn agent(&self) -> &str;
+
+    /// Whether `line` is this agent's startup banner. Detection runs on
+    /// every line, so this should not match text the agent merely mentions.
+    fn detect(&self, line: &str) -> bool;
+
+    /// Returns a usage report once a summary is complete.
+    fn parse_line(&mut self, line: &str) -> Option<ParsedUsage>;
+
+    /// Whether summaries report the running totals of the agent's session,
+    /// like Claude Code's `/cost`, rather than usage since the last one.
+    fn cumulative(&self) -> bool {
+        false
+    }
+
+    /// Drops any partially read summary.
+    fn reset(&mut self);
+}
+
+/// User rules followed by every built-in parser, in detection priority
+/// order.
+pub fn all_parsers(rules: &[Arc<CompiledRule>]) -> Vec<Box<dyn AgentParser>> {
+    rules
+        .iter()
+        .map(|rule| Box::new(RuleParser::new(Arc::clone(rule))) as Box<dyn AgentParser>)
+        .chain(builtin_parsers())
+        .collect()
+}
+
+fn builtin_parsers() -> Vec<Box<dyn AgentParser>> {
+    vec![
+        Box::new(ClaudeCodeParser::default()),
+        Box::new(CodexParser),
+        Box::new(AiderParser),
+        Box::new(GeminiParser::default()),
+        Box::new(CopilotParser::default()),
+    ]
+}
+
+/// `line` without the leading box border, logo glyphs and prompt marks that
+/// agents draw around their banner.
+pub(crate) fn banner_text(line: &str) -> &str {
+    line.trim_start_matches(|ch: char| ch.is_whitespace() || "│|▐▛▜▌▝▘█✻*>_".contains(ch))
+}
+
+pub(crate) fn parse_int_with_commas(input: &str) -> Option<i64> {
+    input.replace(',', "").parse::<i64>().ok()
+}
+
+pub(crate) fn parse_compact_int(input: &str) -> Option<i64> {
+    let normalized = input.replace(',', "").trim().to_ascii_uppercase();
+
+    if let Some(number) = normalized.strip_suffix('K') {
+        let parsed = number.parse::<f64>().ok()?;
+        return Some((parsed * 1_000.0).round() as i64);
+    }
+
+    if let Some(number) = normalized.strip_suffix('M') {
+        let parsed = number.parse::<f64>().ok()?;
+        return Some((parsed * 1_000_000.0).round() as i64);
+    }
+
+    normalized.parse::<i64>().ok()
+}
+
+/// Seconds in durations like `1h 2m 3.5s`, `6m 19.7s` or `42s`.
+pub(crate) fn parse_duration_s(input: &str) -> Option<i64> {
+    let mut total = 0.0;
+    let mut matched = false;
+
+    for part in input.split_whitespace() {
+        let (number, scale) = if let Some(number) = part.strip_suffix("ms") {
+            (number, 0.001)
+        } else if let Some(number) = part.strip_suffix('h') {
+            (number, 3600.0)
+        } else if let Some(number) = part.strip_suffix('m') {
+            (number, 60.0)
+        } else if let Some(number) = part.strip_suffix('s') {
+            (number, 1.0)
+        } else {
+            break;
+        };
+        let Ok(value) = number.parse::<f64>() else {
+            break;
+        };
+        total += value * scale;
+        matched = true;
+    }
+
+    matched.then(|| total.round() as i64)
+}
@@ -0,0 +1,366 @@
+use std::sync::Arc;
+
+use regex::Regex;
+use serde::{Deserialize, Serialize};
+
+use super::{parse_compact_int, parse_duration_s, AgentParser, ParsedUsage};
+
+/// Capture names a rule's patterns may use.
+const FIELDS: &[&str] = &[
+    "cost",
+    "tokens_in",
+    "tokens_out",
+    "tokens_total",
+    "cache_read_tokens",
+    "cache_creation_tokens",
+    "duration",
+    "model",
+];
+
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase", default)]
+pub struct ScraperConfig {
+    /// Tried before the built-in parsers, so a rule can take over an agent
+    /// whose output format changed.
+    pub rules: Vec<ScrapeRule>,
+}
+
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase", default)]
+pub struct ScrapeRule {
+    pub name: String,
+    /// Stored in `token_usage.agent`.
+    pub agent: String,
+    /// Banner regex that selects this rule for a session.
+    pub detect: Option<String>,
+    /// Regexes whose named captures (`cost`, `tokens_in`, `tokens_out`,
+    /// `tokens_total`, `cache_read_tokens`, `cache_creation_tokens`,
+    /// `duration`, `model`) fill in the summary.
+    pub patterns: Vec<String>,
+    /// Fields that must be captured before the summary is complete.
+    pub require: Vec<String>,
+    /// When set, the summary completes on a line matching this regex (once
+    /// `require` is met) rather than as soon as `require` is met.
+    pub complete_on: Option<String>,
+    /// Add repeated captures together, for per-model tables.
+    pub sum: bool,
+    /// Summaries report session totals, so only the increase since the
+    /// previous one is counted.
+    pub cumulative: bool,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct RuleError {
+    pub rule: String,
+    pub error: String,
+}
+
+/// A rule whose regexes compiled and whose field names check out.
+#[derive(Debug)]
+pub struct CompiledRule {
+    rule: ScrapeRule,
+    detect: Option<Regex>,
+    patterns: Vec<Regex>,
+    complete_on: Option<Regex>,
+}
+
+/// Compiles every rule, returning the ones that are usable and an error for
+/// each problem found in the rest.
+pub fn compile_rules(rules: &[ScrapeRule]) -> (Vec<Arc<CompiledRule>>, Vec<RuleError>) {
+    let mut compiled = Vec::new();
+    let mut errors = Vec::new();
+
+    for (index, rule) in rules.iter().enumerate() {
+        match compile_rule(rule) {
+            Ok(rule) => compiled.push(Arc::new(rule)),
+            Err(rule_errors) => {
+                let name = if rule.name.trim().is_empty() {
+                    format!("rule {}", index + 1)
+                } else {
+                    rule.name.clone()
+                };
+                errors.extend(rule_errors.into_iter().map(|error| RuleError {
+                    rule: name.clone(),
+                    error,
+                }));
+            }
+        }
+    }
+
+    (compiled, errors)
+}
+
+pub fn compile_rule(rule: &ScrapeRule) -> Result<CompiledRule, Vec<String>> {
+    let mut errors = Vec::new();
+
+    if rule.agent.trim().is_empty() {
+        errors.push("agent must not be empty".to_string());
+    }
+    if rule.patterns.is_empty() {
+        errors.push("at least one pattern is required".to_string());
+    }
+
+    let mut compile = |label: &str, source: &str| match Regex::new(source) {
+        Ok(regex) => Some(regex),
+        Err(err) => {
+            errors.push(format!("invalid {label} regex: {err}"));
+            None
+        }
+    };
+    let detect = rule
+        .detect
+        .as_deref()
+        .and_then(|source| compile("detect", source));
+    let complete_on = rule
+        .complete_on
+        .as_deref()
+        .and_then(|source| compile("completeOn", source));
+    let patterns = rule
+        .patterns
+        .iter()
+        .enumerate()
+        .filter_map(|(index, source)| compile(&format!("pattern {}", index + 1), source))
+        .collect::<Vec<_>>();
+
+    let mut captured = Vec::new();
+    for name in patterns
+        .iter()
+        .flat_map(|pattern| pattern.capture_names().flatten())
+    {
+        if !FIELDS.contains(&name) {
+            errors.push(format!("unknown capture name '{name}'"));
+        } else if !captured.contains(&name) {
+            captured.push(name);
+        }
+    }
+    if !captured
+        .iter()
+        .any(|name| name.starts_with("tokens_") || *name == "cost")
+    {
+        errors.push("patterns capture neither cost nor tokens".to_string());
+    }
+    for name in &rule.require {
+        if !FIELDS.contains(&name.as_str()) {
+            errors.push(format!("unknown required field '{name}'"));
+        } else if !captured.contains(&name.as_str()) {
+            errors.push(format!("required field '{name}' is never captured"));
+        }
+    }
+
+    if !errors.is_empty() {
+        return Err(errors);
+    }
+
+    Ok(CompiledRule {
+        rule: rule.clone(),
+        detect,
+        patterns,
+        complete_on,
+    })
+}
+
+#[derive(Default)]
+struct Captured {
+    cost: Option<f64>,
+    tokens_in: Option<i64>,
+    tokens_out: Option<i64>,
+    tokens_total: Option<i64>,
+    cache_read_tokens: Option<i64>,
+    cache_creation_tokens: Option<i64>,
+    duration_s: Option<i64>,
+    model: Option<String>,
+    raw_lines: Vec<String>,
+}
+
+impl Captured {
+    fn has(&self, field: &str) -> bool {
+        match field {
+            "cost" => self.cost.is_some(),
+            "tokens_in" => self.tokens_in.is_some(),
+            "tokens_out" => self.tokens_out.is_some(),
+            "tokens_total" => self.tokens_total.is_some(),
+            "cache_read_tokens" => self.cache_read_tokens.is_some(),
+            "cache_creation_tokens" => self.cache_creation_tokens.is_some(),
+            "duration" => self.duration_s.is_some(),
+            "model" => self.model.is_some(),
+            _ => false,
+        }
+    }
+
+    fn is_empty(&self) -> bool {
+        FIELDS.iter().all(|field| !self.has(field))
+    }
+}
+
+/// Per-session parser running one compiled rule.
+pub struct RuleParser {
+    rule: Arc<CompiledRule>,
+    captured: Captured,
+}
+
+impl RuleParser {
+    pub fn new(rule: Arc<CompiledRule>) -> Self {
+        Self {
+            rule,
+            captured: Captured::default(),
+        }
+    }
+
+    fn capture(&mut self, line: &str) -> bool {
+        let sum = self.rule.rule.sum;
+        let mut matched = false;
+
+        for pattern in &self.rule.patterns {
+            let Some(captures) = pattern.captures(line) else {
+                continue;
+            };
+            matched = true;
+
+            let int = |name: &str| {
+                captures
+                    .name(name)
+                    .and_then(|value| parse_compact_int(value.as_str()))
+            };
+            let merge = |slot: &mut Option<i64>, value: Option<i64>| {
+                if let Some(value) = value {
+                    *slot = Some(if sum {
+                        slot.unwrap_or(0) + value
+                    } else {
+                        value
+                    });
+                }
+            };
+
+            if let Some(cost) = captures.name("cost").and_then(|value| {
+                value
+                    .as_str()
+                    .trim_start_matches('$')
+                    .replace(',', "")
+                    .parse::<f64>()
+                    .ok()
+            }) {
+                self.captured.cost = Some(if sum {
+                    self.captured.cost.unwrap_or(0.0) + cost
+                } else {
+                    cost
+                });
+            }
+            merge(&mut self.captured.tokens_in, int("tokens_in"));
+            merge(&mut self.captured.tokens_out, int("tokens_out"));
+            merge(&mut self.captured.tokens_total, int("tokens_total"));
+            merge(
+                &mut self.captured.cache_read_tokens,
+                int("cache_read_tokens"),
+            );
+            merge(
+                &mut self.captured.cache_creation_tokens,
+                int("cache_creation_tokens"),
+            );
+            if let Some(duration) = captures.name("duration") {
+                let value = duration.as_str();
+                self.captured.duration_s = parse_duration_s(value)
+                    .or_else(|| value.parse::<f64>().ok().map(|secs| secs.round() as i64));
+            }
+            if let Some(model) = captures.name("model") {
+                self.captured.model = Some(model.as_str().to_string());
+            }
+        }
+
+        if matched {
+            self.captured.raw_lines.push(line.trim().to_string());
+        }
+        matched
+    }
+}
+
+impl AgentParser for RuleParser {
+    fn agent(&self) -> &str {
+        &self.rule.rule.agent
+    }
+
+    fn detect(&self, line: &str) -> bool {
+        self.rule
+            .detect
+            .as_ref()
+            .is_some_and(|detect| detect.is_match(line))
+    }
+
+    fn parse_line(&mut self, line: &str) -> Option<ParsedUsage> {
+        let matched = self.capture(line);
+
+        let required = self
+            .rule
+            .rule
+            .require
+            .iter()
+            .all(|field| self.captured.has(field));
+        let complete = match &self.rule.complete_on {
+            Some(complete_on) => complete_on.is_match(line) && !self.captured.is_empty(),
+            None => matched,
+        };
+        if !complete || !required {
+            return None;
+        }
+
+        let captured = std::mem::take(&mut self.captured);
+        let tokens_in = captured.tokens_in.unwrap_or_default();
+        let tokens_out = captured.tokens_out.unwrap_or_default();
+        Some(ParsedUsage {
+            cost_usd: captured.cost.unwrap_or_default(),
+            tokens_in,
+            tokens_out,
+            tokens_total: captured.tokens_total.unwrap_or(tokens_in + tokens_out),
+            duration_s: captured.duration_s,
+            cache_read_tokens: captured.cache_read_tokens,
+            cache_creation_tokens: captured.cache_creation_tokens,
+            model: captured.model,
+            raw_lines: captured.raw_lines,
+        })
+    }
+
+    fn cumulative(&self) -> bool {
+        self.rule.rule.cumulative
+    }
+
+    fn reset(&mut self) {
+        self.captured = Captured::default();
+    }
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct RuleTestResult {
+    pub errors: Vec<String>,
+    /// Whether the rule's `detect` regex matched any sample line.
+    pub detected: bool,
+    pub usages: Vec<ParsedUsage>,
+}
+
+/// Runs one rule over sample output the way the scraper would, without
+/// touching the database.
+pub fn test_rule(rule: &ScrapeRule, sample_text: &str) -> RuleTestResult {
+    let compiled = match compile_rule(rule) {
+        Ok(compiled) => compiled,
+        Err(errors) => {
+            return RuleTestResult {
+                errors,
+                detected: false,
+                usages: Vec::new(),
+            }
+        }
+    };
+
+    let mut parser = RuleParser::new(Arc::new(compiled));
+    let mut detected = false;
+    let mut usages = Vec::new();
+    let sample = strip_ansi_escapes::strip_str(sample_text);
+    for line in sample.lines() {
+        detected |= parser.detect(line);
+        usages.extend(parser.parse_line(line));
+    }
+
+    RuleTestResult {
+        errors: Vec::new(),
+        detected,
+        usages,
+    }
+}
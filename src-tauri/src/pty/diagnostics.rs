@@ -0,0 +1,330 @@
+use std::collections::{HashMap, HashSet};
+use std::path::{Path, PathBuf};
+use std::sync::{Arc, Mutex};
+
+use once_cell::sync::Lazy;
+use regex::Regex;
+use serde::Serialize;
+
+use crate::pty::lines::LineSplitter;
+
+/// Diagnostics kept per run; a runaway build shouldn't grow the list forever.
+const MAX_DIAGNOSTICS: usize = 500;
+
+/// Longest partial line buffered while waiting for a newline.
+const MAX_PENDING_LINE: usize = 16 * 1024;
+
+static RUSTC_HEADER_RE: Lazy<Regex> = Lazy::new(|| {
+    Regex::new(r"^(error|warning)(?:\[(E\d{4})\])?: (.+)$").expect("valid RUSTC_HEADER_RE")
+});
+static RUSTC_LOCATION_RE: Lazy<Regex> =
+    Lazy::new(|| Regex::new(r"^\s*--> (.+?):(\d+):(\d+)$").expect("valid RUSTC_LOCATION_RE"));
+static TSC_RE: Lazy<Regex> = Lazy::new(|| {
+    Regex::new(r"^(.+?)(?:\((\d+),(\d+)\):|:(\d+):(\d+) -) (error|warning) (TS\d+): (.+)$")
+        .expect("valid TSC_RE")
+});
+static ESLINT_FILE_RE: Lazy<Regex> = Lazy::new(|| {
+    Regex::new(r"^(\S+\.(?:[cm]?[jt]sx?|vue|svelte|astro))$").expect("valid ESLINT_FILE_RE")
+});
+static ESLINT_ENTRY_RE: Lazy<Regex> = Lazy::new(|| {
+    Regex::new(r"^\s+(\d+):(\d+)\s+(error|warning)\s+(.+?)(?:\s{2,}(\S+))?$")
+        .expect("valid ESLINT_ENTRY_RE")
+});
+static GCC_RE: Lazy<Regex> = Lazy::new(|| {
+    Regex::new(r"^(.+?):(\d+):(\d+): (?:fatal )?(error|warning): (.+?)(?: \[(-W[\w=+-]*)\])?$")
+        .expect("valid GCC_RE")
+});
+static GO_RE: Lazy<Regex> = Lazy::new(|| {
+    Regex::new(r"^\s*(\S+\.go):(\d+)(?::(\d+))?: (.+)$").expect("valid GO_RE")
+});
+static PYTEST_ERROR_RE: Lazy<Regex> =
+    Lazy::new(|| Regex::new(r"^E\s+(.+)$").expect("valid PYTEST_ERROR_RE"));
+static PYTEST_LOCATION_RE: Lazy<Regex> =
+    Lazy::new(|| Regex::new(r"^(\S+\.py):(\d+): (\w+)$").expect("valid PYTEST_LOCATION_RE"));
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct Diagnostic {
+    /// rustc, tsc, eslint, gcc, pytest or go.
+    pub source: String,
+    /// error or warning.
+    pub severity: String,
+    /// Resolved against the session's working directory.
+    pub file: String,
+    pub line: u32,
+    pub column: Option<u32>,
+    pub message: String,
+    pub code: Option<String>,
+}
+
+/// Latest run's diagnostics per session, for `pty_diagnostics`.
+#[derive(Clone, Default)]
+pub struct DiagnosticsStore {
+    inner: Arc<Mutex<HashMap<String, Vec<Diagnostic>>>>,
+}
+
+impl DiagnosticsStore {
+    pub fn set(&self, session_id: &str, diagnostics: Vec<Diagnostic>) {
+        if let Ok(mut guard) = self.inner.lock() {
+            guard.insert(session_id.to_string(), diagnostics);
+        }
+    }
+
+    pub fn get(&self, session_id: &str) -> Vec<Diagnostic> {
+        self.inner
+            .lock()
+            .ok()
+            .and_then(|guard| guard.get(session_id).cloned())
+            .unwrap_or_default()
+    }
+
+    pub fn remove(&self, session_id: &str) {
+        if let Ok(mut guard) = self.inner.lock() {
+            guard.remove(session_id);
+        }
+    }
+}
+
+struct PendingRustc {
+    severity: String,
+    code: Option<String>,
+    message: String,
+}
+
+/// Recognises compiler, linter and test-runner diagnostics in a session's
+/// output. A run starts at each OSC 133;C command mark; without shell
+/// integration the whole session is one run. Identical diagnostics are
+/// reported once per run.
+pub struct DiagnosticExtractor {
+    cwd: PathBuf,
+    lines: LineSplitter,
+    rustc: Option<PendingRustc>,
+    eslint_file: Option<String>,
+    pytest_errors: Vec<String>,
+    seen: HashSet<(String, u32, Option<u32>, String)>,
+    diagnostics: Vec<Diagnostic>,
+}
+
+impl DiagnosticExtractor {
+    pub fn new(cwd: impl Into<PathBuf>) -> Self {
+        Self {
+            cwd: cwd.into(),
+            lines: LineSplitter::default(),
+            rustc: None,
+            eslint_file: None,
+            pytest_errors: Vec::new(),
+            seen: HashSet::new(),
+            diagnostics: Vec::new(),
+        }
+    }
+
+    pub fn set_cwd(&mut self, cwd: impl Into<PathBuf>) {
+        self.cwd = cwd.into();
+    }
+
+    /// Clears the current run. Returns whether there was anything to clear.
+    pub fn begin_run(&mut self) -> bool {
+        self.rustc = None;
+        self.eslint_file = None;
+        self.pytest_errors.clear();
+        self.seen.clear();
+        !std::mem::take(&mut self.diagnostics).is_empty()
+    }
+
+    pub fn diagnostics(&self) -> &[Diagnostic] {
+        &self.diagnostics
+    }
+
+    /// Drops a partial line; used when a secret span starts.
+    pub fn discard_pending(&mut self) {
+        self.lines.clear();
+    }
+
+    /// Feeds decoded output. Returns whether new diagnostics were found.
+    pub fn feed(&mut self, text: &str) -> bool {
+        let before = self.diagnostics.len();
+
+        for line in self.lines.feed(text) {
+            // Progress bars redraw with a bare CR; only the last frame counts.
+            let line = line.rsplit('\r').next().unwrap_or(&line);
+            // strip_str drops tabs along with other control characters.
+            let line = strip_ansi_escapes::strip_str(line.replace('\t', " "));
+            self.parse_line(&line);
+        }
+        if self.lines.pending_len() > MAX_PENDING_LINE {
+            self.lines.clear();
+        }
+
+        self.diagnostics.len() > before
+    }
+
+    fn parse_line(&mut self, line: &str) {
+        // rustc puts the location on the line after the header.
+        if let Some(header) = self.rustc.take() {
+            if let Some(captures) = RUSTC_LOCATION_RE.captures(line) {
+                self.push(
+                    "rustc",
+                    header.severity,
+                    &captures[1],
+                    parse_number(&captures[2]),
+                    Some(parse_number(&captures[3])),
+                    header.message,
+                    header.code,
+                );
+                return;
+            }
+        }
+
+        if line.trim().is_empty() {
+            self.eslint_file = None;
+            return;
+        }
+
+        if let Some(captures) = RUSTC_HEADER_RE.captures(line) {
+            self.rustc = Some(PendingRustc {
+                severity: captures[1].to_string(),
+                code: captures.get(2).map(|code| code.as_str().to_string()),
+                message: captures[3].to_string(),
+            });
+            return;
+        }
+
+        if let Some(captures) = TSC_RE.captures(line) {
+            let (line_no, column) = match (captures.get(2), captures.get(3)) {
+                (Some(line_no), Some(column)) => (line_no, column),
+                _ => (captures.get(4).expect("tsc line"), captures.get(5).expect("tsc column")),
+            };
+            self.push(
+                "tsc",
+                captures[6].to_string(),
+                &captures[1],
+                parse_number(line_no.as_str()),
+                Some(parse_number(column.as_str())),
+                captures[8].to_string(),
+                Some(captures[7].to_string()),
+            );
+            return;
+        }
+
+        if let Some(captures) = ESLINT_FILE_RE.captures(line) {
+            self.eslint_file = Some(captures[1].to_string());
+            return;
+        }
+
+        if let Some(file) = self.eslint_file.clone() {
+            if let Some(captures) = ESLINT_ENTRY_RE.captures(line) {
+                self.push(
+                    "eslint",
+                    captures[3].to_string(),
+                    &file,
+                    parse_number(&captures[1]),
+                    Some(parse_number(&captures[2])),
+                    captures[4].to_string(),
+                    captures.get(5).map(|rule| rule.as_str().to_string()),
+                );
+                return;
+            }
+        }
+
+        if let Some(captures) = GCC_RE.captures(line) {
+            self.push(
+                "gcc",
+                captures[4].to_string(),
+                &captures[1],
+                parse_number(&captures[2]),
+                Some(parse_number(&captures[3])),
+                captures[5].to_string(),
+                captures.get(6).map(|flag| flag.as_str().to_string()),
+            );
+            return;
+        }
+
+        if let Some(captures) = GO_RE.captures(line) {
+            self.push(
+                "go",
+                "error".to_string(),
+                &captures[1],
+                parse_number(&captures[2]),
+                captures.get(3).map(|column| parse_number(column.as_str())),
+                captures[4].to_string(),
+                None,
+            );
+            return;
+        }
+
+        if let Some(captures) = PYTEST_ERROR_RE.captures(line) {
+            self.pytest_errors.push(captures[1].trim_end().to_string());
+            return;
+        }
+
+        if let Some(captures) = PYTEST_LOCATION_RE.captures(line) {
+            let errors = std::mem::take(&mut self.pytest_errors);
+            let exception = captures[3].to_string();
+            let message = errors
+                .into_iter()
+                .next()
+                .unwrap_or_else(|| exception.clone());
+            self.push(
+                "pytest",
+                "error".to_string(),
+                &captures[1],
+                parse_number(&captures[2]),
+                None,
+                message,
+                Some(exception),
+            );
+        }
+    }
+
+    #[allow(clippy::too_many_arguments)]
+    fn push(
+        &mut self,
+        source: &str,
+        severity: String,
+        file: &str,
+        line: u32,
+        column: Option<u32>,
+        message: String,
+        code: Option<String>,
+    ) {
+        if self.diagnostics.len() >= MAX_DIAGNOSTICS {
+            return;
+        }
+
+        let file = resolve_path(&self.cwd, file.trim());
+        if !self.seen.insert((file.clone(), line, column, message.clone())) {
+            return;
+        }
+
+        self.diagnostics.push(Diagnostic {
+            source: source.to_string(),
+            severity,
+            file,
+            line,
+            column,
+            message,
+            code,
+        });
+    }
+}
+
+/// Joins `file` onto `cwd`. Tools like cargo report paths relative to the
+/// workspace root rather than the cwd, so ancestors are tried when the
+/// direct join doesn't exist.
+fn resolve_path(cwd: &Path, file: &str) -> String {
+    let file = file.strip_prefix("./").unwrap_or(file);
+    let path = Path::new(file);
+    if path.is_absolute() {
+        return file.to_string();
+    }
+
+    cwd.ancestors()
+        .map(|dir| dir.join(path))
+        .find(|candidate| candidate.exists())
+        .unwrap_or_else(|| cwd.join(path))
+        .to_string_lossy()
+        .to_string()
+}
+
+fn parse_number(value: &str) -> u32 {
+    value.parse().unwrap_or_default()
+}
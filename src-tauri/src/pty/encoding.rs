@@ -0,0 +1,43 @@
+use encoding_rs::{Decoder, Encoding, UTF_8};
+
+/// Looks up a session encoding by WHATWG label ("utf-8", "shift_jis", "gbk",
+/// "euc-kr", ...). Note that "latin1"/"iso-8859-1" resolve to windows-1252,
+/// its superset, as browsers do.
+pub fn resolve(label: Option<&str>) -> Result<&'static Encoding, String> {
+    match label.map(str::trim).filter(|label| !label.is_empty()) {
+        Some(label) => Encoding::for_label(label.as_bytes())
+            .ok_or_else(|| format!("unknown session encoding: {label}")),
+        None => Ok(UTF_8),
+    }
+}
+
+/// Streaming decoder for PTY output. Bytes of a multi-byte sequence split
+/// across reads are held back until the rest arrives instead of being
+/// replaced with U+FFFD.
+pub struct StreamDecoder {
+    encoding: &'static Encoding,
+    decoder: Decoder,
+}
+
+impl StreamDecoder {
+    pub fn new(encoding: &'static Encoding) -> Self {
+        Self {
+            encoding,
+            decoder: encoding.new_decoder_without_bom_handling(),
+        }
+    }
+
+    pub fn encoding(&self) -> &'static Encoding {
+        self.encoding
+    }
+
+    pub fn decode(&mut self, bytes: &[u8]) -> String {
+        let capacity = self
+            .decoder
+            .max_utf8_buffer_length(bytes.len())
+            .unwrap_or(bytes.len() * 3 + 16);
+        let mut text = String::with_capacity(capacity);
+        let _ = self.decoder.decode_to_string(bytes, &mut text, false);
+        text
+    }
+}
@@ -0,0 +1,153 @@
+/// Longest OSC payload kept; anything longer is dropped rather than buffered.
+const MAX_OSC_LEN: usize = 4096;
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum TerminalEvent {
+    Title(String),
+    Bell,
+    Notify { title: Option<String>, body: String },
+    /// OSC 7 working directory report from the shell.
+    Cwd(String),
+    /// OSC 133;C: the shell is about to run a command.
+    CommandStart,
+}
+
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+enum State {
+    #[default]
+    Ground,
+    Escape,
+    Osc,
+    OscEscape,
+}
+
+/// Picks title (OSC 0/2), bell (BEL), notification (OSC 9, OSC 777), cwd
+/// (OSC 7) and command start (OSC 133;C) sequences out of decoded PTY
+/// output. State is carried between calls, so a sequence split across reads
+/// is still recognised.
+#[derive(Default)]
+pub struct EscapeTracker {
+    state: State,
+    osc: String,
+    overflowed: bool,
+}
+
+impl EscapeTracker {
+    pub fn feed(&mut self, text: &str) -> Vec<TerminalEvent> {
+        let mut events = Vec::new();
+        let mut rang = false;
+
+        for ch in text.chars() {
+            match self.state {
+                State::Ground => match ch {
+                    '\x1b' => self.state = State::Escape,
+                    '\u{9d}' => self.begin_osc(),
+                    // One bell per read is enough to get attention.
+                    '\x07' if !rang => {
+                        rang = true;
+                        events.push(TerminalEvent::Bell);
+                    }
+                    _ => {}
+                },
+                State::Escape => match ch {
+                    ']' => self.begin_osc(),
+                    '\x1b' => {}
+                    _ => self.state = State::Ground,
+                },
+                State::Osc => match ch {
+                    '\x07' | '\u{9c}' => self.finish_osc(&mut events),
+                    '\x1b' => self.state = State::OscEscape,
+                    _ => self.push_osc(ch),
+                },
+                State::OscEscape => {
+                    if ch == '\\' {
+                        self.finish_osc(&mut events);
+                    } else {
+                        // Any other escape aborts the string, as in xterm.
+                        self.state = if ch == ']' { State::Osc } else { State::Ground };
+                        self.osc.clear();
+                        self.overflowed = false;
+                    }
+                }
+            }
+        }
+
+        events
+    }
+
+    fn begin_osc(&mut self) {
+        self.state = State::Osc;
+        self.osc.clear();
+        self.overflowed = false;
+    }
+
+    fn push_osc(&mut self, ch: char) {
+        if self.osc.len() >= MAX_OSC_LEN {
+            self.overflowed = true;
+        } else {
+            self.osc.push(ch);
+        }
+    }
+
+    fn finish_osc(&mut self, events: &mut Vec<TerminalEvent>) {
+        self.state = State::Ground;
+        let payload = std::mem::take(&mut self.osc);
+        if std::mem::take(&mut self.overflowed) {
+            return;
+        }
+
+        if let Some(event) = parse_osc(&payload) {
+            events.push(event);
+        }
+    }
+}
+
+fn parse_osc(payload: &str) -> Option<TerminalEvent> {
+    let (command, rest) = payload.split_once(';').unwrap_or((payload, ""));
+
+    match command {
+        "0" | "2" => Some(TerminalEvent::Title(rest.to_string())),
+        // OSC 9;4 is ConEmu/Windows Terminal progress, not a notification.
+        "9" if !rest.is_empty() && !rest.starts_with("4;") => Some(TerminalEvent::Notify {
+            title: None,
+            body: rest.to_string(),
+        }),
+        "777" => {
+            let mut parts = rest.splitn(3, ';');
+            if parts.next() != Some("notify") {
+                return None;
+            }
+            let title = parts.next().filter(|title| !title.is_empty()).map(str::to_string);
+            let body = parts.next().unwrap_or_default().to_string();
+            Some(TerminalEvent::Notify { title, body })
+        }
+        "7" => file_url_path(rest).map(TerminalEvent::Cwd),
+        "133" if rest == "C" || rest.starts_with("C;") => Some(TerminalEvent::CommandStart),
+        _ => None,
+    }
+}
+
+/// Path of a `file://host/path` URL, percent-decoded.
+fn file_url_path(url: &str) -> Option<String> {
+    let rest = url.strip_prefix("file://")?;
+    let path = &rest[rest.find('/')?..];
+
+    let bytes = path.as_bytes();
+    let mut decoded = Vec::with_capacity(bytes.len());
+    let mut index = 0;
+    while index < bytes.len() {
+        let hex = path.get(index + 1..index + 3);
+        match (bytes[index], hex.and_then(|hex| u8::from_str_radix(hex, 16).ok())) {
+            (b'%', Some(byte)) => {
+                decoded.push(byte);
+                index += 3;
+            }
+            (byte, _) => {
+                decoded.push(byte);
+                index += 1;
+            }
+        }
+    }
+
+    String::from_utf8(decoded).ok()
+}
@@ -0,0 +1,185 @@
+use std::sync::atomic::{AtomicBool, AtomicU64, Ordering};
+use std::sync::Mutex;
+use std::time::{Duration, Instant};
+
+use serde::{Deserialize, Serialize};
+
+pub const LIMIT_MAX_RUNTIME: &str = "max_runtime";
+pub const LIMIT_IDLE_TIMEOUT: &str = "idle_timeout";
+pub const LIMIT_MAX_OUTPUT_BYTES: &str = "max_output_bytes";
+
+#[derive(Debug, Clone, Serialize, Deserialize, Default)]
+#[serde(rename_all = "camelCase", default)]
+pub struct SessionLimits {
+    pub max_runtime_s: Option<u64>,
+    pub idle_timeout_s: Option<u64>,
+    pub max_output_bytes: Option<u64>,
+    pub cpu_seconds: Option<u64>,
+    pub address_space_mb: Option<u64>,
+    pub open_files: Option<u64>,
+    /// Alert-only thresholds; crossing them emits an event but does not kill.
+    pub cpu_alert_pct: Option<f64>,
+    pub rss_alert_mb: Option<u64>,
+}
+
+impl SessionLimits {
+    pub fn has_rlimits(&self) -> bool {
+        self.cpu_seconds.is_some() || self.address_space_mb.is_some() || self.open_files.is_some()
+    }
+
+    pub fn has_timers(&self) -> bool {
+        self.max_runtime_s.is_some() || self.idle_timeout_s.is_some()
+    }
+}
+
+/// Live counters for a session, shared between the reader, exit and
+/// watchdog threads.
+pub struct SessionActivity {
+    started_at: Instant,
+    last_activity: Mutex<Instant>,
+    output_bytes: AtomicU64,
+    tripped_limit: Mutex<Option<String>>,
+    exited: AtomicBool,
+}
+
+impl Default for SessionActivity {
+    fn default() -> Self {
+        Self {
+            started_at: Instant::now(),
+            last_activity: Mutex::new(Instant::now()),
+            output_bytes: AtomicU64::new(0),
+            tripped_limit: Mutex::new(None),
+            exited: AtomicBool::new(false),
+        }
+    }
+}
+
+impl SessionActivity {
+    pub fn touch(&self) {
+        if let Ok(mut guard) = self.last_activity.lock() {
+            *guard = Instant::now();
+        }
+    }
+
+    /// Counts output bytes and returns the running total.
+    pub fn record_output(&self, len: usize) -> u64 {
+        self.touch();
+        self.output_bytes.fetch_add(len as u64, Ordering::Relaxed) + len as u64
+    }
+
+    pub fn idle_for(&self) -> Duration {
+        self.last_activity
+            .lock()
+            .map(|guard| guard.elapsed())
+            .unwrap_or_default()
+    }
+
+    pub fn running_for(&self) -> Duration {
+        self.started_at.elapsed()
+    }
+
+    /// Records the limit that fired. Returns false if a limit already tripped,
+    /// so the kill path only runs once.
+    pub fn trip(&self, limit: &str) -> bool {
+        let Ok(mut guard) = self.tripped_limit.lock() else {
+            return false;
+        };
+        if guard.is_some() {
+            return false;
+        }
+        *guard = Some(limit.to_string());
+        true
+    }
+
+    pub fn tripped_limit(&self) -> Option<String> {
+        self.tripped_limit.lock().ok().and_then(|guard| guard.clone())
+    }
+
+    pub fn mark_exited(&self) {
+        self.exited.store(true, Ordering::SeqCst);
+    }
+
+    pub fn has_exited(&self) -> bool {
+        self.exited.load(Ordering::SeqCst)
+    }
+}
+
+/// Returns the limit a timer check says should fire, if any.
+pub fn check_timers(limits: &SessionLimits, activity: &SessionActivity) -> Option<&'static str> {
+    if let Some(max_runtime) = limits.max_runtime_s {
+        if activity.running_for() >= Duration::from_secs(max_runtime) {
+            return Some(LIMIT_MAX_RUNTIME);
+        }
+    }
+
+    if let Some(idle_timeout) = limits.idle_timeout_s {
+        if activity.idle_for() >= Duration::from_secs(idle_timeout) {
+            return Some(LIMIT_IDLE_TIMEOUT);
+        }
+    }
+
+    None
+}
+
+/// Wraps the shell in `/bin/sh -c 'ulimit ...; exec "$@"'` so the rlimits are
+/// set in the child right before the real shell is exec'd and are inherited
+/// by everything it starts. The limits are tried in a throwaway `/bin/sh`
+/// first so a refused one fails the spawn; the wrapper also exits with 126
+/// instead of starting an unlimited shell.
+pub fn wrap_rlimits(
+    limits: &SessionLimits,
+    shell: &str,
+    shell_args: &[String],
+) -> Result<(String, Vec<String>), String> {
+    if !limits.has_rlimits() {
+        return Ok((shell.to_string(), shell_args.to_vec()));
+    }
+
+    if cfg!(target_os = "windows") {
+        return Err("rlimits are not supported for Windows sessions".to_string());
+    }
+
+    let mut script = String::new();
+    if let Some(cpu_seconds) = limits.cpu_seconds {
+        script.push_str(&ulimit_line("-t", cpu_seconds, "CPU time"));
+    }
+    if let Some(address_space_mb) = limits.address_space_mb {
+        script.push_str(&ulimit_line(
+            "-v",
+            address_space_mb.saturating_mul(1024),
+            "address space",
+        ));
+    }
+    if let Some(open_files) = limits.open_files {
+        script.push_str(&ulimit_line("-n", open_files, "open files"));
+    }
+
+    let check = std::process::Command::new("/bin/sh")
+        .arg("-c")
+        .arg(&script)
+        .stdin(std::process::Stdio::null())
+        .output()
+        .map_err(|err| format!("failed to check rlimits: {err}"))?;
+    if !check.status.success() {
+        let stderr = String::from_utf8_lossy(&check.stderr);
+        return Err(format!("failed to apply rlimits: {}", stderr.trim()));
+    }
+
+    script.push_str("exec \"$@\"\n");
+
+    let mut args = vec![
+        "-c".to_string(),
+        script,
+        "aion-limits".to_string(),
+        shell.to_string(),
+    ];
+    args.extend(shell_args.iter().cloned());
+
+    Ok(("/bin/sh".to_string(), args))
+}
+
+fn ulimit_line(flag: &str, value: u64, name: &str) -> String {
+    format!(
+        "ulimit {flag} {value} || {{ echo \"aion: cannot limit {name} to {value}\" >&2; exit 126; }}\n"
+    )
+}
@@ -0,0 +1,39 @@
+/// Cuts streamed output into lines. Each chunk is scanned once, starting
+/// where the previous scan stopped, and the unfinished tail is moved once
+/// per chunk rather than once per line, so the cost stays linear in the
+/// size of the output however many lines a chunk holds.
+#[derive(Debug, Default)]
+pub struct LineSplitter {
+    pending: String,
+}
+
+impl LineSplitter {
+    /// Appends `text` and returns the lines it completed, without their
+    /// `\n` or `\r\n` endings.
+    pub fn feed(&mut self, text: &str) -> Vec<String> {
+        // The pending tail holds no newline; only the new text is searched.
+        let mut from = self.pending.len();
+        self.pending.push_str(text);
+
+        let mut lines = Vec::new();
+        let mut start = 0;
+        while let Some(offset) = self.pending[from..].find('\n') {
+            let end = from + offset;
+            lines.push(self.pending[start..end].trim_end_matches('\r').to_string());
+            start = end + 1;
+            from = start;
+        }
+        self.pending.drain(..start);
+
+        lines
+    }
+
+    /// Bytes of the line still waiting for its newline.
+    pub fn pending_len(&self) -> usize {
+        self.pending.len()
+    }
+
+    pub fn clear(&mut self) {
+        self.pending.clear();
+    }
+}
@@ -0,0 +1,162 @@
+use std::path::{Path, PathBuf};
+
+use once_cell::sync::Lazy;
+use regex::Regex;
+use serde::Serialize;
+
+use crate::git::porcelain;
+use crate::pty::sandbox;
+
+/// Links returned for one piece of text.
+const MAX_LINKS: usize = 64;
+
+static PYTHON_RE: Lazy<Regex> =
+    Lazy::new(|| Regex::new(r#"File "([^"]+)", line (\d+)"#).expect("valid PYTHON_RE"));
+static CANDIDATE_RE: Lazy<Regex> = Lazy::new(|| {
+    Regex::new(
+        r#"((?:[A-Za-z]:[\\/])?[^\s:'"`()\[\]{}<>,;|]+)(?::(\d+)(?::(\d+))?|\((\d+),(\d+)\))?"#,
+    )
+    .expect("valid CANDIDATE_RE")
+});
+
+#[derive(Debug, Clone, Serialize)]
+pub struct PathLink {
+    /// The matched text, location suffix included.
+    pub text: String,
+    /// UTF-16 offsets into the input, as JavaScript indexes strings.
+    pub start: usize,
+    pub end: usize,
+    /// Absolute path of an existing file or directory.
+    pub path: String,
+    pub line: Option<u32>,
+    pub column: Option<u32>,
+}
+
+/// Directories a relative path is tried against, in order: the live cwd and
+/// its parents up to the repository root (tools like cargo print paths
+/// relative to the workspace), then the session's spawn cwd.
+pub fn search_dirs(live_cwd: &str, spawn_cwd: &str) -> Vec<PathBuf> {
+    let live = PathBuf::from(live_cwd);
+    let mut dirs = match porcelain::discover_repo_root(live_cwd) {
+        Ok(root) => {
+            let root = PathBuf::from(root);
+            live.ancestors()
+                .take_while(|dir| dir.starts_with(&root))
+                .map(Path::to_path_buf)
+                .collect::<Vec<_>>()
+        }
+        Err(_) => vec![live],
+    };
+
+    let spawn = PathBuf::from(spawn_cwd);
+    if !dirs.contains(&spawn) {
+        dirs.push(spawn);
+    }
+    dirs
+}
+
+/// Finds `path`, `path:line`, `path:line:col`, `path(line,col)` and Python
+/// traceback references in `text` that point at something on disk.
+pub fn find_links(text: &str, dirs: &[PathBuf]) -> Vec<PathLink> {
+    let mut links = Vec::new();
+    let mut taken = Vec::<(usize, usize)>::new();
+
+    for captures in PYTHON_RE.captures_iter(text) {
+        let whole = captures.get(0).expect("whole match");
+        if let Some(path) = resolve(&captures[1], dirs) {
+            taken.push((whole.start(), whole.end()));
+            links.push(link(
+                text,
+                whole.start(),
+                whole.end(),
+                path,
+                captures[2].parse().ok(),
+                None,
+            ));
+        }
+    }
+
+    for captures in CANDIDATE_RE.captures_iter(text) {
+        if links.len() >= MAX_LINKS {
+            break;
+        }
+        let whole = captures.get(0).expect("whole match");
+        if taken
+            .iter()
+            .any(|(start, end)| whole.start() < *end && *start < whole.end())
+        {
+            continue;
+        }
+
+        let candidate = &captures[1];
+        // Bare words and numbers are never worth a filesystem lookup.
+        if !candidate.contains(['/', '\\', '.']) || candidate.chars().all(|ch| ch == '.') {
+            continue;
+        }
+
+        let number = |index: usize| {
+            captures
+                .get(index)
+                .and_then(|value| value.as_str().parse().ok())
+        };
+        let (line, column) = match (number(2), number(4)) {
+            (Some(line), _) => (Some(line), number(3)),
+            (None, Some(line)) => (Some(line), number(5)),
+            (None, None) => (None, None),
+        };
+
+        // "see src/main.rs." ends a sentence, not the file name.
+        let trimmed = candidate.trim_end_matches('.');
+        let resolved = resolve(candidate, dirs)
+            .map(|path| (path, whole.end()))
+            .or_else(|| {
+                (line.is_none() && trimmed.len() < candidate.len())
+                    .then(|| resolve(trimmed, dirs))
+                    .flatten()
+                    .map(|path| (path, whole.start() + trimmed.len()))
+            });
+
+        if let Some((path, end)) = resolved {
+            links.push(link(text, whole.start(), end, path, line, column));
+        }
+    }
+
+    links.sort_by_key(|link| link.start);
+    links
+}
+
+fn resolve(candidate: &str, dirs: &[PathBuf]) -> Option<PathBuf> {
+    let path = sandbox::expand_home(candidate);
+    if path.is_absolute() {
+        return path.exists().then_some(path);
+    }
+
+    // `a/` and `b/` prefix the two sides of a git diff.
+    let stripped = candidate
+        .strip_prefix("a/")
+        .or_else(|| candidate.strip_prefix("b/"));
+
+    std::iter::once(candidate)
+        .chain(stripped)
+        .flat_map(|relative| dirs.iter().map(move |dir| dir.join(relative)))
+        .find(|path| path.exists())
+        .and_then(|path| std::fs::canonicalize(path).ok())
+}
+
+fn link(
+    text: &str,
+    start: usize,
+    end: usize,
+    path: PathBuf,
+    line: Option<u32>,
+    column: Option<u32>,
+) -> PathLink {
+    PathLink {
+        text: text[start..end].to_string(),
+        start: text[..start].encode_utf16().count(),
+        end: text[..end].encode_utf16().count(),
+        path: path.to_string_lossy().to_string(),
+        line,
+        column,
+    }
+}
@@ -0,0 +1,368 @@
+use std::fs::{File, OpenOptions};
+use std::collections::HashSet;
+use std::io::{BufReader, BufWriter, Read, Write};
+use std::path::{Path, PathBuf};
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant, SystemTime};
+
+use chrono::Utc;
+use flate2::read::GzDecoder;
+use flate2::write::GzEncoder;
+use flate2::Compression;
+use once_cell::sync::Lazy;
+use serde::{Deserialize, Serialize};
+
+use crate::pty::sandbox;
+use crate::pty::secrets;
+
+/// Parts still being written by a live session, which `prune` leaves alone
+/// however old their mtime; an idle session's part can outlive `max_age_days`.
+static OPEN_PARTS: Lazy<Mutex<HashSet<PathBuf>>> = Lazy::new(|| Mutex::new(HashSet::new()));
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase", default)]
+pub struct LogConfig {
+    /// Log every session. Individual sessions can opt in or out at spawn.
+    pub enabled: bool,
+    /// Defaults to `logs/` under the app data dir. `~/` is expanded.
+    pub dir: Option<String>,
+    /// Keep escape sequences instead of stripping them.
+    pub raw: bool,
+    pub max_bytes: u64,
+    /// Start a new part after this many hours even if it is still small.
+    pub max_part_hours: u64,
+    pub max_age_days: u64,
+    pub compress: bool,
+}
+
+impl Default for LogConfig {
+    fn default() -> Self {
+        Self {
+            enabled: false,
+            dir: None,
+            raw: false,
+            max_bytes: 10 * 1024 * 1024,
+            max_part_hours: 24,
+            max_age_days: 30,
+            compress: true,
+        }
+    }
+}
+
+impl LogConfig {
+    fn max_age(&self) -> Duration {
+        Duration::from_secs(self.max_age_days * 24 * 60 * 60)
+    }
+
+    fn max_part_age(&self) -> Duration {
+        Duration::from_secs(self.max_part_hours * 60 * 60)
+    }
+
+    pub fn resolve_dir(&self, default_dir: &Path) -> PathBuf {
+        self.dir
+            .as_deref()
+            .filter(|dir| !dir.is_empty())
+            .map(sandbox::expand_home)
+            .unwrap_or_else(|| default_dir.to_path_buf())
+    }
+}
+
+/// Timestamped, line-oriented log of one session's output. Files are named
+/// `<session>-<started>-<part>.log`; a part is closed and gzipped once it
+/// grows past `max_bytes` or has been open for `max_part_hours`, and when
+/// the session ends. Old logs are pruned on open and on every rotation,
+/// except parts other sessions are still writing.
+/// Secrets are redacted before anything is written.
+pub struct SessionLog {
+    config: LogConfig,
+    dir: PathBuf,
+    prefix: String,
+    part: u32,
+    writer: BufWriter<File>,
+    path: PathBuf,
+    written: u64,
+    opened_at: Instant,
+    pending: String,
+    current_path: Arc<Mutex<Option<String>>>,
+}
+
+impl SessionLog {
+    /// Opens the first part and prunes logs older than `max_age_days`.
+    /// `current_path` is kept up to date with the file being written.
+    pub fn open(
+        config: &LogConfig,
+        dir: PathBuf,
+        session_id: &str,
+        current_path: Arc<Mutex<Option<String>>>,
+    ) -> Result<Self, String> {
+        std::fs::create_dir_all(&dir)
+            .map_err(|err| format!("failed to create log dir '{}': {err}", dir.display()))?;
+        prune(&dir, config.max_age());
+
+        let prefix = format!(
+            "{}-{}",
+            session_slug(session_id),
+            Utc::now().format("%Y%m%dT%H%M%S")
+        );
+        let path = part_path(&dir, &prefix, 1);
+        let writer = open_part(&path)?;
+        set_current(&current_path, &path);
+
+        Ok(Self {
+            config: config.clone(),
+            dir,
+            prefix,
+            part: 1,
+            writer,
+            path,
+            written: 0,
+            opened_at: Instant::now(),
+            pending: String::new(),
+            current_path,
+        })
+    }
+
+    pub fn write(&mut self, text: &str) {
+        self.pending.push_str(text);
+
+        while let Some(pos) = self.pending.find('\n') {
+            let line = self.pending[..pos].trim_end_matches('\r').to_string();
+            self.pending.drain(..=pos);
+            self.write_line(&line);
+        }
+        let _ = self.writer.flush();
+    }
+
+    /// Drops any partial line; used when a secret span starts.
+    pub fn discard_pending(&mut self) {
+        self.pending.clear();
+    }
+
+    pub fn finish(mut self) {
+        if !self.pending.is_empty() {
+            let line = std::mem::take(&mut self.pending);
+            self.write_line(&line);
+        }
+        let _ = self.writer.flush();
+        set_open(&self.path, false);
+        if self.config.compress {
+            if let Some(compressed) = compress(&self.path) {
+                set_current(&self.current_path, &compressed);
+            }
+        }
+    }
+
+    fn write_line(&mut self, line: &str) {
+        let line = if self.config.raw {
+            line.to_string()
+        } else {
+            strip_ansi_escapes::strip_str(line)
+        };
+        let record = format!("[{}] {}\n", Utc::now().to_rfc3339(), secrets::redact(&line));
+
+        if self.writer.write_all(record.as_bytes()).is_err() {
+            return;
+        }
+        self.written += record.len() as u64;
+
+        let too_big = self.config.max_bytes > 0 && self.written >= self.config.max_bytes;
+        let too_old = self.config.max_part_hours > 0
+            && self.opened_at.elapsed() >= self.config.max_part_age();
+        if too_big || too_old {
+            self.rotate();
+        }
+    }
+
+    fn rotate(&mut self) {
+        let next_path = part_path(&self.dir, &self.prefix, self.part + 1);
+        let Ok(writer) = open_part(&next_path) else {
+            return;
+        };
+
+        let closed = std::mem::replace(&mut self.path, next_path);
+        let _ = std::mem::replace(&mut self.writer, writer).flush();
+        set_open(&closed, false);
+        self.part += 1;
+        self.written = 0;
+        self.opened_at = Instant::now();
+        set_current(&self.current_path, &self.path);
+
+        let compress_closed = self.config.compress;
+        let dir = self.dir.clone();
+        let max_age = self.config.max_age();
+        std::thread::spawn(move || {
+            if compress_closed {
+                compress(&closed);
+            }
+            prune(&dir, max_age);
+        });
+    }
+}
+
+/// Newest log file for a session in `dir`, compressed or not.
+pub fn latest_log(dir: &Path, session_id: &str) -> Option<PathBuf> {
+    let prefix = format!("{}-", session_slug(session_id));
+    std::fs::read_dir(dir)
+        .ok()?
+        .flatten()
+        .map(|entry| entry.path())
+        .filter(|path| {
+            path.file_name()
+                .and_then(|name| name.to_str())
+                .is_some_and(|name| name.starts_with(&prefix) && is_log_file(name))
+        })
+        .max_by_key(|path| {
+            path.file_name()
+                .and_then(|name| name.to_str())
+                .map(|name| name.trim_end_matches(".gz").to_string())
+        })
+}
+
+/// Every part of a session's log concatenated in order, with the timestamp
+/// prefixes removed. `None` when the session has no log in `dir`.
+pub fn read_session_log(dir: &Path, session_id: &str) -> Result<Option<String>, String> {
+    let Some(latest) = latest_log(dir, session_id) else {
+        return Ok(None);
+    };
+    // All parts of the latest run share its `<session>-<started>-` prefix.
+    let Some(run_prefix) = latest
+        .file_name()
+        .and_then(|name| name.to_str())
+        .and_then(|name| name.rsplit_once('-'))
+        .map(|(prefix, _)| format!("{prefix}-"))
+    else {
+        return Ok(None);
+    };
+
+    let mut parts = std::fs::read_dir(dir)
+        .map_err(|err| format!("failed to read log dir '{}': {err}", dir.display()))?
+        .flatten()
+        .map(|entry| entry.path())
+        .filter(|path| {
+            path.file_name()
+                .and_then(|name| name.to_str())
+                .is_some_and(|name| name.starts_with(&run_prefix) && is_log_file(name))
+        })
+        .collect::<Vec<_>>();
+    parts.sort();
+
+    let mut text = String::new();
+    for part in parts {
+        let mut content = String::new();
+        let file = File::open(&part)
+            .map_err(|err| format!("failed to open log '{}': {err}", part.display()))?;
+        let read = if part.extension().is_some_and(|ext| ext == "gz") {
+            GzDecoder::new(file).read_to_string(&mut content)
+        } else {
+            BufReader::new(file).read_to_string(&mut content)
+        };
+        read.map_err(|err| format!("failed to read log '{}': {err}", part.display()))?;
+
+        for line in content.lines() {
+            let line = line
+                .strip_prefix('[')
+                .and_then(|rest| rest.split_once("] "))
+                .map(|(_, line)| line)
+                .unwrap_or(line);
+            text.push_str(line);
+            text.push('\n');
+        }
+    }
+
+    Ok(Some(text))
+}
+
+fn open_part(path: &Path) -> Result<BufWriter<File>, String> {
+    let file = OpenOptions::new()
+        .create(true)
+        .append(true)
+        .open(path)
+        .map_err(|err| format!("failed to open session log '{}': {err}", path.display()))?;
+    set_open(path, true);
+    Ok(BufWriter::new(file))
+}
+
+fn set_open(path: &Path, open: bool) {
+    if let Ok(mut guard) = OPEN_PARTS.lock() {
+        if open {
+            guard.insert(path.to_path_buf());
+        } else {
+            guard.remove(path);
+        }
+    }
+}
+
+fn part_path(dir: &Path, prefix: &str, part: u32) -> PathBuf {
+    dir.join(format!("{prefix}-{part:03}.log"))
+}
+
+fn set_current(current_path: &Arc<Mutex<Option<String>>>, path: &Path) {
+    if let Ok(mut guard) = current_path.lock() {
+        *guard = Some(path.to_string_lossy().to_string());
+    }
+}
+
+fn compress(path: &Path) -> Option<PathBuf> {
+    let target = PathBuf::from(format!("{}.gz", path.display()));
+    let result = (|| -> std::io::Result<()> {
+        let mut source = File::open(path)?;
+        let mut encoder = GzEncoder::new(File::create(&target)?, Compression::default());
+        std::io::copy(&mut source, &mut encoder)?;
+        encoder.finish()?;
+        std::fs::remove_file(path)
+    })();
+
+    match result {
+        Ok(()) => Some(target),
+        Err(err) => {
+            log::warn!("failed to compress session log '{}': {err}", path.display());
+            let _ = std::fs::remove_file(&target);
+            None
+        }
+    }
+}
+
+fn prune(dir: &Path, max_age: Duration) {
+    if max_age.is_zero() {
+        return;
+    }
+    let Ok(entries) = std::fs::read_dir(dir) else {
+        return;
+    };
+
+    let cutoff = SystemTime::now() - max_age;
+    for entry in entries.flatten() {
+        let name = entry.file_name();
+        if !name.to_str().is_some_and(is_log_file) {
+            continue;
+        }
+        let open = OPEN_PARTS
+            .lock()
+            .map(|guard| guard.contains(&entry.path()))
+            .unwrap_or(true);
+        if open {
+            continue;
+        }
+        let modified = entry.metadata().and_then(|metadata| metadata.modified());
+        if modified.is_ok_and(|modified| modified < cutoff) {
+            let _ = std::fs::remove_file(entry.path());
+        }
+    }
+}
+
+fn is_log_file(name: &str) -> bool {
+    name.ends_with(".log") || name.ends_with(".log.gz")
+}
+
+fn session_slug(session_id: &str) -> String {
+    session_id
+        .chars()
+        .map(|ch| {
+            if ch.is_ascii_alphanumeric() || ch == '-' || ch == '_' {
+                ch
+            } else {
+                '_'
+            }
+        })
+        .collect()
+}
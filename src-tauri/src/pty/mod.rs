@@ -1,24 +1,55 @@
+pub mod agents;
+pub mod diagnostics;
+pub mod encoding;
+pub mod escapes;
+pub mod limits;
+pub mod lines;
+pub mod links;
+pub mod logging;
+pub mod ports;
+pub mod resources;
+pub mod sandbox;
 pub mod scraper;
+pub mod scrollback;
+pub mod secrets;
 pub mod session;
+pub mod terminal;
+pub mod test_runs;
+pub mod transcript;
+pub mod usage_writer;
 
 use std::collections::HashMap;
 use std::path::PathBuf;
 use std::sync::{Arc, Mutex};
 
+use tauri::{AppHandle, Emitter};
+
+use diagnostics::DiagnosticsStore;
+use resources::ResourceMonitor;
 use scraper::TokenScraper;
+use scrollback::ScrollbackStore;
 use session::PtySession;
 
 #[derive(Clone)]
 pub struct PtyManager {
     sessions: Arc<Mutex<HashMap<String, PtySession>>>,
     scraper: Arc<TokenScraper>,
+    resources: ResourceMonitor,
+    scrollback: ScrollbackStore,
+    diagnostics: DiagnosticsStore,
 }
 
 impl PtyManager {
-    pub fn new(db_path: PathBuf) -> Self {
+    pub fn new(app: AppHandle, db_path: PathBuf) -> Self {
+        let on_captured = move |payload| {
+            let _ = app.emit("token:captured", payload);
+        };
         Self {
             sessions: Arc::new(Mutex::new(HashMap::new())),
-            scraper: Arc::new(TokenScraper::new(db_path)),
+            scraper: Arc::new(TokenScraper::new(db_path, on_captured)),
+            resources: ResourceMonitor::default(),
+            scrollback: ScrollbackStore::default(),
+            diagnostics: DiagnosticsStore::default(),
         }
     }
 
@@ -29,4 +60,16 @@ impl PtyManager {
     pub fn scraper(&self) -> Arc<TokenScraper> {
         Arc::clone(&self.scraper)
     }
+
+    pub fn resources(&self) -> ResourceMonitor {
+        self.resources.clone()
+    }
+
+    pub fn scrollback(&self) -> ScrollbackStore {
+        self.scrollback.clone()
+    }
+
+    pub fn diagnostics(&self) -> DiagnosticsStore {
+        self.diagnostics.clone()
+    }
 }
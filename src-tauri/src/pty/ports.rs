@@ -0,0 +1,158 @@
+use std::collections::{HashMap, HashSet};
+use std::time::Duration;
+
+use serde::Serialize;
+
+use crate::pty::resources;
+
+pub const PORT_SCAN_INTERVAL: Duration = Duration::from_secs(2);
+
+/// `st` value for TCP_LISTEN in `/proc/<pid>/net/tcp{,6}`.
+const TCP_LISTEN: &str = "0A";
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct ListeningPort {
+    pub port: u16,
+    pub address: String,
+    pub protocol: String,
+    pub pid: i64,
+}
+
+impl ListeningPort {
+    /// Identity used to diff scans; the owning pid may change when a server
+    /// forks workers, which is not reported as a new port.
+    fn key(&self) -> (String, String, u16) {
+        (self.protocol.clone(), self.address.clone(), self.port)
+    }
+}
+
+/// TCP sockets in LISTEN state held by any process in the tree rooted at
+/// `root_pid`. Socket inodes from `/proc/<pid>/net/tcp{,6}` are matched
+/// against `socket:[inode]` links under each process's `/proc/<pid>/fd`.
+/// The socket tables are read once per network namespace in the tree, so
+/// servers inside a sandbox started with `--unshare-net` are seen too.
+pub fn listening_ports(root_pid: i64) -> Vec<ListeningPort> {
+    let tree = resources::process_tree(root_pid);
+
+    let mut namespaces = HashSet::new();
+    let mut sockets = HashMap::new();
+    for pid in &tree {
+        let namespace = std::fs::read_link(format!("/proc/{pid}/ns/net")).ok();
+        if !namespaces.insert(namespace) {
+            continue;
+        }
+        read_listen_sockets(&format!("/proc/{pid}/net/tcp"), "tcp", &mut sockets);
+        read_listen_sockets(&format!("/proc/{pid}/net/tcp6"), "tcp6", &mut sockets);
+    }
+    if sockets.is_empty() {
+        return Vec::new();
+    }
+
+    let mut ports = Vec::new();
+    for pid in tree {
+        let Ok(fds) = std::fs::read_dir(format!("/proc/{pid}/fd")) else {
+            continue;
+        };
+
+        for fd in fds.flatten() {
+            let Ok(target) = std::fs::read_link(fd.path()) else {
+                continue;
+            };
+            let Some(inode) = target
+                .to_str()
+                .and_then(|target| target.strip_prefix("socket:["))
+                .and_then(|rest| rest.strip_suffix(']'))
+                .and_then(|inode| inode.parse::<u64>().ok())
+            else {
+                continue;
+            };
+
+            if let Some((protocol, address, port)) = sockets.remove(&inode) {
+                ports.push(ListeningPort {
+                    port,
+                    address,
+                    protocol,
+                    pid,
+                });
+            }
+        }
+    }
+
+    ports.sort_by_key(|port| (port.port, port.protocol.clone()));
+    ports
+}
+
+/// Splits a new scan against the previous one into (opened, closed).
+pub fn diff(
+    previous: &[ListeningPort],
+    current: &[ListeningPort],
+) -> (Vec<ListeningPort>, Vec<ListeningPort>) {
+    let opened = current
+        .iter()
+        .filter(|port| !previous.iter().any(|old| old.key() == port.key()))
+        .cloned()
+        .collect();
+    let closed = previous
+        .iter()
+        .filter(|port| !current.iter().any(|new| new.key() == port.key()))
+        .cloned()
+        .collect();
+
+    (opened, closed)
+}
+
+fn read_listen_sockets(
+    path: &str,
+    protocol: &str,
+    sockets: &mut HashMap<u64, (String, String, u16)>,
+) {
+    let Ok(text) = std::fs::read_to_string(path) else {
+        return;
+    };
+
+    // sl local_address rem_address st tx_queue:rx_queue tr:tm->when retrnsmt uid timeout inode
+    for line in text.lines().skip(1) {
+        let fields = line.split_whitespace().collect::<Vec<_>>();
+        if fields.len() < 10 || fields[3] != TCP_LISTEN {
+            continue;
+        }
+
+        let Some((address, port)) = parse_socket_address(fields[1]) else {
+            continue;
+        };
+        let Ok(inode) = fields[9].parse::<u64>() else {
+            continue;
+        };
+        if inode == 0 {
+            continue;
+        }
+
+        sockets.insert(inode, (protocol.to_string(), address, port));
+    }
+}
+
+/// Parses `0100007F:1F90` (IPv4) or the 32-digit IPv6 form. Addresses are
+/// written as native-endian 32-bit words, hence the per-word byte swap.
+fn parse_socket_address(value: &str) -> Option<(String, u16)> {
+    let (address, port) = value.split_once(':')?;
+    let port = u16::from_str_radix(port, 16).ok()?;
+
+    let words = (0..address.len() / 8)
+        .map(|index| u32::from_str_radix(&address[index * 8..index * 8 + 8], 16).ok())
+        .collect::<Option<Vec<_>>>()?;
+    let bytes = words
+        .iter()
+        .flat_map(|word| word.to_ne_bytes())
+        .collect::<Vec<_>>();
+
+    let address = match bytes.len() {
+        4 => std::net::Ipv4Addr::new(bytes[0], bytes[1], bytes[2], bytes[3]).to_string(),
+        16 => {
+            let octets: [u8; 16] = bytes.try_into().ok()?;
+            std::net::Ipv6Addr::from(octets).to_string()
+        }
+        _ => return None,
+    };
+
+    Some((address, port))
+}
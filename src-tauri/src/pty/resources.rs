@@ -0,0 +1,330 @@
+use std::collections::{HashMap, VecDeque};
+use std::path::Path;
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant};
+
+use chrono::Utc;
+use serde::Serialize;
+
+use crate::pty::limits::SessionLimits;
+
+pub const SAMPLE_INTERVAL: Duration = Duration::from_secs(2);
+
+/// Samples kept per session; at the default interval this is five minutes.
+pub const MAX_SAMPLES: usize = 150;
+
+#[derive(Debug, Clone, Serialize)]
+pub struct ResourceSample {
+    pub sampled_at: String,
+    pub processes: usize,
+    pub cpu_pct: f64,
+    pub rss_bytes: u64,
+    pub read_bytes: u64,
+    pub write_bytes: u64,
+    pub read_bps: f64,
+    pub write_bps: f64,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct ResourceUsage {
+    pub session_id: String,
+    pub latest: Option<ResourceSample>,
+    pub samples: Vec<ResourceSample>,
+}
+
+/// Short in-memory time series of resource samples, keyed by session.
+#[derive(Clone, Default)]
+pub struct ResourceMonitor {
+    series: Arc<Mutex<HashMap<String, VecDeque<ResourceSample>>>>,
+}
+
+impl ResourceMonitor {
+    pub fn push(&self, session_id: &str, sample: ResourceSample) {
+        let Ok(mut guard) = self.series.lock() else {
+            return;
+        };
+
+        let series = guard.entry(session_id.to_string()).or_default();
+        if series.len() == MAX_SAMPLES {
+            series.pop_front();
+        }
+        series.push_back(sample);
+    }
+
+    pub fn usage(&self, session_id: &str) -> ResourceUsage {
+        let samples = self
+            .series
+            .lock()
+            .ok()
+            .and_then(|guard| guard.get(session_id).cloned())
+            .map(Vec::from)
+            .unwrap_or_default();
+
+        ResourceUsage {
+            session_id: session_id.to_string(),
+            latest: samples.last().cloned(),
+            samples,
+        }
+    }
+
+    pub fn remove(&self, session_id: &str) {
+        if let Ok(mut guard) = self.series.lock() {
+            guard.remove(session_id);
+        }
+    }
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct ThresholdCrossing {
+    pub resource: &'static str,
+    pub value: f64,
+    pub threshold: f64,
+}
+
+/// Edge-triggered threshold checks: a crossing is reported once when a
+/// resource goes over its threshold and re-arms after it drops back below.
+#[derive(Default)]
+pub struct ThresholdWatch {
+    cpu_over: bool,
+    rss_over: bool,
+}
+
+impl ThresholdWatch {
+    pub fn check(&mut self, limits: &SessionLimits, sample: &ResourceSample) -> Vec<ThresholdCrossing> {
+        let mut crossings = Vec::new();
+
+        if let Some(threshold) = limits.cpu_alert_pct {
+            let over = sample.cpu_pct > threshold;
+            if over && !self.cpu_over {
+                crossings.push(ThresholdCrossing {
+                    resource: "cpu",
+                    value: sample.cpu_pct,
+                    threshold,
+                });
+            }
+            self.cpu_over = over;
+        }
+
+        if let Some(threshold_mb) = limits.rss_alert_mb {
+            let threshold = threshold_mb.saturating_mul(1024 * 1024) as f64;
+            let value = sample.rss_bytes as f64;
+            let over = value > threshold;
+            if over && !self.rss_over {
+                crossings.push(ThresholdCrossing {
+                    resource: "rss",
+                    value,
+                    threshold,
+                });
+            }
+            self.rss_over = over;
+        }
+
+        crossings
+    }
+}
+
+/// Turns successive `/proc` readings of a process tree into samples with
+/// CPU percentage and I/O rates.
+pub struct TreeSampler {
+    root_pid: i64,
+    ticks_per_sec: f64,
+    previous: Option<(Instant, TreeTotals)>,
+}
+
+#[derive(Debug, Clone, Copy, Default)]
+struct TreeTotals {
+    processes: usize,
+    cpu_ticks: u64,
+    rss_bytes: u64,
+    read_bytes: u64,
+    write_bytes: u64,
+}
+
+impl TreeSampler {
+    pub fn new(root_pid: i64) -> Self {
+        Self {
+            root_pid,
+            ticks_per_sec: clock_ticks_per_sec(),
+            previous: None,
+        }
+    }
+
+    /// Reads the tree once. Returns `None` when the root process is gone or
+    /// `/proc` is unavailable.
+    pub fn sample(&mut self) -> Option<ResourceSample> {
+        let now = Instant::now();
+        let totals = read_tree(self.root_pid)?;
+
+        let (cpu_pct, read_bps, write_bps) = match self.previous {
+            Some((at, previous)) => {
+                let elapsed = now.duration_since(at).as_secs_f64().max(f64::EPSILON);
+                let cpu_secs = totals.cpu_ticks.saturating_sub(previous.cpu_ticks) as f64
+                    / self.ticks_per_sec;
+                (
+                    cpu_secs / elapsed * 100.0,
+                    totals.read_bytes.saturating_sub(previous.read_bytes) as f64 / elapsed,
+                    totals.write_bytes.saturating_sub(previous.write_bytes) as f64 / elapsed,
+                )
+            }
+            None => (0.0, 0.0, 0.0),
+        };
+
+        self.previous = Some((now, totals));
+
+        Some(ResourceSample {
+            sampled_at: Utc::now().to_rfc3339(),
+            processes: totals.processes,
+            cpu_pct,
+            rss_bytes: totals.rss_bytes,
+            read_bytes: totals.read_bytes,
+            write_bytes: totals.write_bytes,
+            read_bps,
+            write_bps,
+        })
+    }
+}
+
+/// Every pid in the tree rooted at `root_pid`, root first. Walks down from
+/// the root through `/proc/<pid>/task/*/children`; kernels built without
+/// those files fall back to reading every process's parent.
+pub fn process_tree(root_pid: i64) -> Vec<i64> {
+    let mut tree = vec![root_pid];
+    let mut cursor = 0;
+    while cursor < tree.len() {
+        match read_children(tree[cursor]) {
+            Some(children) => tree.extend(children),
+            None if cursor == 0 => return scan_tree(root_pid),
+            None => {}
+        }
+        cursor += 1;
+    }
+
+    tree
+}
+
+fn read_children(pid: i64) -> Option<Vec<i64>> {
+    let mut children = Vec::new();
+    for task in std::fs::read_dir(format!("/proc/{pid}/task")).ok()?.flatten() {
+        let text = std::fs::read_to_string(task.path().join("children")).ok()?;
+        children.extend(text.split_whitespace().filter_map(|child| child.parse::<i64>().ok()));
+    }
+    Some(children)
+}
+
+fn scan_tree(root_pid: i64) -> Vec<i64> {
+    let Ok(entries) = std::fs::read_dir("/proc") else {
+        return Vec::new();
+    };
+
+    let mut children = HashMap::<i64, Vec<i64>>::new();
+    for entry in entries.flatten() {
+        let Some(pid) = entry
+            .file_name()
+            .to_str()
+            .and_then(|name| name.parse::<i64>().ok())
+        else {
+            continue;
+        };
+        if let Some(stat) = read_stat(pid) {
+            children.entry(stat.ppid).or_default().push(pid);
+        }
+    }
+
+    let mut tree = vec![root_pid];
+    let mut cursor = 0;
+    while cursor < tree.len() {
+        if let Some(kids) = children.get(&tree[cursor]) {
+            tree.extend(kids);
+        }
+        cursor += 1;
+    }
+
+    tree
+}
+
+fn read_tree(root_pid: i64) -> Option<TreeTotals> {
+    if root_pid <= 0 || !Path::new(&format!("/proc/{root_pid}")).exists() {
+        return None;
+    }
+
+    let mut totals = TreeTotals::default();
+    for pid in process_tree(root_pid) {
+        let Some(stat) = read_stat(pid) else {
+            continue;
+        };
+        totals.processes += 1;
+        // Children that exited and were reaped inside the tree move their
+        // time into the parent's cutime/cstime, so it doesn't drop out.
+        totals.cpu_ticks += stat.utime + stat.stime + stat.cutime + stat.cstime;
+        totals.rss_bytes += read_rss_bytes(pid).unwrap_or_default();
+        if let Some((read_bytes, write_bytes)) = read_io(pid) {
+            totals.read_bytes += read_bytes;
+            totals.write_bytes += write_bytes;
+        }
+    }
+
+    Some(totals)
+}
+
+struct ProcStat {
+    ppid: i64,
+    utime: u64,
+    stime: u64,
+    cutime: u64,
+    cstime: u64,
+}
+
+fn read_stat(pid: i64) -> Option<ProcStat> {
+    let text = std::fs::read_to_string(format!("/proc/{pid}/stat")).ok()?;
+    // The command name is parenthesised and may itself contain spaces or
+    // parens, so fields are counted from the last ')'.
+    let rest = &text[text.rfind(')')? + 1..];
+    let fields = rest.split_whitespace().collect::<Vec<_>>();
+
+    Some(ProcStat {
+        ppid: fields.get(1)?.parse().ok()?,
+        utime: fields.get(11)?.parse().ok()?,
+        stime: fields.get(12)?.parse().ok()?,
+        cutime: fields.get(13)?.parse().ok()?,
+        cstime: fields.get(14)?.parse().ok()?,
+    })
+}
+
+fn read_rss_bytes(pid: i64) -> Option<u64> {
+    let text = std::fs::read_to_string(format!("/proc/{pid}/status")).ok()?;
+    let line = text.lines().find(|line| line.starts_with("VmRSS:"))?;
+    let kb = line.split_whitespace().nth(1)?.parse::<u64>().ok()?;
+    Some(kb * 1024)
+}
+
+fn read_io(pid: i64) -> Option<(u64, u64)> {
+    let text = std::fs::read_to_string(format!("/proc/{pid}/io")).ok()?;
+    let mut read_bytes = None;
+    let mut write_bytes = None;
+
+    for line in text.lines() {
+        if let Some(value) = line.strip_prefix("read_bytes:") {
+            read_bytes = value.trim().parse::<u64>().ok();
+        } else if let Some(value) = line.strip_prefix("write_bytes:") {
+            write_bytes = value.trim().parse::<u64>().ok();
+        }
+    }
+
+    Some((read_bytes?, write_bytes?))
+}
+
+#[cfg(unix)]
+fn clock_ticks_per_sec() -> f64 {
+    // SAFETY: sysconf has no preconditions.
+    let ticks = unsafe { libc::sysconf(libc::_SC_CLK_TCK) };
+    if ticks > 0 {
+        ticks as f64
+    } else {
+        100.0
+    }
+}
+
+#[cfg(not(unix))]
+fn clock_ticks_per_sec() -> f64 {
+    100.0
+}
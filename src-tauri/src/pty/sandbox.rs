@@ -0,0 +1,215 @@
+use std::path::{Path, PathBuf};
+
+use serde::{Deserialize, Serialize};
+
+use crate::commands::settings;
+use crate::git::porcelain;
+
+#[derive(Debug, Clone, Serialize, Deserialize, Default)]
+#[serde(rename_all = "camelCase", default)]
+pub struct SandboxProfile {
+    /// Keep the host network reachable. Off by default.
+    pub network: bool,
+    /// Extra paths the session may write to besides the project directory,
+    /// e.g. `~/.claude` for agent state. `~/` is expanded.
+    pub writable_paths: Vec<String>,
+    /// Force `bwrap` or `unshare`; picks bubblewrap when it is installed.
+    pub backend: Option<String>,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct SandboxInfo {
+    pub backend: String,
+    pub network: bool,
+    pub writable_paths: Vec<String>,
+}
+
+pub struct SandboxCommand {
+    pub program: String,
+    pub args: Vec<String>,
+    pub info: SandboxInfo,
+}
+
+/// Wraps the shell invocation so it runs inside user, mount and (optionally)
+/// network namespaces: the root filesystem is read-only, while the project
+/// directory, its git dir and the profile's allowlisted paths stay writable.
+pub fn wrap(
+    profile: &SandboxProfile,
+    shell: &str,
+    shell_args: &[String],
+    cwd: &str,
+) -> Result<SandboxCommand, String> {
+    if !cfg!(target_os = "linux") {
+        return Err("sandboxed sessions are only supported on Linux".to_string());
+    }
+
+    let backend = match profile.backend.as_deref() {
+        Some("bwrap") => "bwrap",
+        Some("unshare") => "unshare",
+        Some(other) => return Err(format!("unknown sandbox backend: {other}")),
+        None if settings::which_in_path("bwrap") => "bwrap",
+        None => "unshare",
+    };
+
+    if !settings::which_in_path(backend) {
+        return Err(format!("sandbox backend not found in PATH: {backend}"));
+    }
+
+    let mut writable = project_paths(cwd);
+    writable.extend(profile.writable_paths.iter().map(|path| expand_home(path)));
+    writable.retain(|path| path.exists());
+    writable.dedup();
+
+    let writable = writable
+        .iter()
+        .map(|path| path.to_string_lossy().to_string())
+        .collect::<Vec<_>>();
+
+    let args = if backend == "bwrap" {
+        bwrap_args(profile, &writable, shell, shell_args, cwd)
+    } else {
+        unshare_args(profile, &writable, shell, shell_args)
+    };
+
+    Ok(SandboxCommand {
+        program: backend.to_string(),
+        args,
+        info: SandboxInfo {
+            backend: backend.to_string(),
+            network: profile.network,
+            writable_paths: writable,
+        },
+    })
+}
+
+fn bwrap_args(
+    profile: &SandboxProfile,
+    writable: &[String],
+    shell: &str,
+    shell_args: &[String],
+    cwd: &str,
+) -> Vec<String> {
+    let mut args = vec![
+        "--ro-bind".to_string(),
+        "/".to_string(),
+        "/".to_string(),
+        // The PTY slave lives in the host devpts, so /dev is passed through.
+        "--dev-bind".to_string(),
+        "/dev".to_string(),
+        "/dev".to_string(),
+        "--proc".to_string(),
+        "/proc".to_string(),
+        "--tmpfs".to_string(),
+        "/tmp".to_string(),
+    ];
+
+    for path in writable {
+        args.push("--bind".to_string());
+        args.push(path.clone());
+        args.push(path.clone());
+    }
+
+    args.extend(
+        [
+            "--unshare-user",
+            "--unshare-ipc",
+            "--unshare-pid",
+            "--unshare-uts",
+            "--unshare-cgroup-try",
+            "--die-with-parent",
+        ]
+        .map(str::to_string),
+    );
+
+    if !profile.network {
+        args.push("--unshare-net".to_string());
+    }
+
+    args.push("--chdir".to_string());
+    args.push(cwd.to_string());
+    args.push("--".to_string());
+    args.push(shell.to_string());
+    args.extend(shell_args.iter().cloned());
+
+    args
+}
+
+fn unshare_args(
+    profile: &SandboxProfile,
+    writable: &[String],
+    shell: &str,
+    shell_args: &[String],
+) -> Vec<String> {
+    // Without bubblewrap the read-only view is built by hand: bind the
+    // writable paths onto themselves, then remount every other mount
+    // read-only before exec'ing the shell. The host /tmp stays writable.
+    let mut script = String::from("set -e\nmount --make-rprivate /\n");
+    let mut skip_patterns = vec!["/proc|/proc/*|/dev|/dev/*|/sys|/sys/*|/tmp|/tmp/*".to_string()];
+
+    for path in writable {
+        let quoted = shell_quote(path);
+        script.push_str(&format!("mount --bind {quoted} {quoted}\n"));
+        skip_patterns.push(format!("{quoted}|{quoted}/*"));
+    }
+
+    script.push_str(&format!(
+        "awk '{{print $2}}' /proc/self/mounts | while read -r mp; do\n  case \"$mp\" in\n    {}) continue ;;\n  esac\n  mount -o remount,bind,ro \"$mp\" 2>/dev/null || true\ndone\nexec \"$@\"\n",
+        skip_patterns.join("|")
+    ));
+
+    let mut args = vec![
+        "--user".to_string(),
+        "--map-root-user".to_string(),
+        "--mount".to_string(),
+        "--pid".to_string(),
+        "--fork".to_string(),
+        "--kill-child".to_string(),
+    ];
+
+    if !profile.network {
+        args.push("--net".to_string());
+    }
+
+    args.extend([
+        "--".to_string(),
+        "/bin/sh".to_string(),
+        "-c".to_string(),
+        script,
+        "aion-sandbox".to_string(),
+        shell.to_string(),
+    ]);
+    args.extend(shell_args.iter().cloned());
+
+    args
+}
+
+/// The directories a session must be able to write: the repo workdir plus
+/// its git dir (and the shared git dir for linked worktrees), or just the
+/// cwd outside a repository.
+fn project_paths(cwd: &str) -> Vec<PathBuf> {
+    let Ok(repo) = porcelain::open_repo(cwd) else {
+        return vec![std::fs::canonicalize(cwd).unwrap_or_else(|_| PathBuf::from(cwd))];
+    };
+
+    let mut paths = vec![porcelain::repo_root(&repo, cwd)];
+    let git_dir = repo.path().to_path_buf();
+    if repo.is_worktree() {
+        if let Some(common_dir) = git_dir.parent().and_then(Path::parent) {
+            paths.push(common_dir.to_path_buf());
+        }
+    }
+    paths.push(git_dir);
+
+    paths
+}
+
+pub(crate) fn expand_home(path: &str) -> PathBuf {
+    match (path.strip_prefix("~/"), std::env::var_os("HOME")) {
+        (Some(rest), Some(home)) => PathBuf::from(home).join(rest),
+        _ => PathBuf::from(path),
+    }
+}
+
+fn shell_quote(value: &str) -> String {
+    format!("'{}'", value.replace('\'', r"'\''"))
+}
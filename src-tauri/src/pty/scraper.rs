@@ -1,192 +1,236 @@
-use std::collections::HashMap;
+use std::collections::{HashMap, HashSet};
 use std::path::PathBuf;
-use std::sync::Mutex;
-
-use once_cell::sync::Lazy;
-use regex::Regex;
-use rusqlite::{params, Connection};
-
-static COST_RE: Lazy<Regex> =
-    Lazy::new(|| Regex::new(r"Total cost:\s*\$(\d+\.\d{2})").expect("valid COST_RE"));
-static TOKENS_RE: Lazy<Regex> = Lazy::new(|| {
-    Regex::new(r"Total tokens:\s*([\d,]+)\s*\(in:\s*([\d.,KM]+),\s*out:\s*([\d.,KM]+)\)")
-        .expect("valid TOKENS_RE")
-});
-static DURATION_RE: Lazy<Regex> =
-    Lazy::new(|| Regex::new(r"Duration:\s*(?:(\d+)m)?\s*(\d+)s").expect("valid DURATION_RE"));
-
-#[derive(Default, Clone)]
-struct ParseState {
-    cost_usd: Option<f64>,
-    tokens_in: Option<i64>,
-    tokens_out: Option<i64>,
-    tokens_total: Option<i64>,
-    duration_s: Option<i64>,
-    raw_lines: Vec<String>,
-}
+use std::sync::mpsc::{self, Sender};
+use std::sync::{Arc, Mutex};
 
-#[derive(Default)]
-pub struct TokenScraper {
-    db_path: PathBuf,
-    line_buffers: Mutex<HashMap<String, String>>,
-    parse_states: Mutex<HashMap<String, ParseState>>,
+use crate::pty::agents::{self, AgentParser, CompiledRule, RuleError, ScrapeRule};
+use crate::pty::lines::LineSplitter;
+use crate::pty::usage_writer::{self, Summary, TokenCapturedPayload, WriterMessage};
+
+/// Longest partial line buffered while waiting for a newline.
+const MAX_PENDING_LINE: usize = 64 * 1024;
+
+/// Parsers for one session. Until an agent is detected every parser sees
+/// the output, and the first to produce a summary claims the session.
+struct SessionAgents {
+    parsers: Vec<Box<dyn AgentParser>>,
+    detected: Option<usize>,
 }
 
-impl TokenScraper {
-    pub fn new(db_path: PathBuf) -> Self {
+impl SessionAgents {
+    fn new(rules: &[Arc<CompiledRule>]) -> Self {
         Self {
-            db_path,
-            line_buffers: Mutex::new(HashMap::new()),
-            parse_states: Mutex::new(HashMap::new()),
+            parsers: agents::all_parsers(rules),
+            detected: None,
         }
     }
 
-    pub fn ingest(&self, session_id: &str, data: &[u8]) -> usize {
-        let stripped = strip_ansi_escapes::strip(data);
-        let text = String::from_utf8_lossy(&stripped);
-
-        let mut emitted_lines = Vec::<String>::new();
-
-        {
-            let mut buffers = match self.line_buffers.lock() {
-                Ok(guard) => guard,
-                Err(_) => return 0,
-            };
+    fn agent(&self) -> Option<&str> {
+        self.detected.map(|index| self.parsers[index].agent())
+    }
 
-            let buf = buffers.entry(session_id.to_string()).or_default();
-            buf.push_str(&text);
+    fn detect(&mut self, index: usize) {
+        self.detected = Some(index);
+        for parser in &mut self.parsers {
+            parser.reset();
+        }
+    }
 
-            while let Some(pos) = buf.find('\n') {
-                let line = buf[..pos].trim_end_matches('\r').to_string();
-                emitted_lines.push(line);
-                *buf = buf[pos + 1..].to_string();
-            }
+    /// A summary completed by `line`, or a reset when `line` is a banner.
+    fn parse_line(&mut self, session_id: &str, line: &str) -> Option<WriterMessage> {
+        // Only a banner moves a claimed session to another parser, e.g. when
+        // one agent exits and another starts in the same shell. A banner
+        // always starts a fresh run, even for the agent already detected,
+        // so cumulative totals from the previous run no longer apply.
+        if let Some(index) = self.parsers.iter().position(|parser| parser.detect(line)) {
+            self.detect(index);
+            return Some(WriterMessage::Reset {
+                session_id: session_id.to_string(),
+            });
         }
 
-        let mut inserts = 0;
-        for line in emitted_lines {
-            if self.parse_line(session_id, &line) {
-                inserts += 1;
+        let (index, usage) = match self.detected {
+            Some(index) => (index, self.parsers[index].parse_line(line)?),
+            None => {
+                let (index, usage) = self.parsers.iter_mut().enumerate().find_map(
+                    |(index, parser)| parser.parse_line(line).map(|usage| (index, usage)),
+                )?;
+                // The first summary claims the session for its parser.
+                self.detect(index);
+                (index, usage)
             }
-        }
+        };
 
-        inserts
+        let parser = &self.parsers[index];
+        Some(WriterMessage::Usage(Summary {
+            session_id: session_id.to_string(),
+            agent: parser.agent().to_string(),
+            cumulative: parser.cumulative(),
+            usage,
+        }))
     }
+}
 
-    fn parse_line(&self, session_id: &str, line: &str) -> bool {
-        let mut states = match self.parse_states.lock() {
-            Ok(guard) => guard,
-            Err(_) => return false,
-        };
+/// What `ingest` found in a chunk of output.
+#[derive(Debug, Default)]
+pub struct Ingested {
+    /// Summaries queued for the writer thread.
+    pub summaries: usize,
+    /// Agent whose banner was printed, i.e. one that just started.
+    pub started: Option<String>,
+}
 
-        let state = states.entry(session_id.to_string()).or_default();
+pub struct TokenScraper {
+    writer: Sender<WriterMessage>,
+    line_buffers: Mutex<HashMap<String, LineSplitter>>,
+    agents: Mutex<HashMap<String, SessionAgents>>,
+    rules: Mutex<Vec<Arc<CompiledRule>>>,
+    rule_errors: Mutex<Vec<RuleError>>,
+    suppressed: Mutex<HashSet<String>>,
+}
 
-        if line.contains("Total cost:") || line.contains("Total tokens:") || line.contains("Duration:") {
-            state.raw_lines.push(line.to_string());
+impl TokenScraper {
+    /// `on_captured` is called from the writer thread once rows are stored.
+    pub fn new(
+        db_path: PathBuf,
+        on_captured: impl Fn(TokenCapturedPayload) + Send + 'static,
+    ) -> Self {
+        Self {
+            writer: usage_writer::spawn(db_path, on_captured),
+            line_buffers: Mutex::new(HashMap::new()),
+            agents: Mutex::new(HashMap::new()),
+            rules: Mutex::new(Vec::new()),
+            rule_errors: Mutex::new(Vec::new()),
+            suppressed: Mutex::new(HashSet::new()),
         }
+    }
 
-        if let Some(captures) = COST_RE.captures(line) {
-            state.cost_usd = captures.get(1).and_then(|m| m.as_str().parse::<f64>().ok());
+    /// Compiles user-defined rules and installs the valid ones. Sessions
+    /// start over with the new rule set, which drops any half-read summary.
+    pub fn set_rules(&self, rules: &[ScrapeRule]) -> Vec<RuleError> {
+        let (compiled, errors) = agents::compile_rules(rules);
+        if let Ok(mut guard) = self.rules.lock() {
+            *guard = compiled;
         }
-
-        if let Some(captures) = TOKENS_RE.captures(line) {
-            let tokens_total = captures
-                .get(1)
-                .and_then(|m| parse_int_with_commas(m.as_str()));
-            let tokens_in = captures.get(2).and_then(|m| parse_compact_int(m.as_str()));
-            let tokens_out = captures.get(3).and_then(|m| parse_compact_int(m.as_str()));
-
-            state.tokens_total = tokens_total;
-            state.tokens_in = tokens_in;
-            state.tokens_out = tokens_out;
+        if let Ok(mut guard) = self.rule_errors.lock() {
+            *guard = errors.clone();
         }
-
-        if let Some(captures) = DURATION_RE.captures(line) {
-            let mins = captures
-                .get(1)
-                .and_then(|m| m.as_str().parse::<i64>().ok())
-                .unwrap_or(0);
-            let secs = captures
-                .get(2)
-                .and_then(|m| m.as_str().parse::<i64>().ok())
-                .unwrap_or(0);
-            state.duration_s = Some(mins * 60 + secs);
+        if let Ok(mut guard) = self.agents.lock() {
+            guard.clear();
         }
+        errors
+    }
 
-        let is_complete = state.cost_usd.is_some()
-            && state.tokens_total.is_some()
-            && state.tokens_in.is_some()
-            && state.tokens_out.is_some()
-            && (state.duration_s.is_some() || line.trim().is_empty());
-
-        if is_complete {
-            let raw = state.raw_lines.join("\n");
-            let persisted = self.persist_usage(
-                session_id,
-                state.cost_usd.unwrap_or_default(),
-                state.tokens_in.unwrap_or_default(),
-                state.tokens_out.unwrap_or_default(),
-                state.tokens_total.unwrap_or_default(),
-                state.duration_s,
-                &raw,
-            );
-
-            *state = ParseState::default();
-
-            return persisted.is_ok();
-        }
+    /// Agents whose summaries are session totals, including user rules.
+    pub fn cumulative_agents(&self) -> Vec<String> {
+        let rules = self
+            .rules
+            .lock()
+            .map(|guard| guard.clone())
+            .unwrap_or_default();
+        let mut agents = agents::all_parsers(&rules)
+            .iter()
+            .filter(|parser| parser.cumulative())
+            .map(|parser| parser.agent().to_string())
+            .collect::<Vec<_>>();
+        agents.sort();
+        agents.dedup();
+        agents
+    }
 
-        false
+    /// Problems found when the current rules were compiled.
+    pub fn rule_errors(&self) -> Vec<RuleError> {
+        self.rule_errors
+            .lock()
+            .map(|guard| guard.clone())
+            .unwrap_or_default()
     }
 
-    fn persist_usage(
-        &self,
-        session_id: &str,
-        cost_usd: f64,
-        tokens_in: i64,
-        tokens_out: i64,
-        tokens_total: i64,
-        duration_s: Option<i64>,
-        raw_output: &str,
-    ) -> Result<(), String> {
-        let conn = Connection::open(&self.db_path)
-            .map_err(|err| format!("failed to open db for scraper insert: {err}"))?;
-
-        conn.execute(
-            "INSERT INTO token_usage (session_id, agent, cost_usd, tokens_in, tokens_out, tokens_total, duration_s, raw_output) VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8)",
-            params![
-                session_id,
-                "claude-code",
-                cost_usd,
-                tokens_in,
-                tokens_out,
-                tokens_total,
-                duration_s,
-                raw_output,
-            ],
-        )
-        .map_err(|err| format!("failed to insert token usage: {err}"))?;
-
-        Ok(())
+    /// Called for output read while the terminal is in secret (no-echo) mode.
+    /// The partial line holding the prompt is dropped, and so is the rest of
+    /// the line that follows once echo comes back, where typed input ends.
+    pub fn suppress(&self, session_id: &str) {
+        if let Ok(mut buffers) = self.line_buffers.lock() {
+            buffers.remove(session_id);
+        }
+        if let Ok(mut suppressed) = self.suppressed.lock() {
+            suppressed.insert(session_id.to_string());
+        }
     }
-}
 
-fn parse_int_with_commas(input: &str) -> Option<i64> {
-    input.replace(',', "").parse::<i64>().ok()
-}
+    /// Takes output already decoded with the session's encoding. Summaries
+    /// found are queued for the writer thread.
+    pub fn ingest(&self, session_id: &str, text: &str) -> Ingested {
+        let mut text = strip_ansi_escapes::strip_str(text);
+
+        // Still on the line a secret was typed on: skip to its end.
+        let suppressed = self
+            .suppressed
+            .lock()
+            .map(|guard| guard.contains(session_id))
+            .unwrap_or(false);
+        if suppressed {
+            let Some(pos) = text.find('\n') else {
+                return Ingested::default();
+            };
+            text.drain(..=pos);
+            if let Ok(mut guard) = self.suppressed.lock() {
+                guard.remove(session_id);
+            }
+        }
 
-fn parse_compact_int(input: &str) -> Option<i64> {
-    let normalized = input.replace(',', "").trim().to_ascii_uppercase();
+        let lines = {
+            let mut buffers = match self.line_buffers.lock() {
+                Ok(guard) => guard,
+                Err(_) => return Ingested::default(),
+            };
 
-    if let Some(number) = normalized.strip_suffix('K') {
-        let parsed = number.parse::<f64>().ok()?;
-        return Some((parsed * 1_000.0).round() as i64);
-    }
+            let splitter = buffers.entry(session_id.to_string()).or_default();
+            let lines = splitter.feed(&text);
+            if splitter.pending_len() > MAX_PENDING_LINE {
+                splitter.clear();
+            }
+            lines
+        };
 
-    if let Some(number) = normalized.strip_suffix('M') {
-        let parsed = number.parse::<f64>().ok()?;
-        return Some((parsed * 1_000_000.0).round() as i64);
+        let mut agents = match self.agents.lock() {
+            Ok(guard) => guard,
+            Err(_) => return Ingested::default(),
+        };
+        let session = agents.entry(session_id.to_string()).or_insert_with(|| {
+            let rules = self
+                .rules
+                .lock()
+                .map(|guard| guard.clone())
+                .unwrap_or_default();
+            SessionAgents::new(&rules)
+        });
+
+        let mut ingested = Ingested::default();
+        for line in lines {
+            let Some(message) = session.parse_line(session_id, &line) else {
+                continue;
+            };
+            let is_usage = matches!(message, WriterMessage::Usage(_));
+            if !is_usage {
+                ingested.started = session.agent().map(str::to_string);
+            }
+            if self.writer.send(message).is_ok() && is_usage {
+                ingested.summaries += 1;
+            }
+        }
+
+        ingested
     }
 
-    normalized.parse::<i64>().ok()
+    /// Waits until every summary queued so far has been handled. Fails if
+    /// any of them couldn't be stored.
+    pub fn flush(&self) -> Result<(), String> {
+        let (done, wait) = mpsc::channel();
+        self.writer
+            .send(WriterMessage::Flush(done))
+            .map_err(|_| "usage writer has stopped".to_string())?;
+        wait.recv()
+            .map_err(|_| "usage writer has stopped".to_string())?
+    }
 }
@@ -0,0 +1,152 @@
+use std::collections::{HashMap, VecDeque};
+use std::sync::{Arc, Mutex};
+
+use chrono::Utc;
+use serde::Serialize;
+
+use crate::pty::session::SessionInfo;
+
+/// Decoded output kept per session, escape sequences included.
+pub const MAX_SCROLLBACK_BYTES: usize = 4 * 1024 * 1024;
+
+/// Exited sessions whose scrollback is kept for export.
+const MAX_CLOSED_SESSIONS: usize = 16;
+
+/// Backend copy of each session's output, so transcripts and lookups work
+/// when the session's pane isn't mounted and for a while after it exits.
+/// Output read while echo was off is never stored.
+#[derive(Clone, Default)]
+pub struct ScrollbackStore {
+    inner: Arc<Mutex<StoreInner>>,
+}
+
+#[derive(Default)]
+struct StoreInner {
+    buffers: HashMap<String, Scrollback>,
+    closed: VecDeque<String>,
+}
+
+#[derive(Debug, Clone)]
+pub struct Scrollback {
+    pub info: SessionInfo,
+    pub ended_at: Option<String>,
+    pub exit_code: Option<i32>,
+    /// Absolute number of the first line still held; earlier lines were
+    /// trimmed to stay under `MAX_SCROLLBACK_BYTES`.
+    pub first_line: u64,
+    pub text: String,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct ScrollbackPage {
+    pub session_id: String,
+    pub first_line: u64,
+    pub start_line: u64,
+    pub total_lines: u64,
+    pub lines: Vec<String>,
+}
+
+impl Scrollback {
+    /// Absolute line count, trimmed lines included.
+    pub fn total_lines(&self) -> u64 {
+        self.first_line + self.text.matches('\n').count() as u64
+    }
+
+    /// `count` lines starting at absolute line `start_line`, clamped to what
+    /// is still held.
+    pub fn page(&self, start_line: u64, count: u64) -> ScrollbackPage {
+        let start_line = start_line.max(self.first_line);
+        let skip = (start_line - self.first_line) as usize;
+
+        ScrollbackPage {
+            session_id: self.info.session_id.clone(),
+            first_line: self.first_line,
+            start_line,
+            total_lines: self.total_lines(),
+            lines: self
+                .text
+                .split('\n')
+                .skip(skip)
+                .take(count as usize)
+                .map(|line| line.trim_end_matches('\r').to_string())
+                .collect(),
+        }
+    }
+}
+
+impl ScrollbackStore {
+    pub fn begin(&self, info: SessionInfo) {
+        let Ok(mut guard) = self.inner.lock() else {
+            return;
+        };
+
+        guard.closed.retain(|session_id| session_id != &info.session_id);
+        guard.buffers.insert(
+            info.session_id.clone(),
+            Scrollback {
+                info,
+                ended_at: None,
+                exit_code: None,
+                first_line: 0,
+                text: String::new(),
+            },
+        );
+    }
+
+    pub fn push(&self, session_id: &str, text: &str) {
+        let Ok(mut guard) = self.inner.lock() else {
+            return;
+        };
+        let Some(scrollback) = guard.buffers.get_mut(session_id) else {
+            return;
+        };
+
+        scrollback.text.push_str(text);
+        if scrollback.text.len() > MAX_SCROLLBACK_BYTES {
+            // Trim a quarter at a time, on a line boundary, so line numbers
+            // stay meaningful and trimming isn't done on every read.
+            let mut excess = scrollback.text.len() - MAX_SCROLLBACK_BYTES * 3 / 4;
+            while !scrollback.text.is_char_boundary(excess) {
+                excess += 1;
+            }
+            let cut = scrollback.text[excess..]
+                .find('\n')
+                .map(|pos| excess + pos + 1)
+                .unwrap_or(excess);
+
+            scrollback.first_line += scrollback.text[..cut].matches('\n').count() as u64;
+            scrollback.text.drain(..cut);
+        }
+    }
+
+    /// Marks the session closed. `info` refreshes the metadata captured at
+    /// spawn (title, ports) when the session is still known.
+    pub fn end(&self, session_id: &str, exit_code: i32, info: Option<SessionInfo>) {
+        let Ok(mut guard) = self.inner.lock() else {
+            return;
+        };
+        let Some(scrollback) = guard.buffers.get_mut(session_id) else {
+            return;
+        };
+
+        if let Some(info) = info {
+            scrollback.info = info;
+        }
+        scrollback.ended_at = Some(Utc::now().to_rfc3339());
+        scrollback.exit_code = Some(exit_code);
+
+        guard.closed.push_back(session_id.to_string());
+        while guard.closed.len() > MAX_CLOSED_SESSIONS {
+            if let Some(evicted) = guard.closed.pop_front() {
+                guard.buffers.remove(&evicted);
+            }
+        }
+    }
+
+    pub fn get(&self, session_id: &str) -> Option<Scrollback> {
+        self.inner
+            .lock()
+            .ok()
+            .and_then(|guard| guard.buffers.get(session_id).cloned())
+    }
+}
@@ -0,0 +1,79 @@
+use std::borrow::Cow;
+
+use once_cell::sync::Lazy;
+use portable_pty::MasterPty;
+use regex::Regex;
+
+const REDACTED: &str = "[REDACTED]";
+
+/// Common credential formats. Each match is replaced wholesale except for the
+/// `keep` capture group, which preserves the label in `Authorization: Bearer`
+/// or `API_KEY=` style assignments.
+static SECRET_PATTERNS: Lazy<Vec<Regex>> = Lazy::new(|| {
+    [
+        // Anthropic, OpenAI (including sk-proj-) and similar `sk-` keys.
+        r"\bsk-[A-Za-z0-9_-]{20,}",
+        // GitHub personal, OAuth, app and refresh tokens.
+        r"\bgh[pousr]_[A-Za-z0-9]{30,}",
+        r"\bgithub_pat_[A-Za-z0-9_]{22,}",
+        // AWS access key ids.
+        r"\b(?:AKIA|ASIA)[0-9A-Z]{16}\b",
+        // Google API keys.
+        r"\bAIza[0-9A-Za-z_-]{35}",
+        // Slack tokens.
+        r"\bxox[abprs]-[A-Za-z0-9-]{10,}",
+        // Bearer tokens in headers or curl invocations.
+        r"(?i)(?P<keep>\bbearer\s+)[A-Za-z0-9._~+/=-]{16,}",
+        // KEY=value / key: value assignments for obviously secret names.
+        r#"(?i)(?P<keep>\b[A-Z0-9_]*(?:API_?KEY|SECRET|TOKEN|PASSWORD|PASSWD)(?:_[A-Z0-9_]*)?\b["']?\s*[:=]\s*["']?)[^\s"']{8,}"#,
+    ]
+    .iter()
+    .map(|pattern| Regex::new(pattern).expect("valid SECRET_PATTERNS"))
+    .collect()
+});
+
+/// Masks anything that looks like a credential. Applied to text before it is
+/// written to `aion.db` or any other persisted store.
+pub fn redact(text: &str) -> Cow<'_, str> {
+    let mut redacted = Cow::Borrowed(text);
+
+    for pattern in SECRET_PATTERNS.iter() {
+        if !pattern.is_match(&redacted) {
+            continue;
+        }
+        let replaced = pattern
+            .replace_all(&redacted, |captures: &regex::Captures<'_>| {
+                let keep = captures.name("keep").map(|m| m.as_str()).unwrap_or_default();
+                format!("{keep}{REDACTED}")
+            })
+            .into_owned();
+        redacted = Cow::Owned(replaced);
+    }
+
+    redacted
+}
+
+/// True while the terminal is reading a secret: echo is off but the line
+/// discipline is still canonical, which is what `read -s`, sudo, ssh and
+/// other password prompts set up. Full-screen programs that switch to raw
+/// mode also clear ECHO, but they clear ICANON too, so they don't count.
+#[cfg(unix)]
+pub fn echo_disabled(master: &(dyn MasterPty + Send)) -> bool {
+    let Some(fd) = master.as_raw_fd() else {
+        return false;
+    };
+
+    // SAFETY: tcgetattr only writes into the termios we hand it; the fd is
+    // the session's master, which the caller keeps alive.
+    let mut termios = unsafe { std::mem::zeroed::<libc::termios>() };
+    if unsafe { libc::tcgetattr(fd, &mut termios) } != 0 {
+        return false;
+    }
+
+    termios.c_lflag & libc::ECHO == 0 && termios.c_lflag & libc::ICANON != 0
+}
+
+#[cfg(not(unix))]
+pub fn echo_disabled(_master: &(dyn MasterPty + Send)) -> bool {
+    false
+}
@@ -1,10 +1,17 @@
 use std::io::Write;
 use std::sync::{Arc, Mutex};
+use std::time::Duration;
 
 use chrono::{DateTime, Utc};
-use portable_pty::{Child, MasterPty};
+use encoding_rs::Encoding;
+use portable_pty::{Child, ChildKiller, MasterPty};
 use serde::Serialize;
 
+use crate::git::worktree::WorktreeInfo;
+use crate::pty::limits::{SessionActivity, SessionLimits};
+use crate::pty::ports::ListeningPort;
+use crate::pty::sandbox::SandboxInfo;
+
 #[derive(Clone)]
 pub struct PtySession {
     pub session_id: String,
@@ -12,18 +19,35 @@ pub struct PtySession {
     pub cwd: String,
     pub pid: i64,
     pub started_at: DateTime<Utc>,
+    pub worktree: Option<WorktreeInfo>,
+    pub sandbox: Option<SandboxInfo>,
+    pub limits: Option<SessionLimits>,
+    encoding: Arc<Mutex<&'static Encoding>>,
     master: Arc<Mutex<Box<dyn MasterPty + Send>>>,
     writer: Arc<Mutex<Box<dyn Write + Send>>>,
     child: Arc<Mutex<Box<dyn Child + Send>>>,
+    killer: Arc<Mutex<Box<dyn ChildKiller + Send + Sync>>>,
+    activity: Arc<SessionActivity>,
+    ports: Arc<Mutex<Vec<ListeningPort>>>,
+    title: Arc<Mutex<Option<String>>>,
+    log_path: Arc<Mutex<Option<String>>>,
+    reported_cwd: Arc<Mutex<Option<String>>>,
 }
 
-#[derive(Debug, Clone, Serialize)]
+#[derive(Debug, Clone, Default, Serialize)]
 pub struct SessionInfo {
     pub session_id: String,
     pub shell: String,
     pub cwd: String,
     pub pid: i64,
     pub started_at: String,
+    pub worktree: Option<WorktreeInfo>,
+    pub sandbox: Option<SandboxInfo>,
+    pub limits: Option<SessionLimits>,
+    pub ports: Vec<ListeningPort>,
+    pub encoding: String,
+    pub title: Option<String>,
+    pub log_path: Option<String>,
 }
 
 impl PtySession {
@@ -34,6 +58,10 @@ impl PtySession {
         cwd: String,
         pid: i64,
         started_at: DateTime<Utc>,
+        worktree: Option<WorktreeInfo>,
+        sandbox: Option<SandboxInfo>,
+        limits: Option<SessionLimits>,
+        encoding: &'static Encoding,
         master: Box<dyn MasterPty + Send>,
         writer: Box<dyn Write + Send>,
         child: Box<dyn Child + Send>,
@@ -44,9 +72,19 @@ impl PtySession {
             cwd,
             pid,
             started_at,
+            worktree,
+            sandbox,
+            limits,
+            encoding: Arc::new(Mutex::new(encoding)),
             master: Arc::new(Mutex::new(master)),
             writer: Arc::new(Mutex::new(writer)),
+            killer: Arc::new(Mutex::new(child.clone_killer())),
             child: Arc::new(Mutex::new(child)),
+            activity: Arc::new(SessionActivity::default()),
+            ports: Arc::new(Mutex::new(Vec::new())),
+            title: Arc::new(Mutex::new(None)),
+            log_path: Arc::new(Mutex::new(None)),
+            reported_cwd: Arc::new(Mutex::new(None)),
         }
     }
 
@@ -57,6 +95,17 @@ impl PtySession {
             cwd: self.cwd.clone(),
             pid: self.pid,
             started_at: self.started_at.to_rfc3339(),
+            worktree: self.worktree.clone(),
+            sandbox: self.sandbox.clone(),
+            limits: self.limits.clone(),
+            ports: self
+                .ports
+                .lock()
+                .map(|guard| guard.clone())
+                .unwrap_or_default(),
+            encoding: self.current_encoding().name().to_string(),
+            title: self.title.lock().ok().and_then(|guard| guard.clone()),
+            log_path: self.log_path.lock().ok().and_then(|guard| guard.clone()),
         }
     }
 
@@ -71,4 +120,102 @@ impl PtySession {
     pub fn child(&self) -> Arc<Mutex<Box<dyn Child + Send>>> {
         Arc::clone(&self.child)
     }
+
+    pub fn activity(&self) -> Arc<SessionActivity> {
+        Arc::clone(&self.activity)
+    }
+
+    /// Shared with the reader thread, which swaps its decoder when the
+    /// encoding is changed mid-session.
+    pub fn encoding(&self) -> Arc<Mutex<&'static Encoding>> {
+        Arc::clone(&self.encoding)
+    }
+
+    pub fn current_encoding(&self) -> &'static Encoding {
+        self.encoding
+            .lock()
+            .map(|guard| *guard)
+            .unwrap_or(encoding_rs::UTF_8)
+    }
+
+    pub fn ports(&self) -> Arc<Mutex<Vec<ListeningPort>>> {
+        Arc::clone(&self.ports)
+    }
+
+    pub fn title(&self) -> Arc<Mutex<Option<String>>> {
+        Arc::clone(&self.title)
+    }
+
+    pub fn log_path(&self) -> Arc<Mutex<Option<String>>> {
+        Arc::clone(&self.log_path)
+    }
+
+    /// Last directory the shell reported with OSC 7.
+    pub fn reported_cwd(&self) -> Arc<Mutex<Option<String>>> {
+        Arc::clone(&self.reported_cwd)
+    }
+
+    /// Where the session is now: the foreground process's cwd where the OS
+    /// exposes it, then the shell's OSC 7 report, then the spawn cwd.
+    pub fn live_cwd(&self) -> String {
+        let foreground = self
+            .master
+            .lock()
+            .ok()
+            .and_then(|master| foreground_cwd(master.as_ref()));
+
+        foreground
+            .or_else(|| self.reported_cwd.lock().ok().and_then(|guard| guard.clone()))
+            .unwrap_or_else(|| self.cwd.clone())
+    }
+
+    /// Graceful kill path: SIGHUP (TerminateProcess on Windows) right away,
+    /// then SIGKILL to the process group if it is still alive after `grace`.
+    /// Uses a cloned killer so it never waits on the exit thread's lock.
+    pub fn terminate(&self, grace: Duration) {
+        if let Ok(mut killer) = self.killer.lock() {
+            let _ = killer.kill();
+        }
+
+        let activity = self.activity();
+        let pid = self.pid;
+        std::thread::spawn(move || {
+            std::thread::sleep(grace);
+            if !activity.has_exited() {
+                force_kill(pid);
+            }
+        });
+    }
+}
+
+#[cfg(target_os = "linux")]
+fn foreground_cwd(master: &(dyn MasterPty + Send)) -> Option<String> {
+    let leader = master.process_group_leader()?;
+    std::fs::read_link(format!("/proc/{leader}/cwd"))
+        .ok()
+        .map(|path| path.to_string_lossy().to_string())
+}
+
+#[cfg(not(target_os = "linux"))]
+fn foreground_cwd(_master: &(dyn MasterPty + Send)) -> Option<String> {
+    None
+}
+
+#[cfg(unix)]
+fn force_kill(pid: i64) {
+    let Ok(pid) = i32::try_from(pid) else {
+        return;
+    };
+    if pid <= 0 {
+        return;
+    }
+
+    // SAFETY: plain signal delivery; a stale pid at worst gets ESRCH.
+    unsafe {
+        libc::kill(-pid, libc::SIGKILL);
+        libc::kill(pid, libc::SIGKILL);
+    }
 }
+
+#[cfg(not(unix))]
+fn force_kill(_pid: i64) {}
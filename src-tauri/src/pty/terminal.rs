@@ -0,0 +1,99 @@
+use std::path::{Path, PathBuf};
+use std::process::Command;
+
+use crate::commands::settings::{self, TerminalIdentity};
+
+const TERMINFO_SOURCE: &str = include_str!("../../terminfo/aion.terminfo");
+const TERMINFO_NAME: &str = "aion";
+const FALLBACK_LOCALE: &str = "en_US.UTF-8";
+
+/// Environment describing the terminal to programs in the session. It is
+/// applied before the profile's `defaultEnv` and per-spawn env, so either can
+/// still override any of it.
+pub fn identity_env(identity: &TerminalIdentity, terminfo_dir: &Path) -> Vec<(String, String)> {
+    let mut env = vec![("TERM".to_string(), identity.term.clone())];
+
+    if let Some(colorterm) = identity.colorterm.as_ref().filter(|value| !value.is_empty()) {
+        env.push(("COLORTERM".to_string(), colorterm.clone()));
+    }
+
+    if identity.term_program {
+        env.push(("TERM_PROGRAM".to_string(), "Aion".to_string()));
+        env.push((
+            "TERM_PROGRAM_VERSION".to_string(),
+            env!("CARGO_PKG_VERSION").to_string(),
+        ));
+    }
+
+    // Apps started from a desktop launcher often have no locale at all,
+    // which makes shells and agents fall back to ASCII output.
+    match identity.locale.as_ref().filter(|value| !value.is_empty()) {
+        Some(locale) => {
+            env.push(("LANG".to_string(), locale.clone()));
+            env.push(("LC_CTYPE".to_string(), locale.clone()));
+        }
+        None if std::env::var_os("LANG").is_none() && std::env::var_os("LC_ALL").is_none() => {
+            env.push(("LANG".to_string(), FALLBACK_LOCALE.to_string()));
+        }
+        None => {}
+    }
+
+    if identity.bundled_terminfo {
+        match install_terminfo(terminfo_dir) {
+            Ok(()) => env.push((
+                "TERMINFO".to_string(),
+                terminfo_dir.to_string_lossy().to_string(),
+            )),
+            Err(err) => log::warn!("failed to install bundled terminfo: {err}"),
+        }
+    }
+
+    env
+}
+
+/// Compiles the bundled `aion` terminfo entry into a private directory with
+/// `tic`. ncurses searches `$TERMINFO` first and then the system database, so
+/// pointing sessions at it doesn't hide any other entries.
+pub fn install_terminfo(dir: &Path) -> Result<(), String> {
+    if compiled_entry(dir).is_some() {
+        return Ok(());
+    }
+
+    if !settings::which_in_path("tic") {
+        return Err("tic not found in PATH".to_string());
+    }
+
+    std::fs::create_dir_all(dir)
+        .map_err(|err| format!("failed to create terminfo dir: {err}"))?;
+
+    let source = dir.join("aion.terminfo");
+    std::fs::write(&source, TERMINFO_SOURCE)
+        .map_err(|err| format!("failed to write terminfo source: {err}"))?;
+
+    let output = Command::new("tic")
+        .arg("-x")
+        .arg("-o")
+        .arg(dir)
+        .arg(&source)
+        .output()
+        .map_err(|err| format!("failed to run tic: {err}"))?;
+
+    if !output.status.success() {
+        return Err(format!(
+            "tic failed: {}",
+            String::from_utf8_lossy(&output.stderr).trim()
+        ));
+    }
+
+    compiled_entry(dir)
+        .map(|_| ())
+        .ok_or_else(|| "tic did not produce the aion entry".to_string())
+}
+
+/// ncurses uses `a/aion`; macOS's tic uses the hex directory `61/aion`.
+fn compiled_entry(dir: &Path) -> Option<PathBuf> {
+    ["a", "61"]
+        .iter()
+        .map(|subdir| dir.join(subdir).join(TERMINFO_NAME))
+        .find(|path| path.exists())
+}
@@ -0,0 +1,235 @@
+use once_cell::sync::Lazy;
+use regex::Regex;
+
+use crate::pty::lines::LineSplitter;
+
+/// Failing test names kept per run.
+const MAX_FAILURES: usize = 200;
+
+/// Longest partial line buffered while waiting for a newline.
+const MAX_PENDING_LINE: usize = 16 * 1024;
+
+static CARGO_RESULT_RE: Lazy<Regex> = Lazy::new(|| {
+    Regex::new(
+        r"^test result: (?:ok|FAILED)\. (\d+) passed; (\d+) failed; (\d+) ignored; \d+ measured; \d+ filtered out(?:; finished in ([\d.]+)s)?",
+    )
+    .expect("valid CARGO_RESULT_RE")
+});
+static CARGO_FAILED_RE: Lazy<Regex> =
+    Lazy::new(|| Regex::new(r"^test (\S+) \.\.\. FAILED$").expect("valid CARGO_FAILED_RE"));
+static JEST_TESTS_RE: Lazy<Regex> =
+    Lazy::new(|| Regex::new(r"^Tests:\s+(.+?)\s*$").expect("valid JEST_TESTS_RE"));
+static JEST_TIME_RE: Lazy<Regex> =
+    Lazy::new(|| Regex::new(r"^Time:\s+([\d.]+)\s*(ms|s)\b").expect("valid JEST_TIME_RE"));
+static JEST_FAILED_RE: Lazy<Regex> =
+    Lazy::new(|| Regex::new(r"^\s*● (.+ › .+?)\s*$").expect("valid JEST_FAILED_RE"));
+static VITEST_TESTS_RE: Lazy<Regex> =
+    Lazy::new(|| Regex::new(r"^\s*Tests\s{2,}(.+?)\s*$").expect("valid VITEST_TESTS_RE"));
+static VITEST_DURATION_RE: Lazy<Regex> = Lazy::new(|| {
+    Regex::new(r"^\s*Duration\s+([\d.]+)\s*(ms|s)\b").expect("valid VITEST_DURATION_RE")
+});
+static VITEST_FAILED_RE: Lazy<Regex> = Lazy::new(|| {
+    Regex::new(r"^\s*(?:FAIL|×)\s+(.+ > .+?)(?:\s+\d+ms)?$").expect("valid VITEST_FAILED_RE")
+});
+static PYTEST_SUMMARY_RE: Lazy<Regex> = Lazy::new(|| {
+    Regex::new(r"^=+ (.+?) in ([\d.]+)s(?: \([^)]*\))? =+$").expect("valid PYTEST_SUMMARY_RE")
+});
+static PYTEST_FAILED_RE: Lazy<Regex> =
+    Lazy::new(|| Regex::new(r"^(?:FAILED|ERROR) (\S+::\S+)").expect("valid PYTEST_FAILED_RE"));
+static GO_TEST_RE: Lazy<Regex> =
+    Lazy::new(|| Regex::new(r"^--- (PASS|FAIL|SKIP): (\S+)").expect("valid GO_TEST_RE"));
+static GO_PACKAGE_RE: Lazy<Regex> = Lazy::new(|| {
+    Regex::new(r"^(ok|FAIL)\s+(\S+)\s+(?:([\d.]+)s|\(cached\)|\[build failed\])")
+        .expect("valid GO_PACKAGE_RE")
+});
+static COUNT_RE: Lazy<Regex> = Lazy::new(|| {
+    Regex::new(r"(\d+) (passed|failed|skipped|todo|errors?|xfailed|xpassed)\b")
+        .expect("valid COUNT_RE")
+});
+
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct TestRun {
+    /// cargo, jest, vitest, pytest or go.
+    pub runner: String,
+    pub passed: u32,
+    pub failed: u32,
+    pub skipped: u32,
+    pub failures: Vec<String>,
+    pub duration_s: Option<f64>,
+}
+
+/// Picks test runner summaries out of a session's output. Failing test names
+/// are collected as they scroll by and attached to the next summary. jest and
+/// vitest print their duration after the counts, so those runs are held until
+/// the duration line arrives.
+#[derive(Default)]
+pub struct TestRunExtractor {
+    lines: LineSplitter,
+    failures: Vec<String>,
+    go_counts: (u32, u32, u32),
+    pending_run: Option<TestRun>,
+}
+
+impl TestRunExtractor {
+    /// Starts over at a command boundary, returning a summary still waiting
+    /// for its duration.
+    pub fn begin_run(&mut self) -> Option<TestRun> {
+        self.failures.clear();
+        self.go_counts = (0, 0, 0);
+        self.pending_run.take()
+    }
+
+    /// Drops a partial line; used when a secret span starts.
+    pub fn discard_pending(&mut self) {
+        self.lines.clear();
+    }
+
+    /// Feeds decoded output and returns the runs that completed.
+    pub fn feed(&mut self, text: &str) -> Vec<TestRun> {
+        let mut runs = Vec::new();
+
+        for line in self.lines.feed(text) {
+            let line = line.rsplit('\r').next().unwrap_or(&line);
+            // strip_str drops tabs along with other control characters.
+            let line = strip_ansi_escapes::strip_str(line.replace('\t', " "));
+            self.parse_line(&line, &mut runs);
+        }
+        if self.lines.pending_len() > MAX_PENDING_LINE {
+            self.lines.clear();
+        }
+
+        runs
+    }
+
+    fn parse_line(&mut self, line: &str, runs: &mut Vec<TestRun>) {
+        if let Some(captures) = CARGO_RESULT_RE.captures(line) {
+            let run = TestRun {
+                runner: "cargo".to_string(),
+                passed: parse_count(&captures[1]),
+                failed: parse_count(&captures[2]),
+                skipped: parse_count(&captures[3]),
+                failures: std::mem::take(&mut self.failures),
+                duration_s: captures.get(4).and_then(|secs| secs.as_str().parse().ok()),
+            };
+            // Doc-test and empty binaries report a run with no tests at all.
+            if run.passed + run.failed + run.skipped > 0 {
+                runs.push(run);
+            }
+            return;
+        }
+
+        if let Some(captures) = PYTEST_SUMMARY_RE.captures(line) {
+            let mut run = counted_run("pytest", &captures[1]);
+            if run.passed + run.failed > 0 {
+                run.failures = std::mem::take(&mut self.failures);
+                run.duration_s = captures[2].parse().ok();
+                runs.push(run);
+            }
+            return;
+        }
+
+        if let Some(captures) = JEST_TESTS_RE.captures(line) {
+            runs.extend(self.pending_run.take());
+            let mut run = counted_run("jest", &captures[1]);
+            run.failures = std::mem::take(&mut self.failures);
+            self.pending_run = Some(run);
+            return;
+        }
+
+        if let Some(captures) = VITEST_TESTS_RE.captures(line) {
+            runs.extend(self.pending_run.take());
+            let mut run = counted_run("vitest", &captures[1]);
+            run.failures = std::mem::take(&mut self.failures);
+            self.pending_run = Some(run);
+            return;
+        }
+
+        let duration = JEST_TIME_RE
+            .captures(line)
+            .or_else(|| VITEST_DURATION_RE.captures(line));
+        if let Some(captures) = duration {
+            if let Some(mut run) = self.pending_run.take() {
+                let value = captures[1].parse::<f64>().unwrap_or_default();
+                run.duration_s = Some(if &captures[2] == "ms" {
+                    value / 1000.0
+                } else {
+                    value
+                });
+                runs.push(run);
+            }
+            return;
+        }
+
+        if let Some(captures) = GO_TEST_RE.captures(line) {
+            match &captures[1] {
+                "PASS" => self.go_counts.0 += 1,
+                "FAIL" => {
+                    self.go_counts.1 += 1;
+                    self.push_failure(&captures[2]);
+                }
+                _ => self.go_counts.2 += 1,
+            }
+            return;
+        }
+
+        if let Some(captures) = GO_PACKAGE_RE.captures(line) {
+            let (passed, mut failed, skipped) = std::mem::take(&mut self.go_counts);
+            // A build failure or a failing TestMain has no `--- FAIL` line.
+            if &captures[1] == "FAIL" && failed == 0 {
+                failed = 1;
+                self.push_failure(&captures[2]);
+            }
+            // Without -v, passing packages print no per-test lines.
+            if passed + failed + skipped > 0 {
+                runs.push(TestRun {
+                    runner: "go".to_string(),
+                    passed,
+                    failed,
+                    skipped,
+                    failures: std::mem::take(&mut self.failures),
+                    duration_s: captures.get(3).and_then(|secs| secs.as_str().parse().ok()),
+                });
+            }
+            return;
+        }
+
+        let failure = CARGO_FAILED_RE
+            .captures(line)
+            .or_else(|| PYTEST_FAILED_RE.captures(line))
+            .or_else(|| VITEST_FAILED_RE.captures(line))
+            .or_else(|| JEST_FAILED_RE.captures(line));
+        if let Some(captures) = failure {
+            self.push_failure(&captures[1]);
+        }
+    }
+
+    fn push_failure(&mut self, name: &str) {
+        let name = name.trim();
+        if self.failures.len() < MAX_FAILURES && !self.failures.iter().any(|seen| seen == name) {
+            self.failures.push(name.to_string());
+        }
+    }
+}
+
+/// Run built from a comma or pipe separated list like `1 failed, 4 passed`.
+fn counted_run(runner: &str, counts: &str) -> TestRun {
+    let mut run = TestRun {
+        runner: runner.to_string(),
+        ..TestRun::default()
+    };
+
+    for captures in COUNT_RE.captures_iter(counts) {
+        let count = parse_count(&captures[1]);
+        match &captures[2] {
+            "passed" | "xpassed" => run.passed += count,
+            "failed" | "error" | "errors" => run.failed += count,
+            _ => run.skipped += count,
+        }
+    }
+
+    run
+}
+
+fn parse_count(value: &str) -> u32 {
+    value.parse().unwrap_or_default()
+}
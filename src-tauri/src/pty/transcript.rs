@@ -0,0 +1,486 @@
+use crate::commands::tokens::UsageRecord;
+use crate::pty::scrollback::Scrollback;
+
+/// A stretch of output between OSC 133 shell-integration marks. Without
+/// those marks the whole transcript is a single block with no command.
+#[derive(Debug, Default)]
+struct Block {
+    command: Option<String>,
+    output: String,
+    exit_code: Option<i32>,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Region {
+    Prompt,
+    Command,
+    Output,
+}
+
+pub fn render(format: &str, source: &Scrollback, usage: &[UsageRecord]) -> Result<String, String> {
+    let blocks = split_blocks(&source.text);
+    match format {
+        "markdown" | "md" => Ok(render_markdown(source, &blocks, usage)),
+        "html" => Ok(render_html(source, &blocks, usage)),
+        other => Err(format!("unsupported transcript format: {other}")),
+    }
+}
+
+fn split_blocks(text: &str) -> Vec<Block> {
+    let mut blocks = vec![Block::default()];
+    let mut region = Region::Output;
+    let mut rest = text;
+
+    while let Some(start) = rest.find("\x1b]133;") {
+        push_region(&mut blocks, region, &rest[..start]);
+
+        let body = &rest[start + 6..];
+        let (mark, consumed) = match (body.find('\x07'), body.find("\x1b\\")) {
+            (Some(bel), Some(st)) if st < bel => (&body[..st], st + 2),
+            (Some(bel), _) => (&body[..bel], bel + 1),
+            (None, Some(st)) => (&body[..st], st + 2),
+            (None, None) => (body, body.len()),
+        };
+        rest = &body[consumed..];
+
+        let mut parts = mark.split(';');
+        match parts.next() {
+            Some("A") => {
+                if !blocks.last().is_some_and(is_empty_block) {
+                    blocks.push(Block::default());
+                }
+                region = Region::Prompt;
+            }
+            Some("B") => region = Region::Command,
+            Some("C") => region = Region::Output,
+            Some("D") => {
+                if let Some(block) = blocks.last_mut() {
+                    block.exit_code = parts.next().and_then(|code| code.parse().ok());
+                }
+                region = Region::Prompt;
+            }
+            _ => {}
+        }
+    }
+    push_region(&mut blocks, region, rest);
+
+    blocks.retain(|block| !is_empty_block(block));
+    blocks
+}
+
+fn push_region(blocks: &mut [Block], region: Region, text: &str) {
+    let Some(block) = blocks.last_mut() else {
+        return;
+    };
+    match region {
+        Region::Prompt => {}
+        Region::Command => block
+            .command
+            .get_or_insert_with(String::new)
+            .push_str(text),
+        Region::Output => block.output.push_str(text),
+    }
+}
+
+fn is_empty_block(block: &Block) -> bool {
+    let has_command = block
+        .command
+        .as_deref()
+        .is_some_and(|command| !command.trim().is_empty());
+    !has_command && block.output.trim().is_empty()
+}
+
+/// Applies carriage returns the way a terminal would for simple progress
+/// output: only what was written after the last `\r` on a line survives.
+fn collapse_carriage_returns(text: &str) -> String {
+    text.split('\n')
+        .map(|line| {
+            let line = line.trim_end_matches('\r');
+            line.rsplit('\r').next().unwrap_or(line)
+        })
+        .collect::<Vec<_>>()
+        .join("\n")
+}
+
+fn plain(text: &str) -> String {
+    strip_ansi_escapes::strip_str(collapse_carriage_returns(text))
+        .trim_matches('\n')
+        .to_string()
+}
+
+fn metadata_rows(source: &Scrollback) -> Vec<(&'static str, String)> {
+    let info = &source.info;
+    let mut rows = vec![
+        ("Session", info.session_id.clone()),
+        ("Shell", info.shell.clone()),
+        ("Working directory", info.cwd.clone()),
+        ("Started", info.started_at.clone()),
+    ];
+
+    if let Some(title) = &info.title {
+        rows.insert(1, ("Title", title.clone()));
+    }
+    if let Some(ended_at) = &source.ended_at {
+        rows.push(("Ended", ended_at.clone()));
+    }
+    if let Some(exit_code) = source.exit_code {
+        rows.push(("Exit code", exit_code.to_string()));
+    }
+    if let Some(worktree) = &info.worktree {
+        rows.push(("Worktree branch", worktree.branch.clone()));
+    }
+    if let Some(sandbox) = &info.sandbox {
+        rows.push(("Sandbox", sandbox.backend.clone()));
+    }
+    rows.retain(|(_, value)| !value.is_empty());
+
+    if source.first_line > 0 {
+        rows.push((
+            "Note",
+            format!("the first {} lines were trimmed from scrollback", source.first_line),
+        ));
+    }
+
+    rows
+}
+
+fn render_markdown(source: &Scrollback, blocks: &[Block], usage: &[UsageRecord]) -> String {
+    let title = source.info.title.as_deref().unwrap_or(&source.info.session_id);
+    let mut out = format!("# Session transcript: {}\n\n", escape_markdown(title));
+
+    out.push_str("| | |\n|---|---|\n");
+    for (label, value) in metadata_rows(source) {
+        out.push_str(&format!("| {label} | {} |\n", escape_markdown(&value)));
+    }
+
+    for (index, block) in blocks.iter().enumerate() {
+        out.push('\n');
+        if let Some(command) = block.command.as_deref().map(plain) {
+            out.push_str(&format!("## {}. `{}`", index + 1, command.replace('`', "'")));
+            if let Some(code) = block.exit_code {
+                out.push_str(&format!(" (exit {code})"));
+            }
+            out.push_str("\n\n");
+        }
+
+        let output = plain(&block.output);
+        let fence = "`".repeat(longest_backtick_run(&output).max(2) + 1);
+        out.push_str(&format!("{fence}text\n{output}\n{fence}\n"));
+    }
+
+    if !usage.is_empty() {
+        out.push_str("\n## Token usage\n\n");
+        out.push_str("| Captured | Agent | Tokens in | Tokens out | Total | Cost (USD) | Duration (s) |\n");
+        out.push_str("|---|---|---:|---:|---:|---:|---:|\n");
+        for record in usage {
+            out.push_str(&format!(
+                "| {} | {} | {} | {} | {} | {:.2} | {} |\n",
+                record.captured_at,
+                record.agent,
+                record.tokens_in,
+                record.tokens_out,
+                record.tokens_total,
+                record.cost_usd,
+                record
+                    .duration_s
+                    .map(|duration| duration.to_string())
+                    .unwrap_or_default(),
+            ));
+        }
+        let (tokens, cost) = usage_totals(usage);
+        out.push_str(&format!("| **Total** | | | | {tokens} | {cost:.2} | |\n"));
+    }
+
+    out
+}
+
+fn render_html(source: &Scrollback, blocks: &[Block], usage: &[UsageRecord]) -> String {
+    let title = source.info.title.as_deref().unwrap_or(&source.info.session_id);
+    let mut out = format!(
+        "<!DOCTYPE html>\n<html lang=\"en\">\n<head>\n<meta charset=\"utf-8\">\n<title>Session transcript: {}</title>\n<style>{HTML_STYLE}</style>\n</head>\n<body>\n<h1>Session transcript: {}</h1>\n<table class=\"meta\">\n",
+        escape_html(title),
+        escape_html(title)
+    );
+
+    for (label, value) in metadata_rows(source) {
+        out.push_str(&format!(
+            "<tr><th>{label}</th><td>{}</td></tr>\n",
+            escape_html(&value)
+        ));
+    }
+    out.push_str("</table>\n");
+
+    for block in blocks {
+        out.push_str("<section class=\"block\">\n");
+        if let Some(command) = block.command.as_deref().map(plain) {
+            out.push_str(&format!("<h2><code>{}</code>", escape_html(&command)));
+            if let Some(code) = block.exit_code {
+                let class = if code == 0 { "ok" } else { "fail" };
+                out.push_str(&format!(" <span class=\"exit {class}\">exit {code}</span>"));
+            }
+            out.push_str("</h2>\n");
+        }
+        let output = collapse_carriage_returns(&block.output);
+        out.push_str(&format!(
+            "<pre class=\"term\">{}</pre>\n</section>\n",
+            ansi_to_html(output.trim_matches('\n'))
+        ));
+    }
+
+    if !usage.is_empty() {
+        out.push_str("<h2>Token usage</h2>\n<table class=\"usage\">\n<tr><th>Captured</th><th>Agent</th><th>Tokens in</th><th>Tokens out</th><th>Total</th><th>Cost (USD)</th><th>Duration (s)</th></tr>\n");
+        for record in usage {
+            out.push_str(&format!(
+                "<tr><td>{}</td><td>{}</td><td>{}</td><td>{}</td><td>{}</td><td>{:.2}</td><td>{}</td></tr>\n",
+                escape_html(&record.captured_at),
+                escape_html(&record.agent),
+                record.tokens_in,
+                record.tokens_out,
+                record.tokens_total,
+                record.cost_usd,
+                record
+                    .duration_s
+                    .map(|duration| duration.to_string())
+                    .unwrap_or_default(),
+            ));
+        }
+        let (tokens, cost) = usage_totals(usage);
+        out.push_str(&format!(
+            "<tr class=\"total\"><th>Total</th><td></td><td></td><td></td><td>{tokens}</td><td>{cost:.2}</td><td></td></tr>\n</table>\n"
+        ));
+    }
+
+    out.push_str("</body>\n</html>\n");
+    out
+}
+
+fn usage_totals(usage: &[UsageRecord]) -> (i64, f64) {
+    usage.iter().fold((0, 0.0), |(tokens, cost), record| {
+        (tokens + record.tokens_total, cost + record.cost_usd)
+    })
+}
+
+fn longest_backtick_run(text: &str) -> usize {
+    text.split(|ch| ch != '`').map(str::len).max().unwrap_or(0)
+}
+
+fn escape_markdown(text: &str) -> String {
+    text.replace('|', "\\|").replace('\n', " ")
+}
+
+fn escape_html(text: &str) -> String {
+    let mut escaped = String::with_capacity(text.len());
+    for ch in text.chars() {
+        match ch {
+            '&' => escaped.push_str("&amp;"),
+            '<' => escaped.push_str("&lt;"),
+            '>' => escaped.push_str("&gt;"),
+            '"' => escaped.push_str("&quot;"),
+            _ => escaped.push(ch),
+        }
+    }
+    escaped
+}
+
+const HTML_STYLE: &str = "body{font-family:-apple-system,BlinkMacSystemFont,'Segoe UI',sans-serif;margin:2rem auto;max-width:1100px;color:#1f2328;background:#fff}\
+table{border-collapse:collapse;margin:1rem 0}th,td{border:1px solid #d0d7de;padding:4px 10px;text-align:left}\
+h2{font-size:1rem;margin:1.5rem 0 .5rem}.exit{font-size:.8rem;padding:1px 6px;border-radius:4px}.ok{background:#dafbe1}.fail{background:#ffebe9}\
+pre.term{background:#0d1117;color:#c9d1d9;padding:12px;border-radius:6px;overflow-x:auto;font:13px/1.4 ui-monospace,SFMono-Regular,Menlo,monospace;white-space:pre-wrap}";
+
+const PALETTE: [&str; 16] = [
+    "#000000", "#cd3131", "#0dbc79", "#e5e510", "#2472c8", "#bc3fbc", "#11a8cd", "#e5e5e5",
+    "#666666", "#f14c4c", "#23d18b", "#f5f543", "#3b8eea", "#d670d6", "#29b8db", "#ffffff",
+];
+
+#[derive(Debug, Default, Clone, PartialEq)]
+struct Style {
+    fg: Option<String>,
+    bg: Option<String>,
+    bold: bool,
+    dim: bool,
+    italic: bool,
+    underline: bool,
+    inverse: bool,
+    strike: bool,
+}
+
+impl Style {
+    fn css(&self) -> String {
+        let (fg, bg) = if self.inverse {
+            (
+                self.bg.clone().or_else(|| Some("#0d1117".to_string())),
+                self.fg.clone().or_else(|| Some("#c9d1d9".to_string())),
+            )
+        } else {
+            (self.fg.clone(), self.bg.clone())
+        };
+
+        let mut css = String::new();
+        if let Some(fg) = fg {
+            css.push_str(&format!("color:{fg};"));
+        }
+        if let Some(bg) = bg {
+            css.push_str(&format!("background:{bg};"));
+        }
+        if self.bold {
+            css.push_str("font-weight:bold;");
+        }
+        if self.dim {
+            css.push_str("opacity:.7;");
+        }
+        if self.italic {
+            css.push_str("font-style:italic;");
+        }
+        match (self.underline, self.strike) {
+            (true, true) => css.push_str("text-decoration:underline line-through;"),
+            (true, false) => css.push_str("text-decoration:underline;"),
+            (false, true) => css.push_str("text-decoration:line-through;"),
+            (false, false) => {}
+        }
+        css
+    }
+
+    fn apply_sgr(&mut self, params: &str) {
+        let codes = params
+            .split([';', ':'])
+            .map(|code| code.parse::<u32>().unwrap_or(0))
+            .collect::<Vec<_>>();
+        let codes = if codes.is_empty() { vec![0] } else { codes };
+
+        let mut index = 0;
+        while index < codes.len() {
+            match codes[index] {
+                0 => *self = Style::default(),
+                1 => self.bold = true,
+                2 => self.dim = true,
+                3 => self.italic = true,
+                4 => self.underline = true,
+                7 => self.inverse = true,
+                9 => self.strike = true,
+                22 => {
+                    self.bold = false;
+                    self.dim = false;
+                }
+                23 => self.italic = false,
+                24 => self.underline = false,
+                27 => self.inverse = false,
+                29 => self.strike = false,
+                code @ 30..=37 => self.fg = Some(PALETTE[(code - 30) as usize].to_string()),
+                39 => self.fg = None,
+                code @ 40..=47 => self.bg = Some(PALETTE[(code - 40) as usize].to_string()),
+                49 => self.bg = None,
+                code @ 90..=97 => self.fg = Some(PALETTE[(code - 90 + 8) as usize].to_string()),
+                code @ 100..=107 => self.bg = Some(PALETTE[(code - 100 + 8) as usize].to_string()),
+                code @ (38 | 48) => {
+                    let (color, used) = extended_color(&codes[index + 1..]);
+                    index += used;
+                    if code == 38 {
+                        self.fg = color;
+                    } else {
+                        self.bg = color;
+                    }
+                }
+                _ => {}
+            }
+            index += 1;
+        }
+    }
+}
+
+/// Parses the tail of `38;5;n` / `38;2;r;g;b`, returning the color and how
+/// many parameters it consumed.
+fn extended_color(codes: &[u32]) -> (Option<String>, usize) {
+    match codes {
+        [5, n, ..] => (Some(xterm_256(*n)), 2),
+        [2, r, g, b, ..] => (Some(format!("#{:02x}{:02x}{:02x}", r & 255, g & 255, b & 255)), 4),
+        _ => (None, codes.len()),
+    }
+}
+
+fn xterm_256(n: u32) -> String {
+    match n {
+        0..=15 => PALETTE[n as usize].to_string(),
+        16..=231 => {
+            let n = n - 16;
+            let level = |value: u32| if value == 0 { 0 } else { 55 + value * 40 };
+            format!(
+                "#{:02x}{:02x}{:02x}",
+                level(n / 36),
+                level((n / 6) % 6),
+                level(n % 6)
+            )
+        }
+        _ => {
+            let gray = 8 + (n.min(255) - 232) * 10;
+            format!("#{gray:02x}{gray:02x}{gray:02x}")
+        }
+    }
+}
+
+/// Converts SGR-styled terminal output into escaped HTML spans. All other
+/// escape sequences are dropped.
+fn ansi_to_html(text: &str) -> String {
+    let mut out = String::with_capacity(text.len());
+    let mut style = Style::default();
+    let mut span_open = false;
+    let mut chars = text.chars().peekable();
+
+    while let Some(ch) = chars.next() {
+        match ch {
+            '\x1b' => match chars.next() {
+                Some('[') => {
+                    let mut params = String::new();
+                    let mut terminator = None;
+                    for next in chars.by_ref() {
+                        if ('\x40'..='\x7e').contains(&next) {
+                            terminator = Some(next);
+                            break;
+                        }
+                        params.push(next);
+                    }
+                    if terminator == Some('m') {
+                        let previous = style.clone();
+                        style.apply_sgr(&params);
+                        if style != previous {
+                            if span_open {
+                                out.push_str("</span>");
+                                span_open = false;
+                            }
+                            let css = style.css();
+                            if !css.is_empty() {
+                                out.push_str(&format!("<span style=\"{css}\">"));
+                                span_open = true;
+                            }
+                        }
+                    }
+                }
+                Some(']') | Some('P') | Some('_') | Some('^') => {
+                    while let Some(next) = chars.next() {
+                        if next == '\x07' {
+                            break;
+                        }
+                        if next == '\x1b' && chars.peek() == Some(&'\\') {
+                            chars.next();
+                            break;
+                        }
+                    }
+                }
+                Some('(') | Some(')') => {
+                    chars.next();
+                }
+                _ => {}
+            },
+            '\n' | '\t' => out.push(ch),
+            ch if ch.is_control() => {}
+            '&' => out.push_str("&amp;"),
+            '<' => out.push_str("&lt;"),
+            '>' => out.push_str("&gt;"),
+            _ => out.push(ch),
+        }
+    }
+
+    if span_open {
+        out.push_str("</span>");
+    }
+    out
+}
@@ -0,0 +1,552 @@
+use std::collections::HashMap;
+use std::path::{Path, PathBuf};
+use std::sync::mpsc::{self, Receiver, Sender};
+use std::time::Duration;
+
+use rusqlite::{params, Connection, ErrorCode, OptionalExtension, Transaction};
+use serde::Serialize;
+
+use crate::pty::agents::ParsedUsage;
+use crate::pty::secrets;
+use crate::usage::pricing::{self, ModelPrice};
+
+/// Most summaries committed in one transaction.
+const MAX_BATCH: usize = 256;
+
+/// Wait before retrying a batch that failed with a transient error, such as
+/// another process holding the database locked; doubled after every try.
+const RETRY_DELAY: Duration = Duration::from_millis(500);
+
+/// Tries of a batch before it is given up on.
+const MAX_ATTEMPTS: u32 = 5;
+
+/// A summary read from a session's output, on its way to `token_usage`.
+pub struct Summary {
+    pub session_id: String,
+    pub agent: String,
+    pub cumulative: bool,
+    pub usage: ParsedUsage,
+}
+
+pub enum WriterMessage {
+    Usage(Summary),
+    /// An agent banner was seen: the session's next cumulative readout
+    /// starts from zero rather than from the previous run's totals.
+    Reset { session_id: String },
+    /// Answered once everything sent before it has been handled, with an
+    /// error if any of it couldn't be stored.
+    Flush(Sender<Result<(), String>>),
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct TokenCapturedPayload {
+    pub session_id: String,
+    pub inserts: usize,
+    pub agent: String,
+}
+
+/// Starts the thread that stores scraped usage. PTY reader threads only
+/// queue summaries, so a slow or locked database never holds up terminal
+/// output. Whatever is queued when the thread wakes is written in one
+/// transaction, then `on_captured` is called once per session and agent.
+pub fn spawn(
+    db_path: PathBuf,
+    on_captured: impl Fn(TokenCapturedPayload) + Send + 'static,
+) -> Sender<WriterMessage> {
+    let (sender, receiver) = mpsc::channel();
+    let spawned = std::thread::Builder::new()
+        .name("usage-writer".to_string())
+        .spawn(move || UsageWriter::new(db_path).run(receiver, on_captured));
+    if let Err(err) = spawned {
+        log::warn!("failed to start usage writer: {err}");
+    }
+    sender
+}
+
+struct UsageWriter {
+    db_path: PathBuf,
+    /// Opened on first use and kept for the life of the app.
+    conn: Option<Connection>,
+    /// Last readout of a cumulative agent per session, with the agent it
+    /// came from; `None` once a new agent run started. Sessions missing
+    /// here are read back from `token_usage`.
+    totals: HashMap<String, Option<(String, UsageTotals)>>,
+}
+
+impl UsageWriter {
+    fn new(db_path: PathBuf) -> Self {
+        Self {
+            db_path,
+            conn: None,
+            totals: HashMap::new(),
+        }
+    }
+
+    fn run(
+        mut self,
+        receiver: Receiver<WriterMessage>,
+        on_captured: impl Fn(TokenCapturedPayload),
+    ) {
+        while let Ok(first) = receiver.recv() {
+            let mut updates = Vec::new();
+            let mut flushes = Vec::new();
+            let mut next = Some(first);
+            while let Some(message) = next {
+                match message {
+                    WriterMessage::Flush(done) => flushes.push(done),
+                    update => updates.push(update),
+                }
+                next = if updates.len() < MAX_BATCH {
+                    receiver.try_recv().ok()
+                } else {
+                    None
+                };
+            }
+
+            let result = if updates.is_empty() {
+                Ok(())
+            } else {
+                self.store(&updates, &on_captured)
+            };
+            for done in flushes {
+                let _ = done.send(result.clone());
+            }
+        }
+    }
+
+    /// Writes a batch, retrying transient failures a few times. When a
+    /// write fails for good, e.g. on a bad row, the updates are written one
+    /// at a time so only the rows that fail are lost.
+    fn store(
+        &mut self,
+        updates: &[WriterMessage],
+        on_captured: &impl Fn(TokenCapturedPayload),
+    ) -> Result<(), String> {
+        let mut delay = RETRY_DELAY;
+        let mut attempt = 1;
+        let err = loop {
+            match self.try_write(updates) {
+                Ok(captured) => {
+                    captured.into_iter().for_each(on_captured);
+                    return Ok(());
+                }
+                Err(err) if err.transient && attempt < MAX_ATTEMPTS => {
+                    log::warn!("failed to store token usage, retrying in {delay:?}: {}", err.message);
+                    std::thread::sleep(delay);
+                    delay *= 2;
+                    attempt += 1;
+                }
+                Err(err) => break err,
+            }
+        };
+
+        if err.transient {
+            log::warn!(
+                "dropping {} token usage updates after {MAX_ATTEMPTS} tries: {}",
+                updates.len(),
+                err.message
+            );
+            return Err(err.message);
+        }
+
+        let mut last_error = None;
+        for update in updates {
+            match self.try_write(std::slice::from_ref(update)) {
+                Ok(captured) => captured.into_iter().for_each(on_captured),
+                Err(err) => {
+                    if let WriterMessage::Usage(summary) = update {
+                        log::warn!(
+                            "dropping token usage of {} for {}: {}",
+                            summary.agent,
+                            summary.session_id,
+                            err.message
+                        );
+                    }
+                    last_error = Some(err.message);
+                }
+            }
+        }
+        last_error.map_or(Ok(()), Err)
+    }
+
+    /// `write`, leaving the connection and session totals as they were
+    /// when it fails.
+    fn try_write(
+        &mut self,
+        updates: &[WriterMessage],
+    ) -> Result<Vec<TokenCapturedPayload>, WriteError> {
+        let committed = self.totals.clone();
+        self.write(updates).inspect_err(|_| {
+            self.conn = None;
+            self.totals = committed;
+        })
+    }
+
+    /// Applies `updates` in order; resets take effect between the summaries
+    /// around them.
+    fn write(
+        &mut self,
+        updates: &[WriterMessage],
+    ) -> Result<Vec<TokenCapturedPayload>, WriteError> {
+        let mut conn = match self.conn.take() {
+            Some(conn) => conn,
+            None => open_conn(&self.db_path)?,
+        };
+
+        let tx = conn
+            .transaction()
+            .map_err(WriteError::sqlite("failed to start token usage batch"))?;
+        let prices = pricing::list_prices(&tx)?;
+        let mut captured = Vec::<TokenCapturedPayload>::new();
+        for update in updates {
+            let summary = match update {
+                WriterMessage::Usage(summary) => summary,
+                WriterMessage::Reset { session_id } => {
+                    self.totals.insert(session_id.clone(), None);
+                    continue;
+                }
+                // Answered by `run` once the batch is done.
+                WriterMessage::Flush(_) => continue,
+            };
+            self.insert(&tx, &prices, summary)?;
+            match captured.iter_mut().find(|payload| {
+                payload.session_id == summary.session_id && payload.agent == summary.agent
+            }) {
+                Some(payload) => payload.inserts += 1,
+                None => captured.push(TokenCapturedPayload {
+                    session_id: summary.session_id.clone(),
+                    inserts: 1,
+                    agent: summary.agent.clone(),
+                }),
+            }
+        }
+        tx.commit()
+            .map_err(WriteError::sqlite("failed to commit token usage batch"))?;
+
+        self.conn = Some(conn);
+        Ok(captured)
+    }
+
+    /// Stores a summary. Cumulative readouts keep the session totals in the
+    /// `cumulative_*` columns and count only the increase over the previous
+    /// readout, so running `/cost` twice doesn't count the session twice.
+    fn insert(
+        &mut self,
+        tx: &Transaction<'_>,
+        prices: &[ModelPrice],
+        summary: &Summary,
+    ) -> Result<(), WriteError> {
+        let Summary {
+            session_id,
+            agent,
+            usage,
+            ..
+        } = summary;
+        let raw_output = usage.raw_lines.join("\n");
+        let raw_output = secrets::redact(&raw_output);
+
+        let reading = UsageTotals::from_usage(usage);
+        let (counts, cumulative) = if summary.cumulative {
+            let previous = self.previous_totals(tx, session_id, agent)?;
+            let delta = previous
+                .and_then(|previous| reading.since(&previous))
+                .unwrap_or(reading);
+            (delta, Some(reading))
+        } else {
+            (reading, None)
+        };
+
+        // Agents that print a model but no dollar amount are priced from
+        // the pricing table, like imported transcripts.
+        let mut cost_usd = counts.cost_usd;
+        let mut estimated = false;
+        if let Some(model) = usage.model.as_deref().filter(|_| usage.cost_usd == 0.0) {
+            let today = chrono::Utc::now().format("%Y-%m-%d").to_string();
+            if let Some(price) = pricing::price_for(prices, model, &today) {
+                cost_usd = pricing::estimate_cost(
+                    price,
+                    counts.tokens_in,
+                    counts.tokens_out,
+                    counts.cache_read_tokens,
+                    counts.cache_creation_tokens,
+                );
+                estimated = true;
+            }
+        }
+
+        let cache_read =
+            |totals: &UsageTotals| usage.cache_read_tokens.map(|_| totals.cache_read_tokens);
+        let cache_creation = |totals: &UsageTotals| {
+            usage
+                .cache_creation_tokens
+                .map(|_| totals.cache_creation_tokens)
+        };
+        tx.execute(
+            "INSERT INTO token_usage (session_id, agent, source, cost_usd, tokens_in, tokens_out, tokens_total, duration_s, raw_output, model, cache_read_tokens, cache_creation_tokens, estimated, cumulative_cost_usd, cumulative_tokens_in, cumulative_tokens_out, cumulative_tokens_total, cumulative_cache_read_tokens, cumulative_cache_creation_tokens)
+             VALUES (?1, ?2, 'scraped', ?3, ?4, ?5, ?6, ?7, ?8, ?9, ?10, ?11, ?12, ?13, ?14, ?15, ?16, ?17, ?18)",
+            params![
+                session_id,
+                agent,
+                cost_usd,
+                counts.tokens_in,
+                counts.tokens_out,
+                counts.tokens_total,
+                usage.duration_s,
+                raw_output,
+                usage.model,
+                cache_read(&counts),
+                cache_creation(&counts),
+                estimated,
+                cumulative.map(|totals| totals.cost_usd),
+                cumulative.map(|totals| totals.tokens_in),
+                cumulative.map(|totals| totals.tokens_out),
+                cumulative.map(|totals| totals.tokens_total),
+                cumulative.as_ref().and_then(cache_read),
+                cumulative.as_ref().and_then(cache_creation),
+            ],
+        )
+        .map_err(WriteError::sqlite("failed to insert token usage"))?;
+
+        if let Some(reading) = cumulative {
+            self.totals
+                .insert(session_id.clone(), Some((agent.clone(), reading)));
+        }
+
+        Ok(())
+    }
+
+    /// The session's previous readout from `agent` in the current run,
+    /// falling back to the last one stored for sessions restored from an
+    /// earlier run of the app.
+    fn previous_totals(
+        &self,
+        conn: &Connection,
+        session_id: &str,
+        agent: &str,
+    ) -> Result<Option<UsageTotals>, WriteError> {
+        if let Some(last) = self.totals.get(session_id) {
+            return Ok(last
+                .as_ref()
+                .filter(|(last_agent, _)| last_agent == agent)
+                .map(|(_, totals)| *totals));
+        }
+
+        conn.query_row(
+            "SELECT cumulative_cost_usd, cumulative_tokens_in, cumulative_tokens_out, cumulative_tokens_total, COALESCE(cumulative_cache_read_tokens, 0), COALESCE(cumulative_cache_creation_tokens, 0)
+             FROM token_usage WHERE session_id = ?1 AND agent = ?2 AND cumulative_cost_usd IS NOT NULL ORDER BY id DESC LIMIT 1",
+            params![session_id, agent],
+            |row| {
+                Ok(UsageTotals {
+                    cost_usd: row.get(0)?,
+                    tokens_in: row.get(1)?,
+                    tokens_out: row.get(2)?,
+                    tokens_total: row.get(3)?,
+                    cache_read_tokens: row.get(4)?,
+                    cache_creation_tokens: row.get(5)?,
+                })
+            },
+        )
+        .optional()
+        .map_err(WriteError::sqlite("failed to read previous usage totals"))
+    }
+}
+
+/// A failed write, and whether trying it again later may succeed.
+#[derive(Debug)]
+struct WriteError {
+    message: String,
+    transient: bool,
+}
+
+impl WriteError {
+    fn sqlite(context: &str) -> impl Fn(rusqlite::Error) -> Self + '_ {
+        move |err| Self {
+            transient: matches!(
+                err.sqlite_error_code(),
+                Some(ErrorCode::DatabaseBusy | ErrorCode::DatabaseLocked | ErrorCode::SystemIoFailure)
+            ),
+            message: format!("{context}: {err}"),
+        }
+    }
+}
+
+impl From<String> for WriteError {
+    fn from(message: String) -> Self {
+        Self {
+            message,
+            transient: false,
+        }
+    }
+}
+
+/// The writer's connection, with a busy timeout for the importer's writes.
+/// `db::prepare` has already put the database in WAL mode.
+fn open_conn(db_path: &Path) -> Result<Connection, WriteError> {
+    let conn = Connection::open(db_path)
+        .map_err(WriteError::sqlite("failed to open db for usage writer"))?;
+    conn.pragma_update(None, "synchronous", "NORMAL")
+        .map_err(WriteError::sqlite("failed to set synchronous mode"))?;
+    conn.busy_timeout(Duration::from_secs(5))
+        .map_err(WriteError::sqlite("failed to set busy timeout"))?;
+    Ok(conn)
+}
+
+/// The counts of one summary. For cumulative agents these are the totals
+/// of the agent's session so far.
+#[derive(Debug, Clone, Copy, Default, PartialEq)]
+struct UsageTotals {
+    cost_usd: f64,
+    tokens_in: i64,
+    tokens_out: i64,
+    tokens_total: i64,
+    cache_read_tokens: i64,
+    cache_creation_tokens: i64,
+}
+
+impl UsageTotals {
+    fn from_usage(usage: &ParsedUsage) -> Self {
+        Self {
+            cost_usd: usage.cost_usd,
+            tokens_in: usage.tokens_in,
+            tokens_out: usage.tokens_out,
+            tokens_total: usage.tokens_total,
+            cache_read_tokens: usage.cache_read_tokens.unwrap_or_default(),
+            cache_creation_tokens: usage.cache_creation_tokens.unwrap_or_default(),
+        }
+    }
+
+    /// Increase over `previous`, or `None` when a count went down because
+    /// the readout comes from a new agent session that started from zero.
+    fn since(&self, previous: &Self) -> Option<Self> {
+        let delta = Self {
+            cost_usd: self.cost_usd - previous.cost_usd,
+            tokens_in: self.tokens_in - previous.tokens_in,
+            tokens_out: self.tokens_out - previous.tokens_out,
+            tokens_total: self.tokens_total - previous.tokens_total,
+            cache_read_tokens: self.cache_read_tokens - previous.cache_read_tokens,
+            cache_creation_tokens: self.cache_creation_tokens - previous.cache_creation_tokens,
+        };
+        // Costs are printed rounded, so allow for float noise.
+        let went_down = delta.cost_usd < -0.000_001
+            || [
+                delta.tokens_in,
+                delta.tokens_out,
+                delta.tokens_total,
+                delta.cache_read_tokens,
+                delta.cache_creation_tokens,
+            ]
+            .iter()
+            .any(|count| *count < 0);
+
+        (!went_down).then_some(Self {
+            cost_usd: delta.cost_usd.max(0.0),
+            ..delta
+        })
+    }
+}
+
+/// Rewrites `scraped` rows of cumulative agents stored before readouts were
+/// tracked, so each counts only its increase over the session's previous
+/// readout. Rows that already carry their session totals are kept as they
+/// are; costs that were estimated are estimated again from the new counts.
+/// Returns the number of rows rewritten.
+pub fn repair_cumulative(conn: &Connection, agents: &[String]) -> Result<usize, String> {
+    let mut statement = conn
+        .prepare(
+            "SELECT id, session_id, cost_usd, estimated, tokens_in, tokens_out, tokens_total, COALESCE(cache_read_tokens, 0), COALESCE(cache_creation_tokens, 0),
+                    cumulative_cost_usd, cumulative_tokens_in, cumulative_tokens_out, cumulative_tokens_total, COALESCE(cumulative_cache_read_tokens, 0), COALESCE(cumulative_cache_creation_tokens, 0)
+             FROM token_usage WHERE source = 'scraped' AND agent = ?1 ORDER BY session_id, captured_at, id",
+        )
+        .map_err(|err| format!("failed to prepare cumulative usage query: {err}"))?;
+
+    let mut repaired = 0;
+    let mut reestimate = Vec::new();
+    for agent in agents {
+        let rows = statement
+            .query_map(params![agent], |row| {
+                let stored = row
+                    .get::<_, Option<f64>>(9)?
+                    .map(|cost_usd| -> rusqlite::Result<UsageTotals> {
+                        Ok(UsageTotals {
+                            cost_usd,
+                            tokens_in: row.get(10)?,
+                            tokens_out: row.get(11)?,
+                            tokens_total: row.get(12)?,
+                            cache_read_tokens: row.get(13)?,
+                            cache_creation_tokens: row.get(14)?,
+                        })
+                    })
+                    .transpose()?;
+                let estimated = row.get::<_, bool>(3)?;
+                let reading = UsageTotals {
+                    // An estimated cost wasn't part of the readout.
+                    cost_usd: if estimated { 0.0 } else { row.get(2)? },
+                    tokens_in: row.get(4)?,
+                    tokens_out: row.get(5)?,
+                    tokens_total: row.get(6)?,
+                    cache_read_tokens: row.get(7)?,
+                    cache_creation_tokens: row.get(8)?,
+                };
+                Ok((
+                    row.get::<_, i64>(0)?,
+                    row.get::<_, String>(1)?,
+                    stored,
+                    reading,
+                    estimated,
+                ))
+            })
+            .map_err(|err| format!("failed to execute cumulative usage query: {err}"))?
+            .collect::<Result<Vec<_>, _>>()
+            .map_err(|err| format!("failed to map cumulative usage rows: {err}"))?;
+
+        let mut previous: Option<(String, UsageTotals)> = None;
+        for (id, session_id, stored, reading, estimated) in rows {
+            let previous_totals = previous
+                .as_ref()
+                .filter(|(session, _)| *session == session_id)
+                .map(|(_, totals)| *totals);
+
+            if let Some(stored) = stored {
+                previous = Some((session_id, stored));
+                continue;
+            }
+
+            let delta = previous_totals
+                .and_then(|previous| reading.since(&previous))
+                .unwrap_or(reading);
+            conn.execute(
+                "UPDATE token_usage SET cost_usd = ?1, estimated = 0, tokens_in = ?2, tokens_out = ?3, tokens_total = ?4,
+                     cache_read_tokens = CASE WHEN cache_read_tokens IS NULL THEN NULL ELSE ?5 END,
+                     cache_creation_tokens = CASE WHEN cache_creation_tokens IS NULL THEN NULL ELSE ?6 END,
+                     cumulative_cost_usd = ?7, cumulative_tokens_in = ?8, cumulative_tokens_out = ?9, cumulative_tokens_total = ?10,
+                     cumulative_cache_read_tokens = CASE WHEN cache_read_tokens IS NULL THEN NULL ELSE ?11 END,
+                     cumulative_cache_creation_tokens = CASE WHEN cache_creation_tokens IS NULL THEN NULL ELSE ?12 END
+                 WHERE id = ?13",
+                params![
+                    delta.cost_usd,
+                    delta.tokens_in,
+                    delta.tokens_out,
+                    delta.tokens_total,
+                    delta.cache_read_tokens,
+                    delta.cache_creation_tokens,
+                    reading.cost_usd,
+                    reading.tokens_in,
+                    reading.tokens_out,
+                    reading.tokens_total,
+                    reading.cache_read_tokens,
+                    reading.cache_creation_tokens,
+                    id,
+                ],
+            )
+            .map_err(|err| format!("failed to repair cumulative usage row: {err}"))?;
+            repaired += 1;
+            if estimated {
+                reestimate.push(id);
+            }
+            previous = Some((session_id, reading));
+        }
+    }
+
+    pricing::estimate_rows(conn, &reestimate)?;
+    Ok(repaired)
+}
@@ -1,18 +1,28 @@
-use std::collections::HashMap;
+use std::collections::{HashMap, HashSet};
 use std::path::PathBuf;
 use std::sync::{Arc, Mutex};
 
 use notify::RecommendedWatcher;
 use tauri::{AppHandle, Manager};
 
+use crate::commands::settings;
 use crate::db;
+use crate::git::attribution::ChangeTracker;
 use crate::pty::PtyManager;
+use crate::usage::importer;
 
 pub struct AppState {
     pub pty_manager: PtyManager,
     pub db_path: Arc<PathBuf>,
     pub shell_config_path: Arc<PathBuf>,
+    pub worktrees_dir: Arc<PathBuf>,
+    pub terminfo_dir: Arc<PathBuf>,
+    pub logs_dir: Arc<PathBuf>,
     pub git_watchers: Mutex<HashMap<String, RecommendedWatcher>>,
+    /// Repos the frontend asked to watch; other watchers only live as long
+    /// as sessions in their repo.
+    pub git_watch_requests: Mutex<HashSet<String>>,
+    pub change_tracker: ChangeTracker,
 }
 
 impl AppState {
@@ -29,11 +39,36 @@ impl AppState {
 
         let shell_config_path = config_dir.join("shell_config.json");
 
+        let data_dir = app
+            .path()
+            .app_data_dir()
+            .map_err(|err| format!("failed to resolve app data dir: {err}"))?;
+        let worktrees_dir = data_dir.join("worktrees");
+        let terminfo_dir = data_dir.join("terminfo");
+        let logs_dir = data_dir.join("logs");
+
+        let pty_manager = PtyManager::new(app.clone(), db_path.clone());
+        match settings::load_shell_config_from_path(&shell_config_path) {
+            Ok(config) => {
+                for error in pty_manager.scraper().set_rules(&config.scraper.rules) {
+                    log::warn!("ignoring scraping rule '{}': {}", error.rule, error.error);
+                }
+            }
+            Err(err) => log::warn!("failed to load scraping rules: {err}"),
+        }
+
+        importer::start(app.clone(), db_path.clone());
+
         Ok(Self {
-            pty_manager: PtyManager::new(db_path.clone()),
+            pty_manager,
             db_path: Arc::new(db_path),
             shell_config_path: Arc::new(shell_config_path),
+            worktrees_dir: Arc::new(worktrees_dir),
+            terminfo_dir: Arc::new(terminfo_dir),
+            logs_dir: Arc::new(logs_dir),
             git_watchers: Mutex::new(HashMap::new()),
+            git_watch_requests: Mutex::new(HashSet::new()),
+            change_tracker: ChangeTracker::new(),
         })
     }
 }
@@ -0,0 +1,98 @@
+use std::path::{Path, PathBuf};
+
+use serde::Deserialize;
+
+use super::{sqlite_timestamp, ImportedUsage, LogContext, UsageLog};
+
+#[derive(Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct Entry {
+    #[serde(rename = "type")]
+    kind: Option<String>,
+    session_id: Option<String>,
+    cwd: Option<String>,
+    timestamp: Option<String>,
+    message: Option<Message>,
+}
+
+#[derive(Deserialize)]
+struct Message {
+    id: Option<String>,
+    model: Option<String>,
+    usage: Option<Usage>,
+}
+
+#[derive(Deserialize)]
+struct Usage {
+    #[serde(default)]
+    input_tokens: i64,
+    #[serde(default)]
+    output_tokens: i64,
+    #[serde(default)]
+    cache_read_input_tokens: Option<i64>,
+    #[serde(default)]
+    cache_creation_input_tokens: Option<i64>,
+}
+
+/// Claude Code's JSONL transcripts, one directory per project under
+/// `~/.claude/projects/` (or `$CLAUDE_CONFIG_DIR/projects/`).
+pub struct ClaudeTranscripts;
+
+impl UsageLog for ClaudeTranscripts {
+    fn agent(&self) -> &'static str {
+        "claude-code"
+    }
+
+    fn root(&self) -> Option<PathBuf> {
+        let config_dir = match std::env::var_os("CLAUDE_CONFIG_DIR") {
+            Some(dir) => PathBuf::from(dir),
+            None => PathBuf::from(std::env::var_os("HOME")?).join(".claude"),
+        };
+        Some(config_dir.join("projects"))
+    }
+
+    fn is_log(&self, path: &Path) -> bool {
+        path.extension().is_some_and(|ext| ext == "jsonl")
+    }
+
+    /// Project directories are named after the cwd with separators replaced
+    /// by dashes, which is ambiguous, so the guess must exist.
+    fn log_cwd(&self, path: &Path) -> Option<String> {
+        path.parent()
+            .and_then(|dir| dir.file_name())
+            .map(|name| name.to_string_lossy().replace('-', "/"))
+            .filter(|dir| Path::new(dir).is_dir())
+    }
+
+    /// Only assistant messages carry usage; other entries yield nothing.
+    fn parse(&self, text: &str, _context: &mut LogContext) -> Result<Vec<ImportedUsage>, String> {
+        let entry = serde_json::from_str::<Entry>(text)
+            .map_err(|err| format!("invalid transcript entry: {err}"))?;
+        if entry.kind.as_deref() != Some("assistant") {
+            return Ok(Vec::new());
+        }
+
+        let Some(message) = entry.message else {
+            return Ok(Vec::new());
+        };
+        let (Some(message_id), Some(usage)) = (message.id, message.usage) else {
+            return Ok(Vec::new());
+        };
+        // Locally generated messages (API errors, interruptions) cost nothing.
+        if message.model.as_deref() == Some("<synthetic>") {
+            return Ok(Vec::new());
+        }
+
+        Ok(vec![ImportedUsage {
+            message_id,
+            session_id: entry.session_id.unwrap_or_default(),
+            model: message.model,
+            cwd: entry.cwd,
+            tokens_in: usage.input_tokens,
+            tokens_out: usage.output_tokens,
+            cache_read_tokens: usage.cache_read_input_tokens.unwrap_or_default(),
+            cache_creation_tokens: usage.cache_creation_input_tokens.unwrap_or_default(),
+            captured_at: entry.timestamp.as_deref().and_then(sqlite_timestamp),
+        }])
+    }
+}
@@ -0,0 +1,115 @@
+use std::path::{Path, PathBuf};
+
+use serde::Deserialize;
+
+use super::{sqlite_timestamp, ImportedUsage, LogContext, UsageLog};
+
+#[derive(Deserialize)]
+struct Entry {
+    #[serde(rename = "type")]
+    kind: Option<String>,
+    timestamp: Option<String>,
+    payload: Option<serde_json::Value>,
+}
+
+#[derive(Deserialize)]
+struct TokenUsage {
+    #[serde(default)]
+    input_tokens: i64,
+    #[serde(default)]
+    cached_input_tokens: i64,
+    #[serde(default)]
+    output_tokens: i64,
+    #[serde(default)]
+    total_tokens: i64,
+}
+
+/// Codex CLI rollout logs under `~/.codex/sessions/YYYY/MM/DD/` (or
+/// `$CODEX_HOME/sessions/`). The session id and cwd are on the first line,
+/// the model on each turn's context line, and every turn ends with a
+/// `token_count` event holding that turn's usage and the session total.
+pub struct CodexSessions;
+
+impl UsageLog for CodexSessions {
+    fn agent(&self) -> &'static str {
+        "codex-cli"
+    }
+
+    fn root(&self) -> Option<PathBuf> {
+        let home = match std::env::var_os("CODEX_HOME") {
+            Some(dir) => PathBuf::from(dir),
+            None => PathBuf::from(std::env::var_os("HOME")?).join(".codex"),
+        };
+        Some(home.join("sessions"))
+    }
+
+    fn is_log(&self, path: &Path) -> bool {
+        path.extension().is_some_and(|ext| ext == "jsonl")
+            && path
+                .file_name()
+                .is_some_and(|name| name.to_string_lossy().starts_with("rollout-"))
+    }
+
+    fn parse(&self, text: &str, context: &mut LogContext) -> Result<Vec<ImportedUsage>, String> {
+        let entry = serde_json::from_str::<Entry>(text)
+            .map_err(|err| format!("invalid rollout entry: {err}"))?;
+        let Some(payload) = entry.payload else {
+            return Ok(Vec::new());
+        };
+        let field = |name: &str| payload.get(name).and_then(|value| value.as_str());
+
+        match entry.kind.as_deref() {
+            Some("session_meta") => {
+                context.session_id = field("id").map(str::to_string);
+                context.cwd = field("cwd").map(str::to_string);
+                Ok(Vec::new())
+            }
+            Some("turn_context") => {
+                if let Some(model) = field("model") {
+                    context.model = Some(model.to_string());
+                }
+                if let Some(cwd) = field("cwd") {
+                    context.cwd = Some(cwd.to_string());
+                }
+                Ok(Vec::new())
+            }
+            Some("event_msg") if field("type") == Some("token_count") => {
+                // `info` is null until the first response arrives.
+                let Some(info) = payload.get("info").filter(|info| !info.is_null()) else {
+                    return Ok(Vec::new());
+                };
+                let usage = |name: &str| {
+                    info.get(name)
+                        .cloned()
+                        .map(serde_json::from_value::<TokenUsage>)
+                        .transpose()
+                        .map_err(|err| format!("invalid token_count {name}: {err}"))
+                };
+                let (Some(last), Some(total)) =
+                    (usage("last_token_usage")?, usage("total_token_usage")?)
+                else {
+                    return Ok(Vec::new());
+                };
+                let Some(session_id) = context.session_id.clone() else {
+                    return Err("token_count before session_meta".to_string());
+                };
+
+                // Codex repeats a token_count when nothing changed; the
+                // running total tells the repeats apart from new turns.
+                Ok(vec![ImportedUsage {
+                    message_id: format!("{session_id}:{}", total.total_tokens),
+                    session_id,
+                    model: context.model.clone(),
+                    cwd: context.cwd.clone(),
+                    // OpenAI counts cached input as part of the input.
+                    tokens_in: last.input_tokens - last.cached_input_tokens,
+                    tokens_out: last.output_tokens,
+                    cache_read_tokens: last.cached_input_tokens,
+                    cache_creation_tokens: 0,
+                    captured_at: entry.timestamp.as_deref().and_then(sqlite_timestamp),
+                }])
+            }
+            _ => Ok(Vec::new()),
+        }
+    }
+}
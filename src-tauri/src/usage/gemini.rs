@@ -0,0 +1,97 @@
+use std::path::{Path, PathBuf};
+
+use serde::Deserialize;
+
+use super::{sqlite_timestamp, ImportedUsage, LogContext, UsageLog};
+
+#[derive(Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct Chat {
+    session_id: Option<String>,
+    #[serde(default)]
+    messages: Vec<Message>,
+}
+
+#[derive(Deserialize)]
+struct Message {
+    id: Option<String>,
+    #[serde(rename = "type")]
+    kind: Option<String>,
+    timestamp: Option<String>,
+    model: Option<String>,
+    tokens: Option<Tokens>,
+}
+
+#[derive(Deserialize)]
+struct Tokens {
+    #[serde(default)]
+    input: i64,
+    #[serde(default)]
+    output: i64,
+    #[serde(default)]
+    cached: i64,
+    #[serde(default)]
+    thoughts: i64,
+}
+
+/// Gemini CLI chat recordings, `~/.gemini/tmp/<project hash>/chats/session-*.json`.
+/// Each file is one JSON document rewritten as the chat grows. The project
+/// directory is only recorded as a hash, so rows carry no project.
+pub struct GeminiChats;
+
+impl UsageLog for GeminiChats {
+    fn agent(&self) -> &'static str {
+        "gemini-cli"
+    }
+
+    fn root(&self) -> Option<PathBuf> {
+        Some(
+            PathBuf::from(std::env::var_os("HOME")?)
+                .join(".gemini")
+                .join("tmp"),
+        )
+    }
+
+    fn is_log(&self, path: &Path) -> bool {
+        path.extension().is_some_and(|ext| ext == "json")
+            && path
+                .file_name()
+                .is_some_and(|name| name.to_string_lossy().starts_with("session-"))
+            && path
+                .parent()
+                .and_then(|dir| dir.file_name())
+                .is_some_and(|name| name == "chats")
+    }
+
+    fn append_only(&self) -> bool {
+        false
+    }
+
+    fn parse(&self, text: &str, _context: &mut LogContext) -> Result<Vec<ImportedUsage>, String> {
+        let chat =
+            serde_json::from_str::<Chat>(text).map_err(|err| format!("invalid chat log: {err}"))?;
+        let session_id = chat.session_id.unwrap_or_default();
+
+        Ok(chat
+            .messages
+            .into_iter()
+            .filter(|message| message.kind.as_deref() == Some("gemini"))
+            .filter_map(|message| {
+                let tokens = message.tokens?;
+                Some(ImportedUsage {
+                    message_id: message.id?,
+                    session_id: session_id.clone(),
+                    model: message.model,
+                    cwd: None,
+                    // Gemini counts cached input as part of the input, and
+                    // bills thinking as output.
+                    tokens_in: tokens.input - tokens.cached,
+                    tokens_out: tokens.output + tokens.thoughts,
+                    cache_read_tokens: tokens.cached,
+                    cache_creation_tokens: 0,
+                    captured_at: message.timestamp.as_deref().and_then(sqlite_timestamp),
+                })
+            })
+            .collect())
+    }
+}
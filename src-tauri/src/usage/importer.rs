@@ -0,0 +1,342 @@
+use std::collections::HashMap;
+use std::fs::File;
+use std::io::{Read, Seek, SeekFrom};
+use std::path::{Path, PathBuf};
+use std::sync::mpsc::{self, RecvTimeoutError};
+use std::time::Duration;
+
+use notify::{
+    Event, EventKind, RecommendedWatcher, RecursiveMode, Result as NotifyResult, Watcher,
+};
+use rusqlite::{params, Connection, OptionalExtension};
+use serde::Serialize;
+use tauri::{AppHandle, Emitter};
+
+use crate::git::porcelain;
+use crate::usage::{self, pricing, upsert_usage, LogContext, UsageLog};
+
+/// How long to wait for a burst of writes to settle before reading.
+const SETTLE: Duration = Duration::from_millis(250);
+
+/// How often log roots that don't exist yet are looked for again, so an
+/// agent installed while the app is running is picked up.
+const ROOT_RECHECK: Duration = Duration::from_secs(30);
+
+#[derive(Debug, Clone, Serialize)]
+pub struct UsageImportedPayload {
+    pub agent: String,
+    /// Rows inserted or updated.
+    pub rows: usize,
+}
+
+/// Backfills every agent's usage logs, then follows them as they grow.
+/// Roots that don't exist yet, for agents that have never run on this
+/// machine, are checked again every `ROOT_RECHECK` and followed once they
+/// appear.
+pub fn start(app_handle: AppHandle, db_path: PathBuf) {
+    std::thread::spawn(move || {
+        let logs = usage::all_logs();
+        let mut pending = logs
+            .iter()
+            .enumerate()
+            .filter_map(|(index, log)| Some((index, log.agent(), log.root()?)))
+            .collect::<Vec<_>>();
+        let mut importer = match UsageImporter::new(&db_path, logs) {
+            Ok(importer) => importer,
+            Err(err) => {
+                log::warn!("failed to start usage import: {err}");
+                return;
+            }
+        };
+
+        let (sender, receiver) = mpsc::channel::<(usize, PathBuf)>();
+        // Kept for the life of the thread, which is the life of the app.
+        let mut watchers = Vec::<RecommendedWatcher>::new();
+        loop {
+            // The watcher starts before the backfill so nothing written in
+            // between is missed.
+            let mut files = Vec::new();
+            pending.retain(|(index, agent, root)| {
+                if !root.is_dir() {
+                    return true;
+                }
+                match watch(root, *index, sender.clone()) {
+                    Ok(watcher) => watchers.push(watcher),
+                    Err(err) => log::warn!("failed to follow {agent} logs: {err}"),
+                }
+                collect_files(root, &mut |path| files.push((*index, path)));
+                false
+            });
+            if !files.is_empty() {
+                emit_imported(&app_handle, importer.import_all(&files));
+            }
+
+            let file = match receiver.recv_timeout(ROOT_RECHECK) {
+                Ok(file) => file,
+                Err(RecvTimeoutError::Timeout) => continue,
+                Err(RecvTimeoutError::Disconnected) => return,
+            };
+            std::thread::sleep(SETTLE);
+            let mut files = vec![file];
+            files.extend(receiver.try_iter());
+            files.sort();
+            files.dedup();
+            emit_imported(&app_handle, importer.import_all(&files));
+        }
+    });
+}
+
+fn watch(
+    root: &Path,
+    index: usize,
+    sender: mpsc::Sender<(usize, PathBuf)>,
+) -> Result<RecommendedWatcher, String> {
+    let mut watcher = notify::recommended_watcher(move |event: NotifyResult<Event>| {
+        let Ok(event) = event else {
+            return;
+        };
+        if !matches!(event.kind, EventKind::Create(_) | EventKind::Modify(_)) {
+            return;
+        }
+        for path in event.paths {
+            let _ = sender.send((index, path));
+        }
+    })
+    .map_err(|err| format!("failed to create usage log watcher: {err}"))?;
+
+    watcher
+        .watch(root, RecursiveMode::Recursive)
+        .map_err(|err| format!("failed to watch {}: {err}", root.display()))?;
+
+    Ok(watcher)
+}
+
+fn emit_imported(app_handle: &AppHandle, imported: Vec<(&'static str, usize)>) {
+    for (agent, rows) in imported {
+        let _ = app_handle.emit(
+            "usage:imported",
+            UsageImportedPayload {
+                agent: agent.to_string(),
+                rows,
+            },
+        );
+    }
+}
+
+/// Reads usage logs from where the previous scan stopped. Positions,
+/// per-file context and parse errors are stored in `usage_import_files`,
+/// so a restart only reads what is new.
+pub struct UsageImporter {
+    conn: Connection,
+    logs: Vec<Box<dyn UsageLog>>,
+    projects: HashMap<String, String>,
+}
+
+#[derive(Default)]
+struct ScanState {
+    position: u64,
+    context: LogContext,
+    errors: i64,
+    last_error: Option<String>,
+}
+
+impl UsageImporter {
+    pub fn new(db_path: &Path, logs: Vec<Box<dyn UsageLog>>) -> Result<Self, String> {
+        let conn = Connection::open(db_path)
+            .map_err(|err| format!("failed to open db for usage import: {err}"))?;
+        conn.busy_timeout(Duration::from_secs(5))
+            .map_err(|err| format!("failed to set busy timeout: {err}"))?;
+
+        Ok(Self {
+            conn,
+            logs,
+            projects: HashMap::new(),
+        })
+    }
+
+    /// Imports each `(log index, path)` and returns the rows written per
+    /// agent, leaving out agents with nothing new.
+    pub fn import_all(&mut self, files: &[(usize, PathBuf)]) -> Vec<(&'static str, usize)> {
+        let mut imported = Vec::<(&'static str, usize)>::new();
+
+        for (index, path) in files {
+            if !self.logs[*index].is_log(path) {
+                continue;
+            }
+            let rows = match self.import(*index, path) {
+                Ok(rows) => rows,
+                Err(err) => {
+                    log::warn!("failed to import {}: {err}", path.display());
+                    0
+                }
+            };
+            if rows == 0 {
+                continue;
+            }
+
+            let agent = self.logs[*index].agent();
+            match imported.iter_mut().find(|(seen, _)| *seen == agent) {
+                Some((_, total)) => *total += rows,
+                None => imported.push((agent, rows)),
+            }
+        }
+
+        imported
+    }
+
+    /// Imports what changed in `path` since the last scan. For append-only
+    /// logs a trailing partial line is left for the next scan.
+    pub fn import(&mut self, index: usize, path: &Path) -> Result<usize, String> {
+        let key = path.to_string_lossy().to_string();
+        let stored = self.scan_state(&key)?;
+
+        let mut file = File::open(path).map_err(|err| format!("failed to open log: {err}"))?;
+        let len = file
+            .metadata()
+            .map_err(|err| format!("failed to stat log: {err}"))?
+            .len();
+        if len == stored.position {
+            return Ok(0);
+        }
+
+        let log = &self.logs[index];
+        // Rewritten logs are read whole, and so is an append-only log that
+        // got shorter, since it was truncated or replaced.
+        let mut state = if log.append_only() && len > stored.position {
+            stored
+        } else {
+            ScanState::default()
+        };
+
+        let mut bytes = Vec::new();
+        file.seek(SeekFrom::Start(state.position))
+            .and_then(|_| file.read_to_end(&mut bytes))
+            .map_err(|err| format!("failed to read log: {err}"))?;
+
+        let mut usages = Vec::new();
+        let mut parse =
+            |text: &str, state: &mut ScanState| match log.parse(text, &mut state.context) {
+                Ok(parsed) => usages.extend(parsed),
+                Err(err) => {
+                    state.errors += 1;
+                    state.last_error = Some(err);
+                }
+            };
+        if log.append_only() {
+            let Some(end) = bytes.iter().rposition(|byte| *byte == b'\n') else {
+                return Ok(0);
+            };
+            let text = String::from_utf8_lossy(&bytes[..=end]).to_string();
+            for line in text.lines().filter(|line| !line.trim().is_empty()) {
+                parse(line, &mut state);
+            }
+            state.position += end as u64 + 1;
+        } else {
+            parse(&String::from_utf8_lossy(&bytes), &mut state);
+            state.position = len;
+        }
+
+        let agent = log.agent();
+        let fallback_cwd = log.log_cwd(path);
+        let projects = usages
+            .iter()
+            .map(|usage| self.project_for(usage.cwd.as_deref().or(fallback_cwd.as_deref())))
+            .collect::<Vec<_>>();
+        let context = serde_json::to_string(&state.context)
+            .map_err(|err| format!("failed to encode log context: {err}"))?;
+
+        let tx = self
+            .conn
+            .transaction()
+            .map_err(|err| format!("failed to start import transaction: {err}"))?;
+        let mut ids = Vec::with_capacity(usages.len());
+        for (usage, project) in usages.iter().zip(&projects) {
+            ids.push(upsert_usage(&tx, agent, project.as_deref(), usage)?);
+        }
+        tx.execute(
+            "INSERT INTO usage_import_files (path, agent, position, context, errors, last_error) VALUES (?1, ?2, ?3, ?4, ?5, ?6)
+             ON CONFLICT(path) DO UPDATE SET position = excluded.position, context = excluded.context, errors = excluded.errors, last_error = excluded.last_error, scanned_at = CURRENT_TIMESTAMP",
+            params![key, agent, state.position as i64, context, state.errors, state.last_error],
+        )
+        .map_err(|err| format!("failed to store import position: {err}"))?;
+        pricing::estimate_rows(&tx, &ids)?;
+        tx.commit()
+            .map_err(|err| format!("failed to commit usage import: {err}"))?;
+
+        Ok(usages.len())
+    }
+
+    fn scan_state(&self, key: &str) -> Result<ScanState, String> {
+        let row = self
+            .conn
+            .query_row(
+                "SELECT position, context, errors, last_error FROM usage_import_files WHERE path = ?1",
+                params![key],
+                |row| {
+                    Ok((
+                        row.get::<_, i64>(0)?,
+                        row.get::<_, Option<String>>(1)?,
+                        row.get::<_, i64>(2)?,
+                        row.get::<_, Option<String>>(3)?,
+                    ))
+                },
+            )
+            .optional()
+            .map_err(|err| format!("failed to read import position: {err}"))?;
+
+        Ok(match row {
+            Some((position, context, errors, last_error)) => ScanState {
+                position: position as u64,
+                context: context
+                    .and_then(|context| serde_json::from_str(&context).ok())
+                    .unwrap_or_default(),
+                errors,
+                last_error,
+            },
+            None => ScanState::default(),
+        })
+    }
+
+    /// Repository root of the directory the agent ran in.
+    fn project_for(&mut self, cwd: Option<&str>) -> Option<String> {
+        let dir = cwd?.to_string();
+        let project = self
+            .projects
+            .entry(dir.clone())
+            .or_insert_with(|| porcelain::discover_repo_root(&dir).unwrap_or(dir));
+        Some(project.clone())
+    }
+}
+
+/// Whether `log`'s root holds at least one of its logs.
+pub fn has_logs(log: &dyn UsageLog) -> bool {
+    fn walk(dir: &Path, log: &dyn UsageLog) -> bool {
+        let Ok(entries) = std::fs::read_dir(dir) else {
+            return false;
+        };
+        entries.flatten().any(|entry| {
+            let path = entry.path();
+            if path.is_dir() {
+                walk(&path, log)
+            } else {
+                log.is_log(&path)
+            }
+        })
+    }
+
+    log.root().is_some_and(|root| walk(&root, log))
+}
+
+fn collect_files(dir: &Path, found: &mut impl FnMut(PathBuf)) {
+    let Ok(entries) = std::fs::read_dir(dir) else {
+        return;
+    };
+    for entry in entries.flatten() {
+        let path = entry.path();
+        if path.is_dir() {
+            collect_files(&path, found);
+        } else {
+            found(path);
+        }
+    }
+}
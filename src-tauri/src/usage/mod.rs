@@ -0,0 +1,135 @@
+pub mod claude;
+pub mod codex;
+pub mod gemini;
+pub mod importer;
+pub mod pricing;
+
+use std::path::{Path, PathBuf};
+
+use rusqlite::{params, Connection};
+use serde::{Deserialize, Serialize};
+
+/// An agent CLI's own session logs on disk.
+pub trait UsageLog: Send {
+    /// Name stored in `token_usage.agent`.
+    fn agent(&self) -> &'static str;
+
+    /// Directory the agent writes its logs under.
+    fn root(&self) -> Option<PathBuf>;
+
+    fn is_log(&self, path: &Path) -> bool;
+
+    /// JSONL logs are appended to and read line by line from where the last
+    /// scan stopped. Other logs are rewritten whole and re-read on change.
+    fn append_only(&self) -> bool {
+        true
+    }
+
+    /// Working directory implied by the log's location, for entries that
+    /// don't record one.
+    fn log_cwd(&self, _path: &Path) -> Option<String> {
+        None
+    }
+
+    /// Parses one line of an append-only log, or a whole rewritten log.
+    fn parse(&self, text: &str, context: &mut LogContext) -> Result<Vec<ImportedUsage>, String>;
+}
+
+/// Per-file state carried from one scan to the next, such as the session
+/// id a Codex log only records on its first line.
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct LogContext {
+    pub session_id: Option<String>,
+    pub cwd: Option<String>,
+    pub model: Option<String>,
+}
+
+/// Values of `token_usage.source`: read from terminal output, imported from
+/// an agent's logs, or reported by agent hooks and OpenTelemetry exporters.
+pub const SOURCES: &[&str] = &["scraped", "transcript", "hook", "otel"];
+
+/// Condition on `token_usage` rows that count towards spend and totals.
+/// Scraped summaries repeat what an agent's own logs record, so they are
+/// left out on days for which that agent's logs have been imported. Scraped
+/// rows carry an Aion session id and no project, so the day is the closest
+/// overlap the two sources share.
+pub const COUNTED_USAGE: &str = "NOT (source = 'scraped' AND EXISTS (SELECT 1 FROM token_usage AS imported WHERE imported.agent = token_usage.agent AND imported.source = 'transcript' AND imported.captured_at >= date(token_usage.captured_at) AND imported.captured_at < date(token_usage.captured_at, '+1 day')))";
+
+pub fn all_logs() -> Vec<Box<dyn UsageLog>> {
+    vec![
+        Box::new(claude::ClaudeTranscripts),
+        Box::new(codex::CodexSessions),
+        Box::new(gemini::GeminiChats),
+    ]
+}
+
+/// One API response's usage read from an agent's own logs.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct ImportedUsage {
+    /// Provider message id; a message logged more than once is stored once.
+    pub message_id: String,
+    /// The agent's own session id, not an Aion session.
+    pub session_id: String,
+    pub model: Option<String>,
+    /// Working directory the agent ran in.
+    pub cwd: Option<String>,
+    pub tokens_in: i64,
+    pub tokens_out: i64,
+    pub cache_read_tokens: i64,
+    pub cache_creation_tokens: i64,
+    /// `YYYY-MM-DD HH:MM:SS` in UTC, like `CURRENT_TIMESTAMP`.
+    pub captured_at: Option<String>,
+}
+
+/// Inserts `usage` as a `transcript` row, or folds it into the row already
+/// stored for the same message. Agents log a streamed message several
+/// times as it grows, so the largest counts win and any estimated cost is
+/// dropped to be estimated again.
+pub fn upsert_usage(
+    conn: &Connection,
+    agent: &str,
+    project: Option<&str>,
+    usage: &ImportedUsage,
+) -> Result<i64, String> {
+    conn.query_row(
+        "INSERT INTO token_usage (session_id, agent, source, model, project, message_id, cost_usd, tokens_in, tokens_out, tokens_total, cache_read_tokens, cache_creation_tokens, captured_at)
+         VALUES (?1, ?2, 'transcript', ?3, ?4, ?5, 0, ?6, ?7, ?8, ?9, ?10, COALESCE(?11, CURRENT_TIMESTAMP))
+         ON CONFLICT(agent, message_id) WHERE message_id IS NOT NULL DO UPDATE SET
+             tokens_in = MAX(tokens_in, excluded.tokens_in),
+             tokens_out = MAX(tokens_out, excluded.tokens_out),
+             tokens_total = MAX(tokens_total, excluded.tokens_total),
+             cache_read_tokens = MAX(COALESCE(cache_read_tokens, 0), excluded.cache_read_tokens),
+             cache_creation_tokens = MAX(COALESCE(cache_creation_tokens, 0), excluded.cache_creation_tokens),
+             model = COALESCE(excluded.model, model),
+             cost_usd = CASE WHEN estimated = 1 THEN 0 ELSE cost_usd END,
+             estimated = 0
+         RETURNING id",
+        params![
+            usage.session_id,
+            agent,
+            usage.model,
+            project,
+            usage.message_id,
+            usage.tokens_in,
+            usage.tokens_out,
+            usage.tokens_in + usage.tokens_out,
+            usage.cache_read_tokens,
+            usage.cache_creation_tokens,
+            usage.captured_at,
+        ],
+        |row| row.get(0),
+    )
+    .map_err(|err| format!("failed to store imported usage: {err}"))
+}
+
+/// Converts an RFC 3339 timestamp to the format SQLite's
+/// `CURRENT_TIMESTAMP` writes, so imported and scraped rows sort together.
+pub(crate) fn sqlite_timestamp(value: &str) -> Option<String> {
+    chrono::DateTime::parse_from_rfc3339(value)
+        .ok()
+        .map(|time| {
+            time.with_timezone(&chrono::Utc)
+                .format("%Y-%m-%d %H:%M:%S")
+                .to_string()
+        })
+}
@@ -0,0 +1,245 @@
+use rusqlite::{params, Connection, OptionalExtension, Params};
+use serde::{Deserialize, Serialize};
+
+/// Prices seeded when the database is first prepared, in USD per million
+/// tokens: input, output, cache read, cache write.
+const DEFAULT_PRICES: &[(&str, f64, f64, f64, f64)] = &[
+    ("claude-opus-4", 15.0, 75.0, 1.5, 18.75),
+    ("claude-opus-4-5", 5.0, 25.0, 0.5, 6.25),
+    ("claude-sonnet-4", 3.0, 15.0, 0.3, 3.75),
+    ("claude-3-7-sonnet", 3.0, 15.0, 0.3, 3.75),
+    ("claude-3-5-sonnet", 3.0, 15.0, 0.3, 3.75),
+    ("claude-haiku-4-5", 1.0, 5.0, 0.1, 1.25),
+    ("claude-3-5-haiku", 0.8, 4.0, 0.08, 1.0),
+    ("gpt-5", 1.25, 10.0, 0.125, 0.0),
+    ("gpt-5-mini", 0.25, 2.0, 0.025, 0.0),
+    ("gpt-5-nano", 0.05, 0.4, 0.005, 0.0),
+    ("gpt-4.1", 2.0, 8.0, 0.5, 0.0),
+    ("o3", 2.0, 8.0, 0.5, 0.0),
+    ("o3-mini", 1.1, 4.4, 0.55, 0.0),
+    ("o4-mini", 1.1, 4.4, 0.275, 0.0),
+    ("gemini-2.5-pro", 1.25, 10.0, 0.31, 0.0),
+    ("gemini-2.5-flash", 0.3, 2.5, 0.075, 0.0),
+    ("gemini-2.5-flash-lite", 0.1, 0.4, 0.025, 0.0),
+];
+
+/// Effective date of the seeded prices; they apply to all earlier usage too.
+const DEFAULT_EFFECTIVE_FROM: &str = "2024-01-01";
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ModelPrice {
+    #[serde(default)]
+    pub id: i64,
+    /// Matched as a prefix of the reported model, longest match first, so
+    /// `claude-sonnet-4` covers `claude-sonnet-4-20250514`.
+    pub model: String,
+    /// USD per million tokens.
+    pub input: f64,
+    pub output: f64,
+    pub cache_read: f64,
+    pub cache_write: f64,
+    /// `YYYY-MM-DD`; the price applies to usage captured from this day until
+    /// the next price for the same model.
+    pub effective_from: String,
+}
+
+#[derive(Debug, Clone, Default, Serialize)]
+pub struct RecomputeSummary {
+    /// Rows whose estimated cost was written.
+    pub updated: usize,
+    /// Rows with tokens but no reported cost and no matching price.
+    pub unpriced: usize,
+}
+
+/// `app_meta` key set once `DEFAULT_PRICES` have been seeded.
+const SEEDED_KEY: &str = "model_prices_seeded";
+
+/// Seeds `DEFAULT_PRICES` once per database, so prices the user deleted
+/// don't come back on the next start.
+pub fn seed_defaults(conn: &Connection) -> Result<(), String> {
+    let seeded = conn
+        .query_row("SELECT 1 FROM app_meta WHERE key = ?1", params![SEEDED_KEY], |_| Ok(()))
+        .optional()
+        .map_err(|err| format!("failed to read price seeding marker: {err}"))?
+        .is_some();
+    if seeded {
+        return Ok(());
+    }
+
+    // Databases from before the marker were seeded if they hold any price.
+    let count = conn
+        .query_row("SELECT COUNT(*) FROM model_prices", [], |row| {
+            row.get::<_, i64>(0)
+        })
+        .map_err(|err| format!("failed to count model prices: {err}"))?;
+    if count == 0 {
+        for (model, input, output, cache_read, cache_write) in DEFAULT_PRICES {
+            conn.execute(
+                "INSERT INTO model_prices (model, input, output, cache_read, cache_write, effective_from) VALUES (?1, ?2, ?3, ?4, ?5, ?6)",
+                params![model, input, output, cache_read, cache_write, DEFAULT_EFFECTIVE_FROM],
+            )
+            .map_err(|err| format!("failed to seed model prices: {err}"))?;
+        }
+    }
+
+    conn.execute(
+        "INSERT INTO app_meta (key, value) VALUES (?1, '1')",
+        params![SEEDED_KEY],
+    )
+    .map_err(|err| format!("failed to store price seeding marker: {err}"))?;
+
+    Ok(())
+}
+
+pub fn list_prices(conn: &Connection) -> Result<Vec<ModelPrice>, String> {
+    let mut statement = conn
+        .prepare(
+            "SELECT id, model, input, output, cache_read, cache_write, effective_from FROM model_prices ORDER BY model, effective_from",
+        )
+        .map_err(|err| format!("failed to prepare model price query: {err}"))?;
+
+    let prices = statement
+        .query_map([], |row| {
+            Ok(ModelPrice {
+                id: row.get(0)?,
+                model: row.get(1)?,
+                input: row.get(2)?,
+                output: row.get(3)?,
+                cache_read: row.get(4)?,
+                cache_write: row.get(5)?,
+                effective_from: row.get(6)?,
+            })
+        })
+        .map_err(|err| format!("failed to execute model price query: {err}"))?
+        .collect::<Result<Vec<_>, _>>()
+        .map_err(|err| format!("failed to map model price rows: {err}"))?;
+
+    Ok(prices)
+}
+
+/// Price in effect for `model` on `date` (`YYYY-MM-DD...`).
+pub fn price_for<'a>(prices: &'a [ModelPrice], model: &str, date: &str) -> Option<&'a ModelPrice> {
+    let model = model.to_ascii_lowercase();
+    prices
+        .iter()
+        .filter(|price| model.starts_with(&price.model.to_ascii_lowercase()))
+        .filter(|price| price.effective_from.as_str() <= date)
+        .max_by(|a, b| {
+            a.model
+                .len()
+                .cmp(&b.model.len())
+                .then_with(|| a.effective_from.cmp(&b.effective_from))
+        })
+}
+
+pub fn estimate_cost(
+    price: &ModelPrice,
+    tokens_in: i64,
+    tokens_out: i64,
+    cache_read_tokens: i64,
+    cache_creation_tokens: i64,
+) -> f64 {
+    (tokens_in as f64 * price.input
+        + tokens_out as f64 * price.output
+        + cache_read_tokens as f64 * price.cache_read
+        + cache_creation_tokens as f64 * price.cache_write)
+        / 1_000_000.0
+}
+
+/// Estimates the cost of rows that report tokens but no cost. With `all`,
+/// rows estimated earlier are priced again too, for when prices change.
+/// Scraped rows are priced as they are written, and a zero cost there is a
+/// readout that didn't move, so only their earlier estimates are redone.
+pub fn estimate_costs(conn: &Connection, all: bool) -> Result<RecomputeSummary, String> {
+    let filter = if all {
+        "(estimated = 1 OR (cost_usd = 0 AND source <> 'scraped'))"
+    } else {
+        "estimated = 0 AND cost_usd = 0 AND source <> 'scraped'"
+    };
+
+    let rows = unpriced_rows(conn, filter, [])?;
+    store_estimates(conn, rows, all)
+}
+
+/// `estimate_costs` without `all`, limited to the rows in `ids`, for rows
+/// just written rather than the whole table.
+pub fn estimate_rows(conn: &Connection, ids: &[i64]) -> Result<RecomputeSummary, String> {
+    let mut rows = Vec::new();
+    for id in ids {
+        rows.extend(unpriced_rows(
+            conn,
+            "id = ?1 AND estimated = 0 AND cost_usd = 0",
+            params![id],
+        )?);
+    }
+    store_estimates(conn, rows, false)
+}
+
+type UnpricedRow = (i64, String, String, i64, i64, i64, i64);
+
+fn unpriced_rows(
+    conn: &Connection,
+    filter: &str,
+    params: impl Params,
+) -> Result<Vec<UnpricedRow>, String> {
+    let mut statement = conn
+        .prepare_cached(&format!(
+            "SELECT id, model, captured_at, tokens_in, tokens_out, COALESCE(cache_read_tokens, 0), COALESCE(cache_creation_tokens, 0) FROM token_usage WHERE {filter} AND model IS NOT NULL AND tokens_in + tokens_out + COALESCE(cache_read_tokens, 0) + COALESCE(cache_creation_tokens, 0) > 0"
+        ))
+        .map_err(|err| format!("failed to prepare cost estimate query: {err}"))?;
+    let rows = statement
+        .query_map(params, |row| {
+            Ok((
+                row.get::<_, i64>(0)?,
+                row.get::<_, String>(1)?,
+                row.get::<_, String>(2)?,
+                row.get::<_, i64>(3)?,
+                row.get::<_, i64>(4)?,
+                row.get::<_, i64>(5)?,
+                row.get::<_, i64>(6)?,
+            ))
+        })
+        .map_err(|err| format!("failed to execute cost estimate query: {err}"))?
+        .collect::<Result<Vec<_>, _>>()
+        .map_err(|err| format!("failed to map cost estimate rows: {err}"))?;
+    Ok(rows)
+}
+
+fn store_estimates(
+    conn: &Connection,
+    rows: Vec<UnpricedRow>,
+    all: bool,
+) -> Result<RecomputeSummary, String> {
+    if rows.is_empty() {
+        return Ok(RecomputeSummary::default());
+    }
+    let prices = list_prices(conn)?;
+
+    let mut summary = RecomputeSummary::default();
+    for (id, model, captured_at, tokens_in, tokens_out, cache_read, cache_creation) in rows {
+        let (cost, estimated) = match price_for(&prices, &model, &captured_at) {
+            Some(price) => {
+                summary.updated += 1;
+                (
+                    estimate_cost(price, tokens_in, tokens_out, cache_read, cache_creation),
+                    true,
+                )
+            }
+            None => {
+                summary.unpriced += 1;
+                if !all {
+                    continue;
+                }
+                // The price behind an earlier estimate was removed.
+                (0.0, false)
+            }
+        };
+        conn.execute(
+            "UPDATE token_usage SET cost_usd = ?1, estimated = ?2 WHERE id = ?3",
+            params![cost, estimated, id],
+        )
+        .map_err(|err| format!("failed to store estimated cost: {err}"))?;
+    }
+
+    Ok(summary)
+}
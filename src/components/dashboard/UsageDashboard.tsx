@@ -5,14 +5,14 @@ import { AgentBreakdown } from './AgentBreakdown';
 import { BudgetConfig } from '@/components/settings/BudgetConfig';
 
 export function UsageDashboard() {
-  const { loading, error, records, aggregates, budget, updateBudget } = useTokenUsage();
+  const { loading, error, recordCount, aggregates, budget, updateBudget } = useTokenUsage();
 
   return (
     <section className="flex h-full flex-col bg-[var(--surface-secondary)]">
       <header className="flex h-11 items-center justify-between border-b-2 border-[var(--border-strong)] bg-[var(--surface-primary)] px-3">
         <h3 className="font-display text-sm font-bold uppercase tracking-widest">Usage</h3>
         <span className="border-2 border-[var(--border-default)] bg-[var(--surface-tertiary)] px-2 py-0.5 text-[10px] font-semibold uppercase tracking-wider text-[var(--text-secondary)]">
-          {records.length} records
+          {recordCount} records
         </span>
       </header>
       <div className="flex-1 overflow-auto p-3">
@@ -0,0 +1,155 @@
+import { useCallback, useEffect, useState } from 'react';
+import { pricingList, pricingRemove, pricingSet, usageRecomputeCosts, usageRepairCumulative } from '@/lib/ipc';
+import type { ModelPrice } from '@/lib/types';
+import { Button } from '@/components/ui/Button';
+import { Input } from '@/components/ui/Input';
+
+const PRICE_FIELDS = ['input', 'output', 'cache_read', 'cache_write'] as const;
+
+function today() {
+  return new Date().toISOString().slice(0, 10);
+}
+
+function emptyPrice(): ModelPrice {
+  return { id: 0, model: '', input: 0, output: 0, cache_read: 0, cache_write: 0, effective_from: today() };
+}
+
+export function PricingConfig() {
+  const [prices, setPrices] = useState<ModelPrice[]>([]);
+  const [draft, setDraft] = useState<ModelPrice>(emptyPrice);
+  const [status, setStatus] = useState<string | null>(null);
+  const [error, setError] = useState<string | null>(null);
+
+  const run = useCallback(async (action: () => Promise<void>) => {
+    try {
+      setError(null);
+      await action();
+    } catch (err) {
+      setError(err instanceof Error ? err.message : String(err));
+    }
+  }, []);
+
+  useEffect(() => {
+    void run(async () => setPrices(await pricingList()));
+  }, [run]);
+
+  return (
+    <section className="mt-4">
+      <h4 className="mb-2 text-[10px] font-bold uppercase tracking-widest text-[var(--text-secondary)]">
+        Model Pricing (USD / 1M tokens)
+      </h4>
+      <div className="max-h-48 overflow-y-auto border-2 border-[var(--border-default)]">
+        <table className="w-full text-xs">
+          <thead className="text-[10px] uppercase tracking-wider text-[var(--text-secondary)]">
+            <tr>
+              <th className="px-2 py-1 text-left">Model</th>
+              <th className="px-2 py-1 text-right">In</th>
+              <th className="px-2 py-1 text-right">Out</th>
+              <th className="px-2 py-1 text-right">Cache read</th>
+              <th className="px-2 py-1 text-right">Cache write</th>
+              <th className="px-2 py-1 text-left">From</th>
+              <th />
+            </tr>
+          </thead>
+          <tbody className="font-mono">
+            {prices.map((price) => (
+              <tr key={price.id} className="cursor-pointer hover:bg-[var(--surface-tertiary)]" onClick={() => setDraft(price)}>
+                <td className="px-2 py-1">{price.model}</td>
+                {PRICE_FIELDS.map((field) => (
+                  <td key={field} className="px-2 py-1 text-right">
+                    {price[field]}
+                  </td>
+                ))}
+                <td className="px-2 py-1">{price.effective_from}</td>
+                <td className="px-2 py-1 text-right">
+                  <Button
+                    variant="ghost"
+                    compact
+                    onClick={(event) => {
+                      event.stopPropagation();
+                      void run(async () => {
+                        await pricingRemove(price.id);
+                        setPrices(await pricingList());
+                      });
+                    }}
+                  >
+                    Remove
+                  </Button>
+                </td>
+              </tr>
+            ))}
+          </tbody>
+        </table>
+      </div>
+
+      <div className="mt-2 grid grid-cols-6 gap-2">
+        <Input
+          className="col-span-2"
+          value={draft.model}
+          placeholder="model prefix"
+          onChange={(event) => setDraft({ ...draft, model: event.target.value })}
+        />
+        {PRICE_FIELDS.map((field) => (
+          <Input
+            key={field}
+            type="number"
+            min={0}
+            step="any"
+            value={draft[field]}
+            onChange={(event) => setDraft({ ...draft, [field]: Number(event.target.value) })}
+          />
+        ))}
+      </div>
+      <div className="mt-2 flex items-center gap-2">
+        <Input
+          className="w-36"
+          type="date"
+          value={draft.effective_from}
+          onChange={(event) => setDraft({ ...draft, effective_from: event.target.value })}
+        />
+        <Button
+          variant="primary"
+          compact
+          onClick={() =>
+            void run(async () => {
+              setPrices(await pricingSet(draft));
+              setDraft(emptyPrice());
+            })
+          }
+        >
+          {draft.id ? 'Update' : 'Add'}
+        </Button>
+        {draft.id ? (
+          <Button compact onClick={() => setDraft(emptyPrice())}>
+            New
+          </Button>
+        ) : null}
+        <Button
+          compact
+          className="ml-auto"
+          onClick={() =>
+            void run(async () => {
+              const summary = await usageRecomputeCosts();
+              setStatus(`Repriced ${summary.updated} rows, ${summary.unpriced} without a price`);
+            })
+          }
+        >
+          Recompute Costs
+        </Button>
+        <Button
+          compact
+          onClick={() =>
+            void run(async () => {
+              const repaired = await usageRepairCumulative();
+              setStatus(`Recounted ${repaired} /cost rows as increases`);
+            })
+          }
+        >
+          Repair /cost Rows
+        </Button>
+      </div>
+      {status ? <p className="mt-2 text-xs text-[var(--text-secondary)]">{status}</p> : null}
+      {error ? <p className="mt-2 text-xs font-medium text-[var(--status-error)]">{error}</p> : null}
+    </section>
+  );
+}
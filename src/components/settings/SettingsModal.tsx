@@ -1,6 +1,7 @@
 import { useEffect, useState } from 'react';
 import { Modal } from '@/components/ui/Modal';
 import { Button } from '@/components/ui/Button';
+import { PricingConfig } from './PricingConfig';
 import { ShellConfig } from './ShellConfig';
 import { useSettings } from '@/hooks/useSettings';
 
@@ -40,6 +41,7 @@ export function SettingsModal({ open, onClose }: Props) {
       {loading ? <p className="mb-2 text-xs text-[var(--text-secondary)]">Loading settings...</p> : null}
       {error ? <p className="mb-2 text-xs font-medium text-[var(--status-error)]">{error}</p> : null}
       <ShellConfig value={draft} onChange={setDraft} />
+      <PricingConfig />
     </Modal>
   );
 }
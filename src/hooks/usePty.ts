@@ -1,5 +1,14 @@
 import { useEffect, useMemo, useRef } from 'react';
-import { ptyKill, ptyResize, ptySpawn, ptyWrite, onPtyData, onPtyExit } from '@/lib/ipc';
+import {
+  gitCheckpointCreate,
+  ptyKill,
+  ptyResize,
+  ptySpawn,
+  ptyWrite,
+  onPtyData,
+  onPtyExit
+} from '@/lib/ipc';
+import type { AgentType, SandboxProfile, SessionLimits } from '@/lib/types';
 import { useSessionStore } from '@/stores/sessionStore';
 
 const decoder = new TextDecoder();
@@ -10,12 +19,25 @@ export function usePty() {
   const setStatus = useSessionStore((state) => state.setStatus);
   const setAgent = useSessionStore((state) => state.setAgent);
   const runningSessionsRef = useRef<Set<string>>(new Set());
+  const checkpointedSessionsRef = useRef<Set<string>>(new Set());
 
   useEffect(() => {
     let mounted = true;
     let unlistenData: (() => void) | undefined;
     let unlistenExit: (() => void) | undefined;
 
+    // Snapshot the working tree the first time an agent shows up in a session.
+    const detectAgent = (sessionId: string, agent: AgentType) => {
+      setAgent(sessionId, agent);
+      if (checkpointedSessionsRef.current.has(sessionId)) return;
+      checkpointedSessionsRef.current.add(sessionId);
+
+      const session = useSessionStore.getState().sessions.find((entry) => entry.id === sessionId);
+      if (session) {
+        gitCheckpointCreate(session.cwd, sessionId, 'agent start').catch(() => undefined);
+      }
+    };
+
     onPtyData((payload) => {
       if (!mounted) return;
 
@@ -29,9 +51,15 @@ export function usePty() {
 
       const normalized = data.toLowerCase();
       if (normalized.includes('claude code') || normalized.includes('/cost')) {
-        setAgent(payload.session_id, 'claude-code');
+        detectAgent(payload.session_id, 'claude-code');
+      } else if (normalized.includes('openai codex')) {
+        detectAgent(payload.session_id, 'codex-cli');
+      } else if (/aider v\d/.test(normalized)) {
+        detectAgent(payload.session_id, 'aider');
+      } else if (normalized.includes('gemini cli')) {
+        detectAgent(payload.session_id, 'gemini-cli');
       } else if (normalized.includes('copilot') || normalized.includes('github copilot')) {
-        setAgent(payload.session_id, 'copilot-cli');
+        detectAgent(payload.session_id, 'copilot-cli');
       }
     }).then((fn) => {
       if (mounted) {
@@ -44,6 +72,7 @@ export function usePty() {
     onPtyExit((payload) => {
       if (!mounted) return;
       runningSessionsRef.current.delete(payload.session_id);
+      checkpointedSessionsRef.current.delete(payload.session_id);
       setStatus(payload.session_id, 'terminated');
     }).then((fn) => {
       if (mounted) {
@@ -69,6 +98,10 @@ export function usePty() {
         env?: Record<string, string>;
         cols?: number;
         rows?: number;
+        worktree?: boolean;
+        checkpointIntervalS?: number;
+        sandbox?: SandboxProfile;
+        limits?: SessionLimits;
       }) =>
         ptySpawn({
           ...params,
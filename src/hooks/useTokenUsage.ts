@@ -1,7 +1,7 @@
 import { useCallback, useEffect, useMemo, useState } from 'react';
-import { onTokenCaptured, queryBudget, queryUsage, setBudget } from '@/lib/ipc';
+import { onTokenCaptured, onUsageImported, queryBudget, queryUsageSummary, setBudget } from '@/lib/ipc';
 import { useSettingsStore } from '@/stores/settingsStore';
-import type { UsageRecord } from '@/lib/types';
+import type { UsageSummary } from '@/lib/types';
 
 function monthString(date: Date) {
   return `${date.getFullYear()}-${String(date.getMonth() + 1).padStart(2, '0')}`;
@@ -13,7 +13,7 @@ function startOfMonthIso() {
 }
 
 export function useTokenUsage() {
-  const [records, setRecords] = useState<UsageRecord[]>([]);
+  const [summary, setSummary] = useState<UsageSummary | null>(null);
   const [loading, setLoading] = useState(false);
   const [error, setError] = useState<string | null>(null);
   const budget = useSettingsStore((state) => state.budget);
@@ -24,11 +24,11 @@ export function useTokenUsage() {
       setLoading(true);
       setError(null);
       const month = monthString(new Date());
-      const [nextRecords, nextBudget] = await Promise.all([
-        queryUsage({ from: startOfMonthIso() }),
+      const [nextSummary, nextBudget] = await Promise.all([
+        queryUsageSummary({ from: startOfMonthIso() }),
         queryBudget(month)
       ]);
-      setRecords(nextRecords);
+      setSummary(nextSummary);
       setBudgetState(nextBudget);
     } catch (err) {
       setError(err instanceof Error ? err.message : String(err));
@@ -42,27 +42,30 @@ export function useTokenUsage() {
   }, [refresh]);
 
   useEffect(() => {
-    let unlisten: (() => void) | undefined;
+    const unlisteners: Array<() => void> = [];
     let timeout: ReturnType<typeof setTimeout> | undefined;
 
-    void onTokenCaptured(() => {
+    const scheduleRefresh = () => {
       if (timeout) {
         clearTimeout(timeout);
       }
       timeout = setTimeout(() => {
         void refresh();
       }, 100);
-    }).then((fn) => {
-      unlisten = fn;
+    };
+
+    void onTokenCaptured(scheduleRefresh).then((fn) => {
+      unlisteners.push(fn);
+    });
+    void onUsageImported(scheduleRefresh).then((fn) => {
+      unlisteners.push(fn);
     });
 
     return () => {
       if (timeout) {
         clearTimeout(timeout);
       }
-      if (unlisten) {
-        unlisten();
-      }
+      unlisteners.forEach((unlisten) => unlisten());
     };
   }, [refresh]);
 
@@ -75,34 +78,23 @@ export function useTokenUsage() {
     [refresh]
   );
 
-  const aggregates = useMemo(() => {
-    const byDay = new Map<string, { total: number; agents: Record<string, number> }>();
-    const byAgent = new Map<string, number>();
-
-    for (const record of records) {
-      const day = record.captured_at.slice(0, 10);
-      const dayEntry = byDay.get(day) ?? { total: 0, agents: {} };
-      dayEntry.total += record.cost_usd;
-      dayEntry.agents[record.agent] = (dayEntry.agents[record.agent] ?? 0) + record.cost_usd;
-      byDay.set(day, dayEntry);
-
-      byAgent.set(record.agent, (byAgent.get(record.agent) ?? 0) + record.cost_usd);
-    }
-
-    return {
-      timeline: Array.from(byDay.entries())
-        .sort(([a], [b]) => a.localeCompare(b))
-        .map(([date, value]) => ({ date, ...value })),
-      breakdown: Array.from(byAgent.entries())
-        .map(([agent, cost]) => ({ agent, cost }))
-        .sort((a, b) => b.cost - a.cost),
-      totalCost: records.reduce((sum, record) => sum + record.cost_usd, 0),
-      totalTokens: records.reduce((sum, record) => sum + record.tokens_total, 0)
-    };
-  }, [records]);
+  // Totals are summed by the backend over every row, not a capped list.
+  const aggregates = useMemo(
+    () => ({
+      timeline: (summary?.days ?? []).map((day) => ({
+        date: day.date,
+        total: day.cost_usd,
+        agents: Object.fromEntries(day.agents.map((agent) => [agent.agent, agent.cost_usd]))
+      })),
+      breakdown: (summary?.agents ?? []).map((agent) => ({ agent: agent.agent, cost: agent.cost_usd })),
+      totalCost: summary?.cost_usd ?? 0,
+      totalTokens: summary?.tokens_total ?? 0
+    }),
+    [summary]
+  );
 
   return {
-    records,
+    recordCount: summary?.records ?? 0,
     loading,
     error,
     budget,
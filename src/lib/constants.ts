@@ -1,4 +1,4 @@
-import type { ShellConfig } from './types';
+import type { AgentType, ShellConfig } from './types';
 
 export const APP_NAME = 'Aion';
 
@@ -47,7 +47,39 @@ export const DEFAULT_SHELL_CONFIG: ShellConfig = {
   customPaths: {},
   defaultEnv: {},
   loginShell: true,
-  profileLoad: true
+  profileLoad: true,
+  terminal: {
+    term: 'xterm-256color',
+    colorterm: 'truecolor',
+    termProgram: true,
+    locale: null,
+    bundledTerminfo: false
+  },
+  logging: {
+    enabled: false,
+    dir: null,
+    raw: false,
+    maxBytes: 10 * 1024 * 1024,
+    maxPartHours: 24,
+    maxAgeDays: 30,
+    compress: true
+  },
+  editor: {
+    command: null,
+    args: [],
+    terminal: null
+  },
+  scraper: {
+    rules: []
+  }
+};
+
+export const AGENT_LABELS: Record<Exclude<AgentType, null>, string> = {
+  'claude-code': 'Claude Code',
+  'codex-cli': 'Codex CLI',
+  aider: 'Aider',
+  'gemini-cli': 'Gemini CLI',
+  'copilot-cli': 'Copilot CLI'
 };
 
 export const DEFAULT_BUDGET_LIMIT_USD = 100;
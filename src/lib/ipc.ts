@@ -3,24 +3,58 @@ import { listen, type UnlistenFn } from '@tauri-apps/api/event';
 import type {
   BranchList,
   BudgetSummary,
+  CheckpointInfo,
   CherryPickResult,
   CommitEntry,
   CommitInfo,
+  Diagnostic,
+  EditorLaunch,
   DiffResult,
+  FileDiff,
   FileStatusEntry,
   FetchResult,
+  ImportSourceStatus,
   MergeResult,
+  ModelPrice,
+  PathLink,
+  PtyBellEvent,
   PtyDataEvent,
+  PtyDiagnosticsEvent,
   PtyExitEvent,
+  PtyNotifyEvent,
+  PtyPortEvent,
+  PtyTitleEvent,
   PullResult,
   PushResult,
+  RecomputeSummary,
+  ResourceThresholdEvent,
+  ResourceUsage,
+  SandboxProfile,
+  ScrapeRule,
+  ScrapeRuleError,
+  ScrapeRuleTestResult,
+  ScrollbackPage,
+  SessionAnnotations,
+  SessionBookmark,
+  SessionChanges,
+  SessionLimits,
+  SessionInfo,
+  SessionOverlapEvent,
   ShellConfig,
   ShellInfo,
   StashAction,
   StashResult,
+  TagCount,
   TagResult,
+  TestRunRecord,
   TokenCapturedEvent,
-  UsageRecord
+  TranscriptFormat,
+  UsageImportedEvent,
+  UsageRecord,
+  UsageSource,
+  UsageSummary,
+  WorktreeRecord,
+  WorktreeStatus
 } from './types';
 
 export async function ptySpawn(params: {
@@ -30,6 +64,12 @@ export async function ptySpawn(params: {
   env?: Record<string, string>;
   cols: number;
   rows: number;
+  worktree?: boolean;
+  checkpointIntervalS?: number;
+  sandbox?: SandboxProfile;
+  limits?: SessionLimits;
+  encoding?: string;
+  logOutput?: boolean;
 }) {
   return invoke('pty_spawn', {
     sessionId: params.sessionId,
@@ -37,7 +77,13 @@ export async function ptySpawn(params: {
     cwd: params.cwd,
     env: params.env,
     cols: params.cols,
-    rows: params.rows
+    rows: params.rows,
+    worktree: params.worktree,
+    checkpointIntervalS: params.checkpointIntervalS,
+    sandbox: params.sandbox,
+    limits: params.limits,
+    encoding: params.encoding,
+    logOutput: params.logOutput
   });
 }
 
@@ -49,12 +95,80 @@ export async function ptyResize(sessionId: string, cols: number, rows: number) {
   return invoke('pty_resize', { sessionId, cols, rows });
 }
 
+export async function ptySetEncoding(sessionId: string, encoding: string) {
+  return invoke('pty_set_encoding', { sessionId, encoding }) as Promise<SessionInfo>;
+}
+
 export async function ptyKill(sessionId: string) {
   return invoke('pty_kill', { sessionId });
 }
 
-export async function ptyList() {
-  return invoke('pty_list') as Promise<Array<Record<string, unknown>>>;
+export async function ptyList(tag?: string) {
+  return invoke('pty_list', { tag }) as Promise<SessionInfo[]>;
+}
+
+export async function ptyScrollback(sessionId: string, startLine?: number, count?: number) {
+  return invoke('pty_scrollback', { sessionId, startLine, count }) as Promise<ScrollbackPage>;
+}
+
+export async function ptyResourceUsage(sessionId: string) {
+  return invoke('pty_resource_usage', { sessionId }) as Promise<ResourceUsage>;
+}
+
+export async function ptyDiagnostics(sessionId: string) {
+  return invoke('pty_diagnostics', { sessionId }) as Promise<Diagnostic[]>;
+}
+
+export async function resolvePathLink(sessionId: string, text: string) {
+  return invoke('resolve_path_link', { sessionId, text }) as Promise<PathLink[]>;
+}
+
+export async function openInEditor(path: string, line?: number, col?: number) {
+  return invoke('open_in_editor', { path, line, col }) as Promise<EditorLaunch>;
+}
+
+export async function ptyLogPath(sessionId: string) {
+  return invoke('pty_log_path', { sessionId }) as Promise<string | null>;
+}
+
+export async function ptyExportTranscript(sessionId: string, format: TranscriptFormat) {
+  return invoke('pty_export_transcript', { sessionId, format }) as Promise<string>;
+}
+
+export async function sessionAnnotations(sessionId: string) {
+  return invoke('session_annotations', { sessionId }) as Promise<SessionAnnotations>;
+}
+
+export async function sessionNotesSet(sessionId: string, notes: string) {
+  return invoke('session_notes_set', { sessionId, notes });
+}
+
+export async function sessionTagAdd(sessionId: string, tag: string) {
+  return invoke('session_tag_add', { sessionId, tag }) as Promise<string[]>;
+}
+
+export async function sessionTagRemove(sessionId: string, tag: string) {
+  return invoke('session_tag_remove', { sessionId, tag }) as Promise<string[]>;
+}
+
+export async function sessionTagsList() {
+  return invoke('session_tags_list') as Promise<TagCount[]>;
+}
+
+export async function sessionBookmarkAdd(sessionId: string, label?: string, line?: number) {
+  return invoke('session_bookmark_add', { sessionId, label, line }) as Promise<SessionBookmark>;
+}
+
+export async function sessionBookmarkUpdate(id: number, label: string) {
+  return invoke('session_bookmark_update', { id, label }) as Promise<SessionBookmark>;
+}
+
+export async function sessionBookmarkRemove(id: number) {
+  return invoke('session_bookmark_remove', { id });
+}
+
+export async function sessionBookmarkJump(id: number, count?: number) {
+  return invoke('session_bookmark_jump', { id, count }) as Promise<ScrollbackPage>;
 }
 
 export async function gitStatus(path: string) {
@@ -133,20 +247,130 @@ export async function gitStash(path: string, action: StashAction, message?: stri
   return invoke('git_stash', { path, action, message, index }) as Promise<StashResult>;
 }
 
+export async function gitCheckpointCreate(path: string, sessionId: string, label?: string) {
+  return invoke('git_checkpoint_create', { path, sessionId, label }) as Promise<CheckpointInfo | null>;
+}
+
+export async function gitCheckpointList(path: string, sessionId: string) {
+  return invoke('git_checkpoint_list', { path, sessionId }) as Promise<CheckpointInfo[]>;
+}
+
+export async function gitCheckpointDiff(path: string, sessionId: string, index: number) {
+  return invoke('git_checkpoint_diff', { path, sessionId, index }) as Promise<FileDiff[]>;
+}
+
+export async function gitCheckpointRestore(path: string, sessionId: string, index: number) {
+  return invoke('git_checkpoint_restore', { path, sessionId, index }) as Promise<CheckpointInfo | null>;
+}
+
+export async function sessionChanges(sessionId: string) {
+  return invoke('session_changes', { sessionId }) as Promise<SessionChanges>;
+}
+
+export async function worktreeList(repoPath?: string, status?: WorktreeStatus) {
+  return invoke('worktree_list', { repoPath, status }) as Promise<WorktreeRecord[]>;
+}
+
+export async function worktreeMerge(sessionId: string, noFf = false) {
+  return invoke('worktree_merge', { sessionId, noFf }) as Promise<MergeResult>;
+}
+
+export async function worktreeKeep(sessionId: string) {
+  return invoke('worktree_keep', { sessionId });
+}
+
+export async function worktreeRemove(sessionId: string, deleteBranch = false) {
+  return invoke('worktree_remove', { sessionId, deleteBranch });
+}
+
 export async function queryUsage(params: {
   from?: string;
   to?: string;
   agent?: string;
   sessionId?: string;
+  tag?: string;
+  model?: string;
+  source?: UsageSource;
+  project?: string;
 }) {
   return invoke('query_usage', {
     from: params.from,
     to: params.to,
     agent: params.agent,
-    sessionId: params.sessionId
+    sessionId: params.sessionId,
+    tag: params.tag,
+    model: params.model,
+    source: params.source,
+    project: params.project
   }) as Promise<UsageRecord[]>;
 }
 
+export async function queryUsageSummary(params: {
+  from?: string;
+  to?: string;
+  agent?: string;
+  sessionId?: string;
+  tag?: string;
+  model?: string;
+  source?: UsageSource;
+  project?: string;
+}) {
+  return invoke('query_usage_summary', {
+    from: params.from,
+    to: params.to,
+    agent: params.agent,
+    sessionId: params.sessionId,
+    tag: params.tag,
+    model: params.model,
+    source: params.source,
+    project: params.project
+  }) as Promise<UsageSummary>;
+}
+
+export async function importStatus() {
+  return invoke('import_status') as Promise<ImportSourceStatus[]>;
+}
+
+export async function scraperRuleErrors() {
+  return invoke('scraper_rule_errors') as Promise<ScrapeRuleError[]>;
+}
+
+export async function scraperTestRule(rule: ScrapeRule, sampleText: string) {
+  return invoke('scraper_test_rule', { rule, sampleText }) as Promise<ScrapeRuleTestResult>;
+}
+
+export async function queryTestRuns(params: {
+  project?: string;
+  sessionId?: string;
+  limit?: number;
+}) {
+  return invoke('query_test_runs', {
+    project: params.project,
+    sessionId: params.sessionId,
+    limit: params.limit
+  }) as Promise<TestRunRecord[]>;
+}
+
+export async function pricingList() {
+  return invoke('pricing_list') as Promise<ModelPrice[]>;
+}
+
+export async function pricingSet(price: ModelPrice) {
+  return invoke('pricing_set', { price }) as Promise<ModelPrice[]>;
+}
+
+export async function pricingRemove(id: number) {
+  return invoke('pricing_remove', { id });
+}
+
+export async function usageRecomputeCosts() {
+  return invoke('usage_recompute_costs') as Promise<RecomputeSummary>;
+}
+
+export async function usageRepairCumulative() {
+  return invoke('usage_repair_cumulative') as Promise<number>;
+}
+
 export async function queryBudget(month: string) {
   return invoke('query_budget', { month }) as Promise<BudgetSummary>;
 }
@@ -183,6 +407,92 @@ export async function onGitChanged(handler: () => void): Promise<UnlistenFn> {
   return listen('git:changed', () => handler());
 }
 
+export async function onSessionOverlap(
+  handler: (payload: SessionOverlapEvent) => void
+): Promise<UnlistenFn> {
+  return listen<SessionOverlapEvent>('session:overlap', (event) => {
+    handler(event.payload);
+  });
+}
+
+export async function onCheckpointCreated(
+  handler: (payload: CheckpointInfo) => void
+): Promise<UnlistenFn> {
+  return listen<CheckpointInfo>('checkpoint:created', (event) => {
+    handler(event.payload);
+  });
+}
+
+export async function onWorktreeEnded(
+  handler: (payload: WorktreeRecord) => void
+): Promise<UnlistenFn> {
+  return listen<WorktreeRecord>('worktree:ended', (event) => {
+    handler(event.payload);
+  });
+}
+
+export async function onPtyResourceThreshold(
+  handler: (payload: ResourceThresholdEvent) => void
+): Promise<UnlistenFn> {
+  return listen<ResourceThresholdEvent>('pty:resource_threshold', (event) => {
+    handler(event.payload);
+  });
+}
+
+export async function onPtyPortOpened(
+  handler: (payload: PtyPortEvent) => void
+): Promise<UnlistenFn> {
+  return listen<PtyPortEvent>('pty:port_opened', (event) => {
+    handler(event.payload);
+  });
+}
+
+export async function onPtyPortClosed(
+  handler: (payload: PtyPortEvent) => void
+): Promise<UnlistenFn> {
+  return listen<PtyPortEvent>('pty:port_closed', (event) => {
+    handler(event.payload);
+  });
+}
+
+export async function onPtyTitleChanged(
+  handler: (payload: PtyTitleEvent) => void
+): Promise<UnlistenFn> {
+  return listen<PtyTitleEvent>('pty:title_changed', (event) => {
+    handler(event.payload);
+  });
+}
+
+export async function onPtyBell(handler: (payload: PtyBellEvent) => void): Promise<UnlistenFn> {
+  return listen<PtyBellEvent>('pty:bell', (event) => {
+    handler(event.payload);
+  });
+}
+
+export async function onPtyNotify(
+  handler: (payload: PtyNotifyEvent) => void
+): Promise<UnlistenFn> {
+  return listen<PtyNotifyEvent>('pty:notify', (event) => {
+    handler(event.payload);
+  });
+}
+
+export async function onPtyDiagnostics(
+  handler: (payload: PtyDiagnosticsEvent) => void
+): Promise<UnlistenFn> {
+  return listen<PtyDiagnosticsEvent>('pty:diagnostics', (event) => {
+    handler(event.payload);
+  });
+}
+
+export async function onTestRunRecorded(
+  handler: (payload: TestRunRecord) => void
+): Promise<UnlistenFn> {
+  return listen<TestRunRecord>('test:run_recorded', (event) => {
+    handler(event.payload);
+  });
+}
+
 export async function onTokenCaptured(
   handler: (payload: TokenCapturedEvent) => void
 ): Promise<UnlistenFn> {
@@ -190,3 +500,11 @@ export async function onTokenCaptured(
     handler(event.payload);
   });
 }
+
+export async function onUsageImported(
+  handler: (payload: UsageImportedEvent) => void
+): Promise<UnlistenFn> {
+  return listen<UsageImportedEvent>('usage:imported', (event) => {
+    handler(event.payload);
+  });
+}
@@ -1,6 +1,12 @@
 export type ShellType = 'zsh' | 'bash' | 'fish' | 'pwsh' | 'powershell' | 'cmd' | 'custom';
 
-export type AgentType = 'claude-code' | 'copilot-cli' | null;
+export type AgentType =
+  | 'claude-code'
+  | 'codex-cli'
+  | 'aider'
+  | 'gemini-cli'
+  | 'copilot-cli'
+  | null;
 
 export type SessionStatus = 'running' | 'idle' | 'terminated';
 
@@ -21,21 +27,189 @@ export interface SessionInfo {
   cwd: string;
   pid: number;
   started_at: string;
+  worktree: WorktreeInfo | null;
+  sandbox: SandboxInfo | null;
+  limits: SessionLimits | null;
+  ports: ListeningPort[];
+  encoding: string;
+  title: string | null;
+  log_path: string | null;
+}
+
+export interface ListeningPort {
+  port: number;
+  address: string;
+  protocol: 'tcp' | 'tcp6';
+  pid: number;
+}
+
+export interface PtyPortEvent extends ListeningPort {
+  session_id: string;
+}
+
+export interface SessionLimits {
+  maxRuntimeS?: number;
+  idleTimeoutS?: number;
+  maxOutputBytes?: number;
+  cpuSeconds?: number;
+  addressSpaceMb?: number;
+  openFiles?: number;
+  cpuAlertPct?: number;
+  rssAlertMb?: number;
+}
+
+export type SessionLimitKind = 'max_runtime' | 'idle_timeout' | 'max_output_bytes';
+
+export interface SandboxProfile {
+  network?: boolean;
+  writablePaths?: string[];
+  backend?: 'bwrap' | 'unshare';
+}
+
+export interface SandboxInfo {
+  backend: string;
+  network: boolean;
+  writable_paths: string[];
+}
+
+export interface ResourceSample {
+  sampled_at: string;
+  processes: number;
+  cpu_pct: number;
+  rss_bytes: number;
+  read_bytes: number;
+  write_bytes: number;
+  read_bps: number;
+  write_bps: number;
+}
+
+export interface ResourceUsage {
+  session_id: string;
+  latest: ResourceSample | null;
+  samples: ResourceSample[];
+}
+
+export interface ResourceThresholdEvent {
+  session_id: string;
+  resource: 'cpu' | 'rss';
+  value: number;
+  threshold: number;
+}
+
+export type TranscriptFormat = 'markdown' | 'html';
+
+export interface ScrollbackPage {
+  session_id: string;
+  first_line: number;
+  start_line: number;
+  total_lines: number;
+  lines: string[];
+}
+
+export interface SessionBookmark {
+  id: number;
+  session_id: string;
+  label: string;
+  line: number;
+  created_at: string;
+}
+
+export interface SessionAnnotations {
+  session_id: string;
+  notes: string;
+  tags: string[];
+  bookmarks: SessionBookmark[];
+  updated_at: string | null;
+}
+
+export interface TagCount {
+  tag: string;
+  sessions: number;
+}
+
+export interface PtyTitleEvent {
+  session_id: string;
+  title: string;
+}
+
+export interface PtyBellEvent {
+  session_id: string;
+}
+
+export interface PtyNotifyEvent {
+  session_id: string;
+  title: string | null;
+  body: string;
+}
+
+export interface Diagnostic {
+  source: 'rustc' | 'tsc' | 'eslint' | 'gcc' | 'pytest' | 'go';
+  severity: 'error' | 'warning';
+  file: string;
+  line: number;
+  column: number | null;
+  message: string;
+  code: string | null;
+}
+
+export interface PtyDiagnosticsEvent {
+  session_id: string;
+  diagnostics: Diagnostic[];
 }
 
 export interface PtyDataEvent {
   session_id: string;
   data: number[];
+  /** Output read while terminal echo was off; never persist these chunks. */
+  secret: boolean;
 }
 
 export interface PtyExitEvent {
   session_id: string;
   code: number;
+  limit: SessionLimitKind | null;
+}
+
+export interface TestRunRecord {
+  id: number;
+  session_id: string;
+  project: string;
+  git_head: string | null;
+  runner: 'cargo' | 'jest' | 'vitest' | 'pytest' | 'go';
+  passed: number;
+  failed: number;
+  skipped: number;
+  failures: string[];
+  duration_s: number | null;
+  captured_at: string;
 }
 
 export interface TokenCapturedEvent {
   session_id: string;
   inserts: number;
+  /** A built-in agent, or the `agent` of a user-defined scraping rule. */
+  agent: string;
+}
+
+export interface UsageImportedEvent {
+  agent: string;
+  rows: number;
+}
+
+export interface ImportSourceStatus {
+  agent: string;
+  root: string | null;
+  found: boolean;
+  files: ImportFileStatus[];
+}
+
+export interface ImportFileStatus {
+  path: string;
+  position: number;
+  size: number | null;
+  scanned_at: string;
+  errors: number;
+  last_error: string | null;
 }
 
 export interface FileStatusEntry {
@@ -61,6 +235,46 @@ export interface DiffResult {
   hunks: DiffHunk[];
 }
 
+export interface FileDiff {
+  path: string;
+  status: string;
+  hunks: DiffHunk[];
+}
+
+export interface SessionChangedFile {
+  path: string;
+  changed: boolean;
+  ambiguous: boolean;
+  overlapping_sessions: string[];
+}
+
+export interface SessionChanges {
+  session_id: string;
+  repo_root: string;
+  baseline_tree: string;
+  started_at: string;
+  ended_at: string | null;
+  files: SessionChangedFile[];
+  diff: FileDiff[];
+}
+
+export interface SessionOverlapEvent {
+  path: string;
+  repo_root: string;
+  session_ids: string[];
+}
+
+export interface CheckpointInfo {
+  session_id: string;
+  index: number;
+  reference: string;
+  oid: string;
+  tree_oid: string;
+  head_oid: string | null;
+  label: string;
+  created_at: string;
+}
+
 export interface CommitInfo {
   oid: string;
   message: string;
@@ -128,6 +342,8 @@ export interface StashResult {
   detail: string;
 }
 
+export type UsageSource = 'scraped' | 'transcript' | 'hook' | 'otel';
+
 export interface UsageRecord {
   id: number;
   session_id: string;
@@ -139,6 +355,73 @@ export interface UsageRecord {
   duration_s: number | null;
   captured_at: string;
   raw_output: string;
+  estimated: boolean;
+  source: UsageSource;
+  model: string | null;
+  project: string | null;
+  cache_read_tokens: number | null;
+  cache_creation_tokens: number | null;
+  /** Session totals of a cumulative readout such as `/cost`; the counts
+   * above are the increase since the previous readout. */
+  cumulative_cost_usd: number | null;
+  cumulative_tokens_total: number | null;
+  /** Counts towards the summary totals under the same filters. */
+  counted: boolean;
+}
+
+export interface AgentUsage {
+  agent: string;
+  cost_usd: number;
+  tokens_total: number;
+}
+
+export interface UsageDay {
+  date: string;
+  cost_usd: number;
+  tokens_total: number;
+  agents: AgentUsage[];
+}
+
+export interface UsageSummary {
+  records: number;
+  cost_usd: number;
+  tokens_total: number;
+  /** Oldest first. */
+  days: UsageDay[];
+  /** Most expensive first. */
+  agents: AgentUsage[];
+}
+
+export interface ModelPrice {
+  id: number;
+  model: string;
+  input: number;
+  output: number;
+  cache_read: number;
+  cache_write: number;
+  effective_from: string;
+}
+
+export interface RecomputeSummary {
+  updated: number;
+  unpriced: number;
+}
+
+export interface WorktreeInfo {
+  name: string;
+  repo_path: string;
+  worktree_path: string;
+  branch: string;
+  base_oid: string;
+}
+
+export type WorktreeStatus = 'active' | 'ended' | 'merged' | 'kept' | 'removed';
+
+export interface WorktreeRecord extends WorktreeInfo {
+  session_id: string;
+  status: WorktreeStatus;
+  created_at: string;
+  ended_at: string | null;
 }
 
 export interface BudgetSummary {
@@ -161,6 +444,92 @@ export interface ShellConfig {
   defaultEnv: Record<string, string>;
   loginShell: boolean;
   profileLoad: boolean;
+  encoding?: string | null;
+  terminal?: TerminalIdentity;
+  logging?: LogConfig;
+  editor?: EditorConfig;
+  scraper?: ScraperConfig;
+}
+
+export interface EditorConfig {
+  command: string | null;
+  args: string[];
+  terminal: boolean | null;
+}
+
+export interface ScraperConfig {
+  rules: ScrapeRule[];
+}
+
+export interface ScrapeRule {
+  name: string;
+  agent: string;
+  detect: string | null;
+  /** Regexes with named captures: cost, tokens_in, tokens_out, tokens_total,
+   * cache_read_tokens, cache_creation_tokens, duration, model. */
+  patterns: string[];
+  require: string[];
+  completeOn: string | null;
+  sum: boolean;
+  /** Summaries are session totals; only the increase is counted. */
+  cumulative: boolean;
+}
+
+export interface ScrapeRuleError {
+  rule: string;
+  error: string;
+}
+
+export interface ParsedUsage {
+  cost_usd: number;
+  tokens_in: number;
+  tokens_out: number;
+  tokens_total: number;
+  duration_s: number | null;
+  cache_read_tokens: number | null;
+  cache_creation_tokens: number | null;
+  model: string | null;
+  raw_lines: string[];
+}
+
+export interface ScrapeRuleTestResult {
+  errors: string[];
+  detected: boolean;
+  usages: ParsedUsage[];
+}
+
+export interface EditorLaunch {
+  program: string;
+  args: string[];
+  terminal: boolean;
+  launched: boolean;
+}
+
+export interface PathLink {
+  text: string;
+  start: number;
+  end: number;
+  path: string;
+  line: number | null;
+  column: number | null;
+}
+
+export interface LogConfig {
+  enabled: boolean;
+  dir: string | null;
+  raw: boolean;
+  maxBytes: number;
+  maxPartHours: number;
+  maxAgeDays: number;
+  compress: boolean;
+}
+
+export interface TerminalIdentity {
+  term: string;
+  colorterm: string | null;
+  termProgram: boolean;
+  locale: string | null;
+  bundledTerminfo: boolean;
 }
 
 export interface ShellInfo {
@@ -1,4 +1,5 @@
 import { create } from 'zustand';
+import { AGENT_LABELS } from '@/lib/constants';
 import type { AgentType, Session, SessionStatus, ShellType } from '@/lib/types';
 
 interface SessionState {
@@ -137,7 +138,7 @@ export const useSessionStore = create<SessionState>((set, get) => ({
         }
 
         const cwdLabel = session.cwd.split('/').filter(Boolean).pop() ?? 'workspace';
-        const agentLabel = agent === 'claude-code' ? 'Claude Code' : 'Copilot CLI';
+        const agentLabel = AGENT_LABELS[agent];
         return { ...session, agent, label: `${agentLabel} — ${cwdLabel}` };
       })
     }));